@@ -0,0 +1,119 @@
+// Peak heap use of the hashing phase, and run time of the whole scan, for many unique
+// candidates with and without the bloom prefilter. Run with `cargo bench --bench bloom_prefilter`.
+use anyhow::Result;
+use rdupe::domain::{FileMetadata, HashAlgorithm, ScanConfig};
+use rdupe::ports::{FileSystemPort, HashingPort, ProgressPort};
+use rdupe::services::DuplicateFinderService;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime};
+
+const FILES: usize = 200_000;
+const SIZE_GROUPS: u64 = 16;
+
+// Tracks live and peak heap bytes
+struct CountingAllocator;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Measures the heap from the start of hashing to its end, leaving out the directory walk
+#[derive(Default)]
+struct HashingPhase {
+    baseline: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl ProgressPort for HashingPhase {
+    fn start(&self, _total: u64) {
+        let live = LIVE.load(Ordering::Relaxed);
+        self.baseline.store(live, Ordering::Relaxed);
+        PEAK.store(live, Ordering::Relaxed);
+    }
+
+    fn update(&self, _processed: u64) {}
+
+    fn finish(&self) {
+        let peak = PEAK.load(Ordering::Relaxed) - self.baseline.load(Ordering::Relaxed);
+        self.peak.fetch_max(peak, Ordering::Relaxed);
+    }
+}
+
+// Many files spread over a few sizes, so every one of them needs a partial hash
+struct SyntheticFiles;
+
+impl FileSystemPort for SyntheticFiles {
+    fn scan_files(&self, _config: &ScanConfig) -> Result<Vec<FileMetadata>> {
+        Ok((0..FILES)
+            .map(|i| {
+                let path = PathBuf::from(format!("/synthetic/dir{:03}/file{:07}.bin", i % 1000, i));
+                FileMetadata::new(path, 64 * 1024 + i as u64 % SIZE_GROUPS, SystemTime::UNIX_EPOCH)
+            })
+            .collect())
+    }
+}
+
+// Every file gets its own digest, so no candidate survives the partial hash
+struct UniqueHashes;
+
+impl HashingPort for UniqueHashes {
+    fn hash_file(&self, path: &Path, _skip: u64, algorithm: HashAlgorithm) -> Result<String> {
+        self.hash_bytes(path.as_os_str().as_encoded_bytes(), algorithm)
+    }
+
+    fn hash_partial(&self, path: &Path, skip: u64, _bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+        self.hash_file(path, skip, algorithm)
+    }
+
+    fn hash_bytes(&self, data: &[u8], _algorithm: HashAlgorithm) -> Result<String> {
+        Ok(format!("{:016x}{:064}", xxhash_rust::xxh3::xxh3_64(data), 0))
+    }
+}
+
+fn run(bloom_prefilter: bool) {
+    let mut config = ScanConfig::new().with_paths(vec![PathBuf::from("/synthetic")]);
+    config.bloom_prefilter = bloom_prefilter;
+    let phase = Arc::new(HashingPhase::default());
+    let service = DuplicateFinderService::new(SyntheticFiles, UniqueHashes, Arc::clone(&phase));
+
+    let start = Instant::now();
+    let results = service.find_duplicates(&config).expect("scan failed");
+    let elapsed = start.elapsed();
+    let peak = phase.peak.load(Ordering::Relaxed);
+
+    assert!(results.duplicates.is_empty());
+    println!(
+        "bloom {:<3}  {:>8.1} ms  hashing peak heap {:>6.1} MiB",
+        if bloom_prefilter { "on" } else { "off" },
+        elapsed.as_secs_f64() * 1000.0,
+        peak as f64 / (1024.0 * 1024.0)
+    );
+}
+
+fn main() {
+    println!("{} files in {} size groups, all partial hashes unique", FILES, SIZE_GROUPS);
+    for bloom_prefilter in [false, true, false, true] {
+        run(bloom_prefilter);
+    }
+}
@@ -0,0 +1,64 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+// How much of the file start is inspected to decide it is really text
+const TEXT_SAMPLE: usize = 8 * 1024;
+
+// Length of a leading byte-order mark to skip when comparing content, or 0. Only files whose
+// opening bytes decode as text in the BOM's encoding qualify, so a binary file that happens to
+// start with the same bytes is compared as-is.
+pub fn content_offset(path: &Path) -> u64 {
+    let mut sample = Vec::with_capacity(TEXT_SAMPLE);
+    let Ok(file) = File::open(path) else {
+        return 0;
+    };
+    if file.take(TEXT_SAMPLE as u64).read_to_end(&mut sample).is_err() {
+        return 0;
+    }
+
+    if let Some(text) = sample.strip_prefix(UTF8_BOM) {
+        if looks_like_utf8(text) {
+            return UTF8_BOM.len() as u64;
+        }
+    } else if let Some(text) = sample.strip_prefix(UTF16_LE_BOM) {
+        if looks_like_utf16(text, u16::from_le_bytes) {
+            return UTF16_LE_BOM.len() as u64;
+        }
+    } else if let Some(text) = sample.strip_prefix(UTF16_BE_BOM)
+        && looks_like_utf16(text, u16::from_be_bytes)
+    {
+        return UTF16_BE_BOM.len() as u64;
+    }
+    0
+}
+
+fn looks_like_utf8(text: &[u8]) -> bool {
+    if text.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(text) {
+        Ok(_) => true,
+        // The sample may end partway through a multi-byte character
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn looks_like_utf16(text: &[u8], decode: fn([u8; 2]) -> u16) -> bool {
+    let units = text.chunks_exact(2).map(|pair| decode([pair[0], pair[1]]));
+    let mut chars = char::decode_utf16(units).peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            Ok('\0') => return false,
+            Ok(_) => {}
+            // A lone surrogate is only acceptable as the cut-off final unit of the sample
+            Err(_) if chars.peek().is_none() => {}
+            Err(_) => return false,
+        }
+    }
+    true
+}
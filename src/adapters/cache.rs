@@ -1,62 +1,223 @@
 use crate::domain::{FileCache, FileMetadata, ScanConfig};
-use anyhow::Result;
-use std::fs;
-use std::path::Path;
-use std::time::SystemTime;
+use crate::ports::CachePort;
+use anyhow::{bail, Result};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-pub struct FileCacheAdapter;
+// Marks a bincode cache. The revision byte after it is bumped whenever `BinaryCache` or
+// `BinaryEntry` change shape, so an old binary cache is rejected rather than misread.
+const BINARY_MAGIC: &[u8] = b"RDUPECB";
+const BINARY_REVISION: u8 = 1;
+
+const DEFAULT_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+fn is_gzip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheFormat {
+    // Pretty-printed JSON, easy to inspect and diff
+    Json,
+    // bincode, several times smaller and faster to load on large trees
+    Binary,
+}
+
+impl CacheFormat {
+    // Used when no format is chosen explicitly: `.bin` files are binary, anything else JSON.
+    // A trailing `.gz` is compression and is looked past, so `cache.bin.gz` is binary.
+    pub fn from_path(path: &Path) -> Self {
+        let path = if is_gzip_path(path) { Path::new(path.file_stem().unwrap_or_default()) } else { path };
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bin")) {
+            CacheFormat::Binary
+        } else {
+            CacheFormat::Json
+        }
+    }
+}
+
+// bincode isn't self-describing, so it can't skip fields the way `FileMetadata`'s JSON form
+// does; these mirrors always write every field
+#[derive(Serialize, Deserialize)]
+struct BinaryEntry {
+    path: PathBuf,
+    size: u64,
+    partial_hash: Option<String>,
+    full_hash: Option<String>,
+    modified: SystemTime,
+    nlink: Option<u64>,
+    dev: Option<u64>,
+    ino: Option<u64>,
+    symlink_target: Option<PathBuf>,
+    bom_len: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BinaryCache {
+    version: String,
+    scan_config_hash: String,
+    last_scan: SystemTime,
+    dir_mtimes: HashMap<PathBuf, SystemTime>,
+    files: Vec<BinaryEntry>,
+}
+
+impl From<&FileMetadata> for BinaryEntry {
+    fn from(file: &FileMetadata) -> Self {
+        let FileMetadata { path, size, partial_hash, full_hash, modified, nlink, dev, ino, symlink_target, bom_len } =
+            file.clone();
+        Self { path, size, partial_hash, full_hash, modified, nlink, dev, ino, symlink_target, bom_len }
+    }
+}
+
+impl From<BinaryEntry> for FileMetadata {
+    fn from(entry: BinaryEntry) -> Self {
+        let BinaryEntry { path, size, partial_hash, full_hash, modified, nlink, dev, ino, symlink_target, bom_len } =
+            entry;
+        Self { path, size, partial_hash, full_hash, modified, nlink, dev, ino, symlink_target, bom_len }
+    }
+}
+
+pub struct FileCacheAdapter {
+    format: Option<CacheFormat>,
+}
+
+impl Default for FileCacheAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl FileCacheAdapter {
     pub fn new() -> Self {
-        Self
+        Self { format: None }
+    }
+
+    // Format used when saving; `None` picks it from the file extension. Loading always
+    // detects the format from the file itself, so either kind of cache can be read.
+    pub fn with_format(mut self, format: Option<CacheFormat>) -> Self {
+        self.format = format;
+        self
+    }
+
+    // Inflates gzip-compressed cache contents and passes anything else through unchanged
+    pub fn decompress(contents: Vec<u8>) -> Result<Vec<u8>> {
+        if !contents.starts_with(GZIP_MAGIC) {
+            return Ok(contents);
+        }
+        let mut inflated = Vec::new();
+        GzDecoder::new(contents.as_slice()).read_to_end(&mut inflated)?;
+        Ok(inflated)
+    }
+
+    pub fn is_binary_cache(contents: &[u8]) -> bool {
+        contents.starts_with(BINARY_MAGIC)
     }
 
-    pub fn load_cache(&self, cache_path: &Path) -> Result<Option<FileCache>> {
+    pub fn decode_binary(contents: &[u8]) -> Result<FileCache> {
+        if contents.get(BINARY_MAGIC.len()) != Some(&BINARY_REVISION) {
+            bail!("binary cache was written by an incompatible version of rdupe");
+        }
+        let cache: BinaryCache = bincode::deserialize(&contents[BINARY_MAGIC.len() + 1..])?;
+        Ok(FileCache {
+            files: cache.files.into_iter().map(FileMetadata::from).collect(),
+            scan_config_hash: cache.scan_config_hash,
+            last_scan: cache.last_scan,
+            version: cache.version,
+            dir_mtimes: cache.dir_mtimes,
+        })
+    }
+
+    fn encode_binary(cache: &FileCache) -> Result<Vec<u8>> {
+        let binary = BinaryCache {
+            version: cache.version.clone(),
+            scan_config_hash: cache.scan_config_hash.clone(),
+            last_scan: cache.last_scan,
+            dir_mtimes: cache.dir_mtimes.clone(),
+            files: cache.files.iter().map(BinaryEntry::from).collect(),
+        };
+        let mut contents = BINARY_MAGIC.to_vec();
+        contents.push(BINARY_REVISION);
+        bincode::serialize_into(&mut contents, &binary)?;
+        Ok(contents)
+    }
+}
+
+impl CachePort for FileCacheAdapter {
+    fn load_cache(&self, cache_path: &Path) -> Result<Option<FileCache>> {
         if !cache_path.exists() {
             return Ok(None);
         }
 
-        let contents = fs::read_to_string(cache_path)?;
-        let cache: FileCache = serde_json::from_str(&contents)?;
+        let contents = Self::decompress(fs::read(cache_path)?)?;
+        let cache = if Self::is_binary_cache(&contents) {
+            Self::decode_binary(&contents)?
+        } else {
+            serde_json::from_slice(&contents)?
+        };
         Ok(Some(cache))
     }
 
-    pub fn save_cache(&self, cache_path: &Path, cache: &FileCache) -> Result<()> {
+    fn save_cache(&self, cache_path: &Path, cache: &FileCache) -> Result<()> {
         if let Some(parent) = cache_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let contents = serde_json::to_string_pretty(cache)?;
-        fs::write(cache_path, contents)?;
+        let contents = match self.format.unwrap_or_else(|| CacheFormat::from_path(cache_path)) {
+            CacheFormat::Json => serde_json::to_vec_pretty(cache)?,
+            CacheFormat::Binary => Self::encode_binary(cache)?,
+        };
+        if is_gzip_path(cache_path) {
+            let mut encoder = GzEncoder::new(File::create(cache_path)?, Compression::default());
+            encoder.write_all(&contents)?;
+            encoder.finish()?;
+        } else {
+            fs::write(cache_path, contents)?;
+        }
         Ok(())
     }
 
-    pub fn is_cache_valid(&self, cache: &FileCache, config: &ScanConfig) -> bool {
+    fn is_cache_valid(&self, cache: &FileCache, config: &ScanConfig) -> bool {
         if cache.scan_config_hash != config.config_hash() {
             return false;
         }
 
-        if let Ok(elapsed) = SystemTime::now().duration_since(cache.last_scan) {
-            if elapsed.as_secs() > 24 * 60 * 60 {
-                return false;
-            }
+        let max_age = config.cache_max_age.unwrap_or(DEFAULT_CACHE_MAX_AGE);
+        if !max_age.is_zero()
+            && let Ok(elapsed) = SystemTime::now().duration_since(cache.last_scan)
+            && elapsed > max_age
+        {
+            return false;
         }
 
         if cache.version != env!("CARGO_PKG_VERSION") {
             return false;
         }
 
-        true
+        // Guards hash reuse against digests produced by a different algorithm
+        let algorithm = config.hash_algorithm;
+        cache.files.iter().all(|file| {
+            file.partial_hash.as_deref().is_none_or(|h| algorithm.is_valid_digest(h))
+                && file.full_hash.as_deref().is_none_or(|h| algorithm.is_valid_digest(h))
+        })
     }
 
-    pub fn filter_changed_files(&self, cached_files: &[FileMetadata]) -> Vec<FileMetadata> {
+    fn filter_changed_files(&self, cached_files: &[FileMetadata]) -> Vec<FileMetadata> {
         cached_files
             .iter()
             .filter(|file| {
-                if let Ok(metadata) = fs::metadata(&file.path) {
-                    if let Ok(modified) = metadata.modified() {
-                        return metadata.len() == file.size && modified == file.modified;
-                    }
+                if let Ok(metadata) = fs::metadata(&file.path)
+                    && let Ok(modified) = metadata.modified()
+                {
+                    return metadata.len() == file.size && modified == file.modified;
                 }
                 false
             })
@@ -64,12 +225,108 @@ impl FileCacheAdapter {
             .collect()
     }
 
-    pub fn create_cache(&self, files: Vec<FileMetadata>, config: &ScanConfig) -> FileCache {
+    // Directories whose mtime still matches the cache and whose cached subdirectories are all
+    // unchanged too, so the whole subtree can be taken from the cache without walking it.
+    fn trusted_directories(&self, dir_mtimes: &HashMap<PathBuf, SystemTime>) -> HashSet<PathBuf> {
+        let mut tainted: HashSet<&Path> = HashSet::new();
+        for (dir, cached_mtime) in dir_mtimes {
+            let unchanged = fs::metadata(dir)
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified == *cached_mtime);
+            if !unchanged {
+                tainted.extend(dir.ancestors());
+            }
+        }
+
+        dir_mtimes
+            .keys()
+            .filter(|dir| !tainted.contains(dir.as_path()))
+            .cloned()
+            .collect()
+    }
+
+    fn create_cache(&self, files: Vec<FileMetadata>, config: &ScanConfig) -> FileCache {
         FileCache {
             files,
             scan_config_hash: config.config_hash(),
             last_scan: SystemTime::now(),
             version: env!("CARGO_PKG_VERSION").to_string(),
+            dir_mtimes: HashMap::new(),
         }
     }
-}
\ No newline at end of file
+}
+// Cache that never has anything stored and discards whatever it is given
+pub struct NoOpCache;
+
+impl CachePort for NoOpCache {
+    fn load_cache(&self, _cache_path: &Path) -> Result<Option<FileCache>> {
+        Ok(None)
+    }
+
+    fn save_cache(&self, _cache_path: &Path, _cache: &FileCache) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_cache_valid(&self, _cache: &FileCache, _config: &ScanConfig) -> bool {
+        false
+    }
+
+    fn filter_changed_files(&self, _cached_files: &[FileMetadata]) -> Vec<FileMetadata> {
+        Vec::new()
+    }
+
+    fn create_cache(&self, files: Vec<FileMetadata>, config: &ScanConfig) -> FileCache {
+        FileCacheAdapter::new().create_cache(files, config)
+    }
+
+    fn trusted_directories(&self, _dir_mtimes: &HashMap<PathBuf, SystemTime>) -> HashSet<PathBuf> {
+        HashSet::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::HashAlgorithm;
+
+    fn cache_with(partial_hash: Option<&str>, full_hash: Option<&str>, config: &ScanConfig) -> FileCache {
+        let mut file = FileMetadata::new(PathBuf::from("a.txt"), 10, SystemTime::now());
+        file.partial_hash = partial_hash.map(str::to_string);
+        file.full_hash = full_hash.map(str::to_string);
+        FileCacheAdapter::new().create_cache(vec![file], config)
+    }
+
+    #[test]
+    fn digests_of_the_wrong_length_invalidate_the_cache() {
+        let mut config = ScanConfig::new();
+        config.hash_algorithm = HashAlgorithm::Sha256;
+        let adapter = FileCacheAdapter::new();
+        let sha256 = "ab".repeat(32);
+        let xxhash = "ab".repeat(8);
+
+        assert!(adapter.is_cache_valid(&cache_with(Some(&sha256), Some(&sha256), &config), &config));
+        assert!(adapter.is_cache_valid(&cache_with(None, None, &config), &config));
+        assert!(!adapter.is_cache_valid(&cache_with(None, Some(&xxhash), &config), &config));
+        assert!(!adapter.is_cache_valid(&cache_with(Some(&"ab".repeat(16)), Some(&sha256), &config), &config));
+    }
+
+    #[test]
+    fn aged_cache_is_rejected_past_the_configured_max_age() {
+        let mut config = ScanConfig::new();
+        let adapter = FileCacheAdapter::new();
+        let mut cache = cache_with(None, None, &config);
+        let day = Duration::from_secs(24 * 60 * 60);
+        cache.last_scan = SystemTime::now() - 2 * day;
+
+        // The default allows a day
+        assert!(!adapter.is_cache_valid(&cache, &config));
+        config.cache_max_age = Some(7 * day);
+        assert!(adapter.is_cache_valid(&cache, &config));
+        config.cache_max_age = Some(day);
+        assert!(!adapter.is_cache_valid(&cache, &config));
+        // Zero never expires
+        config.cache_max_age = Some(Duration::ZERO);
+        cache.last_scan = SystemTime::now() - 365 * day;
+        assert!(adapter.is_cache_valid(&cache, &config));
+    }
+}
@@ -0,0 +1,257 @@
+use crate::adapters::open_files::OpenFileLimiter;
+use crate::adapters::MultiAlgorithmHasher;
+use crate::domain::{Comparison, FileMetadata};
+use crate::ports::ComparisonPort;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+
+// Compares candidates directly instead of hashing them. Every file is read once, block by block
+// in lockstep, and a partition is split as soon as its members' blocks differ, so unequal files
+// usually stop being read after their first block.
+pub struct StreamingComparator {
+    block_size: usize,
+    open_files: Option<OpenFileLimiter>,
+}
+
+impl Default for StreamingComparator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingComparator {
+    pub fn new() -> Self {
+        Self {
+            block_size: DEFAULT_BLOCK_SIZE,
+            open_files: MultiAlgorithmHasher::default_max_open_files().map(OpenFileLimiter::new),
+        }
+    }
+
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    // Sets with more files than this are compared a batch at a time, so a huge set never runs
+    // the process out of file descriptors. `None` keeps the default from the descriptor limit.
+    pub fn with_max_open_files(mut self, max_open_files: Option<usize>) -> Self {
+        if let Some(max_open_files) = max_open_files {
+            self.open_files = Some(OpenFileLimiter::new(max_open_files));
+        }
+        self
+    }
+
+    // Opened at the file's content offset, past an ignored byte-order mark
+    fn open(file: &FileMetadata) -> io::Result<File> {
+        let mut opened = File::open(&file.path)?;
+        opened.seek(SeekFrom::Start(file.content_offset()))?;
+        Ok(opened)
+    }
+
+    // Splits `members` (indices into `candidates`) into subsets with identical content, all read
+    // in lockstep. Files that can't be opened or read are added to `failed` and left out.
+    fn partition(&self, candidates: &[&FileMetadata], members: &[usize], failed: &mut Vec<(usize, String)>) -> Vec<Vec<usize>> {
+        let _permit = self.open_files.as_ref().map(|limiter| limiter.acquire_many(members.len()));
+        let mut files: Vec<Option<File>> = Vec::with_capacity(members.len());
+        for &i in members {
+            match Self::open(candidates[i]) {
+                Ok(file) => files.push(Some(file)),
+                Err(e) => {
+                    failed.push((i, e.to_string()));
+                    files.push(None);
+                }
+            }
+        }
+        let mut blocks: Vec<Vec<u8>> = vec![Vec::new(); members.len()];
+
+        let mut pending: Vec<Vec<usize>> = vec![(0..members.len()).filter(|&m| files[m].is_some()).collect()];
+        let mut equal = Vec::new();
+
+        while let Some(partition) = pending.pop() {
+            let mut readable = Vec::with_capacity(partition.len());
+            for m in partition {
+                let Some(file) = files[m].as_mut() else {
+                    continue;
+                };
+                blocks[m].resize(self.block_size, 0);
+                match read_block(file, &mut blocks[m]) {
+                    Ok(len) => {
+                        blocks[m].truncate(len);
+                        readable.push(m);
+                    }
+                    Err(e) => {
+                        failed.push((members[m], e.to_string()));
+                        files[m] = None;
+                    }
+                }
+            }
+
+            // Candidates are few and blocks are compared directly, so a linear split is cheapest
+            let mut splits: Vec<Vec<usize>> = Vec::new();
+            for m in readable {
+                match splits.iter_mut().find(|split| blocks[split[0]] == blocks[m]) {
+                    Some(split) => split.push(m),
+                    None => splits.push(vec![m]),
+                }
+            }
+
+            for split in splits.into_iter().filter(|split| split.len() > 1) {
+                if blocks[split[0]].is_empty() {
+                    equal.push(split.into_iter().map(|m| members[m]).collect());
+                } else {
+                    pending.push(split);
+                }
+            }
+        }
+        equal
+    }
+}
+
+// Reads until `buf` is full or the file ends, returning how many bytes were read
+fn read_block(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+impl ComparisonPort for StreamingComparator {
+    fn are_equal(&self, candidates: &[&FileMetadata]) -> Result<Comparison> {
+        let mut failed = Vec::new();
+        let batch = self.open_files.as_ref().map_or(candidates.len(), OpenFileLimiter::capacity).max(2);
+
+        let mut equal = if candidates.len() <= batch {
+            let all: Vec<usize> = (0..candidates.len()).collect();
+            self.partition(candidates, &all, &mut failed)
+        } else {
+            // Too many files to open together: each round compares the first remaining file
+            // against the others a batch at a time, and whatever didn't match goes round again
+            let mut equal = Vec::new();
+            let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+            while remaining.len() > 1 {
+                let first = remaining[0];
+                let mut matched = vec![first];
+                let mut rest = Vec::new();
+                let mut batches = remaining[1..].chunks(batch - 1);
+                while let Some(others) = batches.next() {
+                    let members: Vec<usize> = std::iter::once(first).chain(others.iter().copied()).collect();
+                    let failed_before = failed.len();
+                    let subsets = self.partition(candidates, &members, &mut failed);
+                    let unreadable: HashSet<usize> = failed[failed_before..].iter().map(|(i, _)| *i).collect();
+                    let with_first = subsets.into_iter().find(|subset| subset.contains(&first)).unwrap_or_default();
+
+                    matched.extend(with_first.iter().copied().filter(|&i| i != first));
+                    rest.extend(others.iter().copied().filter(|i| !unreadable.contains(i) && !with_first.contains(i)));
+                    if unreadable.contains(&first) {
+                        // Nothing can be matched against an unreadable file, so its matches so
+                        // far and the batches not yet compared go round again
+                        rest.extend(matched.drain(1..));
+                        rest.extend(batches.flatten());
+                        matched.clear();
+                        break;
+                    }
+                }
+                if matched.len() > 1 {
+                    equal.push(matched);
+                }
+                remaining = rest;
+            }
+            equal
+        };
+
+        for subset in &mut equal {
+            subset.sort_unstable();
+        }
+        equal.sort_unstable();
+        failed.sort_unstable();
+        Ok(Comparison { equal, failed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+    use std::time::SystemTime;
+
+    fn write_files(dir: &Path, contents: &[&str]) -> Vec<FileMetadata> {
+        contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| {
+                let path = dir.join(format!("{}.txt", i));
+                fs::write(&path, content).unwrap();
+                FileMetadata::new(path, content.len() as u64, SystemTime::now())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn partitions_files_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = write_files(dir.path(), &["aaaa", "bbbb", "aaaa", "cccc", "bbbb", "aaaa"]);
+        let files: Vec<&FileMetadata> = files.iter().collect();
+
+        let comparison = StreamingComparator::new().with_block_size(2).are_equal(&files).unwrap();
+        assert_eq!(comparison.equal, vec![vec![0, 2, 5], vec![1, 4]]);
+        assert!(comparison.failed.is_empty());
+    }
+
+    #[test]
+    fn batched_comparison_matches_unbatched() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = write_files(dir.path(), &["aaaa", "bbbb", "aaaa", "cccc", "bbbb", "aaaa", "dddd", "cccc"]);
+        let files: Vec<&FileMetadata> = files.iter().collect();
+
+        let unbatched = StreamingComparator::new().are_equal(&files).unwrap();
+        for max_open in [2, 3, 5] {
+            let batched = StreamingComparator::new()
+                .with_max_open_files(Some(max_open))
+                .are_equal(&files)
+                .unwrap();
+            assert_eq!(batched, unbatched, "max_open_files = {}", max_open);
+        }
+        assert_eq!(unbatched.equal, vec![vec![0, 2, 5], vec![1, 4], vec![3, 7]]);
+    }
+
+    #[test]
+    fn unreadable_files_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut files = write_files(dir.path(), &["aaaa", "aaaa", "aaaa"]);
+        files.insert(0, FileMetadata::new(dir.path().join("missing.txt"), 4, SystemTime::now()));
+        let files: Vec<&FileMetadata> = files.iter().collect();
+
+        for max_open in [None, Some(2)] {
+            let comparison = StreamingComparator::new()
+                .with_max_open_files(max_open)
+                .are_equal(&files)
+                .unwrap();
+            assert_eq!(comparison.equal, vec![vec![1, 2, 3]]);
+            assert_eq!(comparison.failed.len(), 1);
+            assert_eq!(comparison.failed[0].0, 0);
+        }
+    }
+
+    #[test]
+    fn comparison_starts_at_each_files_content_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut files = write_files(dir.path(), &["\u{feff}same text", "same text", "\u{feff}other text"]);
+        // Only the recorded offset counts; the comparator doesn't look for a BOM itself
+        files[0] = files[0].clone().with_bom_len(3);
+        let files: Vec<&FileMetadata> = files.iter().collect();
+
+        let comparison = StreamingComparator::new().are_equal(&files).unwrap();
+        assert_eq!(comparison.equal, vec![vec![0, 1]]);
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Settings read from an `rdupe.toml`. Keys are the long flag names and every key is optional;
+// enum values use the same spellings as on the command line and are checked when merged.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigFile {
+    pub algorithm: Option<String>,
+    pub match_by: Option<String>,
+    pub sort_by: Option<String>,
+    pub keep: Option<String>,
+    pub min_size: Option<u64>,
+    pub skip_empty: Option<bool>,
+    pub max_size: Option<u64>,
+    pub min_group_size: Option<u64>,
+    pub min_nlink: Option<u64>,
+    pub max_nlink: Option<u64>,
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: Option<bool>,
+    pub cross_filesystem: Option<bool>,
+    pub link_only_same_fs: Option<bool>,
+    pub same_extension_only: Option<bool>,
+    pub ignore_bom: Option<bool>,
+    pub keep_hardlinks: Option<bool>,
+    pub verify: Option<bool>,
+    pub bloom: Option<bool>,
+    pub ignore: Option<Vec<String>>,
+    pub glob_exclude: Option<Vec<String>>,
+    pub no_ignore: Option<bool>,
+    pub hidden: Option<bool>,
+    pub ext: Option<Vec<String>>,
+    pub exclude_ext: Option<Vec<String>>,
+    pub include_type: Option<Vec<String>>,
+    pub prefer_keep_glob: Option<Vec<String>>,
+    pub threads: Option<usize>,
+    pub auto_threads: Option<bool>,
+    pub max_open_files: Option<usize>,
+    pub partial_hash_size: Option<u64>,
+    pub partial_mode: Option<String>,
+    pub mmap_threshold: Option<u64>,
+    pub compare_threshold: Option<usize>,
+    pub cache: Option<PathBuf>,
+    pub cache_max_age: Option<String>,
+    pub incremental: Option<bool>,
+    pub quiet: Option<bool>,
+    pub verbose_errors: Option<bool>,
+    pub ordered: Option<bool>,
+}
+
+pub struct ConfigFileAdapter;
+
+impl Default for ConfigFileAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigFileAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn load_config(&self, config_path: &Path) -> Result<ConfigFile> {
+        let contents = fs::read_to_string(config_path)
+            .with_context(|| format!("Failed to read config file '{}'", config_path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config file '{}'", config_path.display()))
+    }
+
+    // `$XDG_CONFIG_HOME/rdupe/rdupe.toml`, falling back to `~/.config` as the spec says.
+    // Only returned when the file exists, so a missing default config is not an error.
+    pub fn default_path(&self) -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .filter(|home| !home.is_empty())
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })?;
+        Some(config_home.join("rdupe").join("rdupe.toml")).filter(|path| path.is_file())
+    }
+}
@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Enough for every signature infer knows and for a Windows executable's PE header offset
+const SNIFF_LEN: usize = 8192;
+
+// Best-effort MIME type from the leading bytes of `path`, or None when nothing matches
+pub fn sniff(path: &Path) -> Option<&'static str> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    File::open(path).ok()?.take(SNIFF_LEN as u64).read_to_end(&mut header).ok()?;
+    detect(&header)
+}
+
+fn detect(header: &[u8]) -> Option<&'static str> {
+    match infer::get(header) {
+        Some(kind) if confirmed(header) => Some(kind.mime_type()),
+        _ => is_text(header).then_some("text/plain"),
+    }
+}
+
+// infer accepts BMP, Windows executables and LLVM bitcode on their first two bytes, which a
+// text file can start with too ("BM...", "MZ..."), so those also need the structure behind them
+fn confirmed(header: &[u8]) -> bool {
+    let u32_at = |offset: usize| {
+        header
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    match header.get(..2) {
+        // The DIB header that follows starts with its own size, one of a few fixed values
+        Some(b"BM") => u32_at(14).is_some_and(|size| matches!(size, 12 | 40 | 52 | 56 | 64 | 108 | 124)),
+        // The DOS stub points at the PE signature
+        Some(b"MZ") => u32_at(0x3C).is_some_and(|offset| {
+            header.get(offset as usize..offset as usize + 4) == Some(b"PE\0\0".as_slice())
+        }),
+        Some(b"BC") => header.get(2..4) == Some(b"\xC0\xDE".as_slice()),
+        _ => true,
+    }
+}
+
+// Text is UTF-8 (possibly cut mid-character at the end of the sample) or carries a UTF-16
+// byte-order mark, and has no control characters besides whitespace and escape
+fn is_text(header: &[u8]) -> bool {
+    if header.starts_with(b"\xFF\xFE") || header.starts_with(b"\xFE\xFF") {
+        return true;
+    }
+    let header = header.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(header);
+    if header.is_empty() {
+        return false;
+    }
+    let text = match std::str::from_utf8(header) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&header[..e.valid_up_to()]).unwrap(),
+        Err(_) => return false,
+    };
+    text.chars()
+        .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0C' | '\x1B'))
+}
+
+// Matches a MIME type against a pattern such as `image/jpeg` or `image/*`
+pub fn matches(mime: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(family) => mime.split('/').next() == Some(family),
+        None => mime.eq_ignore_ascii_case(pattern),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn detects_extensionless_files_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures: &[(&str, &[u8], Option<&str>)] = &[
+            ("photo", b"\xFF\xD8\xFF\xE0\x00\x10JFIF\x00", Some("image/jpeg")),
+            ("image", b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR", Some("image/png")),
+            ("document", b"%PDF-1.7\n", Some("application/pdf")),
+            ("archive", b"PK\x03\x04\x14\x00\x00\x00", Some("application/zip")),
+            ("notes", b"plain old notes\n", Some("text/plain")),
+            ("page", b"<!DOCTYPE html>\n<html></html>", Some("text/html")),
+            ("script", b"#!/bin/sh\necho hi\n", Some("text/x-shellscript")),
+            ("utf8", "caf\u{e9} cr\u{e8}me\n".as_bytes(), Some("text/plain")),
+            ("binary", b"\x00\x01\x02\x03\xFE\xFD", None),
+            ("empty", b"", None),
+        ];
+        for (name, contents, expected) in fixtures {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            assert_eq!(sniff(&path), *expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn two_byte_signatures_need_their_structure() {
+        // Text that merely starts with a short magic number is still text
+        assert_eq!(detect(b"BMW service record\n"), Some("text/plain"));
+        assert_eq!(detect(b"MZ notes\n"), Some("text/plain"));
+        assert_eq!(detect(b"BC dates\n"), Some("text/plain"));
+
+        let mut bmp = b"BM\x3A\x00\x00\x00\x00\x00\x00\x00\x36\x00\x00\x00".to_vec();
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&[0; 36]);
+        assert_eq!(detect(&bmp), Some("image/bmp"));
+
+        let mut exe = vec![0u8; 0x84];
+        exe[..2].copy_from_slice(b"MZ");
+        exe[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        exe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        assert_eq!(detect(&exe), Some("application/vnd.microsoft.portable-executable"));
+    }
+
+    #[test]
+    fn type_patterns_match_families() {
+        assert!(matches("image/jpeg", "image/*"));
+        assert!(matches("image/jpeg", "IMAGE/JPEG"));
+        assert!(matches("text/plain", "text/*"));
+        assert!(!matches("text/plain", "image/*"));
+    }
+}
@@ -1,77 +1,361 @@
-use crate::domain::{FileMetadata, ScanConfig};
-use crate::ports::FileSystemPort;
+use crate::adapters::{bom, content_type, platform};
+use crate::domain::{CancellationToken, DirectoryScan, FileMetadata, ScanConfig};
+use crate::ports::{FileSystemPort, ProgressPort};
 use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
 use ignore::WalkBuilder;
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-pub struct FileSystemAdapter;
+// How many files are found between scan progress reports
+const SCAN_PROGRESS_INTERVAL: u64 = 64;
+
+// None when there are no patterns, so the walk needn't resolve absolute paths at all
+fn compile_exclude_globs(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| anyhow::anyhow!("Invalid --glob-exclude '{}': {}", pattern, e))?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+// `-i` patterns use gitignore syntax, matched relative to the scanned root. Each is added
+// negated, which makes it an ignore rule; everything else is left to the other filters.
+fn compile_ignore_patterns<'a>(root: &Path, patterns: impl IntoIterator<Item = &'a String>) -> Result<Option<Override>> {
+    let mut builder = OverrideBuilder::new(root);
+    let mut any = false;
+    for pattern in patterns {
+        builder
+            .add(&format!("!{}", pattern))
+            .map_err(|e| anyhow::anyhow!("Invalid --ignore '{}': {}", pattern, e))?;
+        any = true;
+    }
+    Ok(if any { Some(builder.build()?) } else { None })
+}
+
+// The entry a walk error is about, when the walker knows it
+fn walk_error_path(error: &ignore::Error) -> Option<&Path> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => walk_error_path(err),
+        ignore::Error::Loop { child, .. } => Some(child),
+        _ => None,
+    }
+}
+
+pub struct FileSystemAdapter {
+    progress: Option<Box<dyn ProgressPort + Send + Sync>>,
+    cancellation: CancellationToken,
+}
+
+impl Default for FileSystemAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl FileSystemAdapter {
     pub fn new() -> Self {
-        Self
+        Self { progress: None, cancellation: CancellationToken::new() }
+    }
+
+    // Reports a running file count while directories are walked
+    pub fn with_progress(mut self, progress: impl ProgressPort + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    // Stops the walk once the token is cancelled, keeping the files found so far
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    fn report_skipped(&self, config: &ScanConfig, path: Option<&Path>, reason: impl std::fmt::Display) {
+        if config.verbose_errors
+            && let Some(progress) = &self.progress
+        {
+            progress.file_skipped(path, &reason.to_string());
+        }
+    }
+
+    fn file_found(&self, found: &AtomicU64) {
+        let count = found.fetch_add(1, Ordering::Relaxed) + 1;
+        if count.is_multiple_of(SCAN_PROGRESS_INTERVAL)
+            && let Some(progress) = &self.progress
+        {
+            progress.scan_progress(count);
+        }
     }
 }
 
 impl FileSystemPort for FileSystemAdapter {
     fn scan_files(&self, config: &ScanConfig) -> Result<Vec<FileMetadata>> {
-        let files: Result<Vec<FileMetadata>> = config
+        Ok(self.scan_files_skipping(config, &HashSet::new())?.files)
+    }
+
+    fn scan_files_skipping(&self, config: &ScanConfig, trusted_dirs: &HashSet<PathBuf>) -> Result<DirectoryScan> {
+        if let Some(progress) = &self.progress {
+            progress.scan_started();
+        }
+        let found = AtomicU64::new(0);
+        let exclude_globs = compile_exclude_globs(&config.exclude_globs)?;
+
+        let scans: Result<Vec<DirectoryScan>> = config
             .paths
             .par_iter()
-            .map(|path| -> Result<Vec<FileMetadata>> {
+            .map(|path| -> Result<DirectoryScan> {
                 let mut builder = WalkBuilder::new(path);
                 if let Some(max_depth) = config.max_depth {
                     builder.max_depth(Some(max_depth));
                 }
                 
                 builder.follow_links(config.follow_symlinks);
-                for pattern in &config.ignore_patterns {
-                    builder.add_ignore(&format!("{}\n", pattern));
+                // Set explicitly rather than relying on the walker's defaults
+                builder
+                    .hidden(!config.include_hidden)
+                    .git_ignore(config.respect_ignore_files)
+                    .git_global(config.respect_ignore_files)
+                    .git_exclude(config.respect_ignore_files)
+                    .ignore(config.respect_ignore_files)
+                    .parents(config.respect_ignore_files);
+                if let Some(overrides) = compile_ignore_patterns(path, &config.ignore_patterns)? {
+                    builder.overrides(overrides);
                 }
                 
                 if !config.cross_filesystem {
                     builder.same_file_system(true);
                 }
 
+                // Filtering entries here prunes whole directories instead of walking into them
+                if !trusted_dirs.is_empty() || exclude_globs.is_some() {
+                    let trusted_dirs = trusted_dirs.clone();
+                    let exclude_globs = exclude_globs.clone();
+                    builder.filter_entry(move |entry| {
+                        let trusted = entry.depth() > 0
+                            && entry.file_type().is_some_and(|t| t.is_dir())
+                            && trusted_dirs.contains(entry.path());
+                        let excluded = exclude_globs.as_ref().is_some_and(|globs| {
+                            std::path::absolute(entry.path()).is_ok_and(|path| globs.is_match(path))
+                        });
+                        !trusted && !excluded
+                    });
+                }
+
                 let walker = builder.build();
                 let root_dev = if !config.cross_filesystem {
-                    fs::metadata(path).ok().map(|m| m.dev())
+                    fs::metadata(path).ok().and_then(|m| platform::device_id(&m))
                 } else {
                     None
                 };
 
-                let entries: Vec<FileMetadata> = walker
+                let mut dir_mtimes = HashMap::new();
+                let mut skipped = Vec::new();
+                let files: Vec<FileMetadata> = walker
+                    .take_while(|_| !self.cancellation.is_cancelled())
                     .filter_map(|entry| {
-                        let entry = entry.ok()?;
+                        let entry = entry
+                            .inspect_err(|e| {
+                                let error_path = walk_error_path(e);
+                                self.report_skipped(config, error_path, e);
+                                skipped.push((error_path.unwrap_or(path).to_path_buf(), e.to_string()));
+                            })
+                            .ok()?;
                         let path = entry.path();
-                        if !path.is_file() {
+                        if config.trust_dir_mtimes && path.is_dir() {
+                            if let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) {
+                                dir_mtimes.insert(path.to_path_buf(), modified);
+                            }
                             return None;
                         }
 
-                        let metadata = fs::metadata(path).ok()?;
+                        if !path.is_file() || !config.extension_allowed(path) {
+                            return None;
+                        }
+
+                        // Files directly inside a trusted scan root are supplied from the cache
+                        if path.parent().is_some_and(|parent| trusted_dirs.contains(parent)) {
+                            return None;
+                        }
+
+                        let metadata = fs::metadata(path)
+                            .inspect_err(|e| {
+                                self.report_skipped(config, Some(path), e);
+                                skipped.push((path.to_path_buf(), e.to_string()));
+                            })
+                            .ok()?;
                         let size = metadata.len();
-                        if size < config.min_size {
+                        if size < config.min_size
+                            || config.max_size.is_some_and(|max| size > max)
+                            || (config.skip_empty && size == 0)
+                        {
+                            return None;
+                        }
+
+                        let (dev, ino, nlink) = (
+                            platform::device_id(&metadata),
+                            platform::inode(&metadata),
+                            platform::link_count(&metadata),
+                        );
+                        if let Some(nlink) = nlink
+                            && (config.min_nlink.is_some_and(|min| nlink < min)
+                                || config.max_nlink.is_some_and(|max| nlink > max))
+                        {
+                            return None;
+                        }
+
+                        // Cross-filesystem check, skipped where the device can't be identified
+                        if let (Some(root_dev), Some(dev)) = (root_dev, dev)
+                            && dev != root_dev
+                        {
                             return None;
                         }
 
-                        // Cross-filesystem check
-                        if let Some(root_dev) = root_dev {
-                            if metadata.dev() != root_dev {
+                        if let Ok(modified) = metadata.modified()
+                            && !config.modified_in_range(modified)
+                        {
+                            return None;
+                        }
+
+                        // Sniffed last so only files passing the cheap checks are opened
+                        if !config.include_types.is_empty() {
+                            let mime = content_type::sniff(path)?;
+                            if !config.include_types.iter().any(|pattern| content_type::matches(mime, pattern)) {
                                 return None;
                             }
                         }
 
                         let modified = metadata.modified().ok()?;
-                        Some(FileMetadata::new(path.to_path_buf(), size, modified))
+                        let mut file = FileMetadata::new(path.to_path_buf(), size, modified);
+                        (file.dev, file.ino, file.nlink) = (dev, ino, nlink);
+                        let file = match config.ignore_bom.then(|| bom::content_offset(path)) {
+                            Some(bom_len) if bom_len > 0 => file.with_bom_len(bom_len),
+                            _ => file,
+                        };
+                        // Covers both a linked file and a file under a followed directory link
+                        let file = match config.resolve_symlinks.then(|| fs::canonicalize(path)) {
+                            Some(Ok(real)) if std::path::absolute(path).is_ok_and(|p| p != real) => {
+                                file.with_symlink_target(real)
+                            }
+                            _ => file,
+                        };
+                        Some(file)
                     })
+                    .inspect(|_| self.file_found(&found))
                     .collect();
 
-                Ok(entries)
+                Ok(DirectoryScan { files, dir_mtimes, skipped })
             })
-            .collect::<Result<Vec<Vec<FileMetadata>>>>()
-            .map(|vecs| vecs.into_iter().flatten().collect());
+            .collect();
+
+        if let Some(progress) = &self.progress {
+            progress.scan_finished(found.load(Ordering::Relaxed));
+        }
+
+        let mut merged = DirectoryScan::default();
+        for scan in scans? {
+            merged.files.extend(scan.files);
+            merged.dir_mtimes.extend(scan.dir_mtimes);
+            merged.skipped.extend(scan.skipped);
+        }
+        Ok(merged)
+    }
+}
 
-        files
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scanned_names(config: &ScanConfig) -> Vec<String> {
+        let mut names: Vec<String> = FileSystemAdapter::new()
+            .scan_files(config)
+            .unwrap()
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn hidden_files_are_only_scanned_when_asked_for() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("visible.txt"), "same").unwrap();
+        fs::write(dir.path().join(".dotfile"), "same").unwrap();
+        fs::create_dir(dir.path().join(".cache")).unwrap();
+        fs::write(dir.path().join(".cache").join("inside.txt"), "same").unwrap();
+        let config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+
+        assert_eq!(scanned_names(&config), ["visible.txt"]);
+        let hidden = ScanConfig { include_hidden: true, ..config };
+        assert_eq!(scanned_names(&hidden), [".dotfile", "inside.txt", "visible.txt"]);
+    }
+
+    #[test]
+    fn ignore_patterns_drop_matching_files_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.tmp"), "same").unwrap();
+        fs::write(dir.path().join("b.txt"), "same").unwrap();
+        fs::create_dir(dir.path().join("build")).unwrap();
+        fs::write(dir.path().join("build").join("c.txt"), "same").unwrap();
+        let config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+
+        assert_eq!(scanned_names(&config), ["a.tmp", "b.txt", "c.txt"]);
+        let ignoring = ScanConfig { ignore_patterns: ["*.tmp".to_string(), "build/".to_string()].into(), ..config.clone() };
+        assert_eq!(scanned_names(&ignoring), ["b.txt"]);
+
+        let invalid = ScanConfig { ignore_patterns: ["a[".to_string()].into(), ..config };
+        assert!(FileSystemAdapter::new().scan_files(&invalid).is_err());
     }
-}
\ No newline at end of file
+
+    #[cfg(unix)]
+    #[test]
+    fn link_count_bounds_filter_hardlinked_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("single.txt"), "same").unwrap();
+        fs::write(dir.path().join("linked.txt"), "same").unwrap();
+        fs::hard_link(dir.path().join("linked.txt"), dir.path().join("linked-too.txt")).unwrap();
+        let config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+
+        assert_eq!(scanned_names(&config), ["linked-too.txt", "linked.txt", "single.txt"]);
+        assert_eq!(scanned_names(&ScanConfig { min_nlink: Some(2), ..config.clone() }), ["linked-too.txt", "linked.txt"]);
+        assert_eq!(scanned_names(&ScanConfig { max_nlink: Some(1), ..config }), ["single.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_symlink_records_its_target_in_another_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let (real, scanned) = (dir.path().join("real"), dir.path().join("scanned"));
+        fs::create_dir(&real).unwrap();
+        fs::create_dir(&scanned).unwrap();
+        fs::write(real.join("target.txt"), "contents").unwrap();
+        std::os::unix::fs::symlink(real.join("target.txt"), scanned.join("link.txt")).unwrap();
+        fs::write(scanned.join("plain.txt"), "contents").unwrap();
+
+        let mut config = ScanConfig::new().with_paths(vec![scanned.clone()]);
+        config.follow_symlinks = true;
+        let targets = |config: &ScanConfig| -> Vec<(String, Option<PathBuf>)> {
+            let mut files: Vec<_> = FileSystemAdapter::new()
+                .scan_files(config)
+                .unwrap()
+                .into_iter()
+                .map(|f| (f.path.file_name().unwrap().to_string_lossy().into_owned(), f.symlink_target))
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(targets(&config), [("link.txt".to_string(), None), ("plain.txt".to_string(), None)]);
+        config.resolve_symlinks = true;
+        let target = real.join("target.txt").canonicalize().unwrap();
+        assert_eq!(targets(&config), [("link.txt".to_string(), Some(target)), ("plain.txt".to_string(), None)]);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::domain::HistoryRecord;
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+pub struct HistoryAdapter;
+
+impl Default for HistoryAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HistoryAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn append_record(&self, history_path: &Path, record: &HistoryRecord) -> Result<()> {
+        if let Some(parent) = history_path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        // A single write of a whole line to a file opened with O_APPEND lands atomically,
+        // so concurrent runs sharing a history file never interleave records.
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history_path)
+            .with_context(|| format!("Failed to open history file '{}'", history_path.display()))?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_records(&self, history_path: &Path) -> Result<Vec<HistoryRecord>> {
+        let contents = fs::read_to_string(history_path)
+            .with_context(|| format!("Failed to read history file '{}'", history_path.display()))?;
+
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid history record on line {}", i + 1))
+            })
+            .collect()
+    }
+
+    pub fn format_trend(&self, records: &[HistoryRecord]) -> String {
+        let mut output = String::new();
+        output.push_str("=== Duplicate History ===\n");
+
+        let (Some(first), Some(last)) = (records.first(), records.last()) else {
+            output.push_str("No runs recorded yet.\n");
+            return output;
+        };
+
+        output.push_str(&format!("Runs recorded: {}\n", records.len()));
+        output.push_str(&format!("First run: {}\n", Self::format_timestamp(first)));
+        output.push_str(&format!("Latest run: {}\n\n", Self::format_timestamp(last)));
+
+        output.push_str(&format!(
+            "{:<22} {:>12} {:>8} {:>12} {:>14}\n",
+            "timestamp", "files", "groups", "duplicates", "wasted (MB)"
+        ));
+        for record in records {
+            output.push_str(&format!(
+                "{:<22} {:>12} {:>8} {:>12} {:>14.2}\n",
+                Self::format_timestamp(record),
+                record.total_files_scanned,
+                record.duplicate_groups,
+                record.duplicate_files,
+                record.wasted_space as f64 / 1_048_576.0
+            ));
+        }
+
+        let delta = last.wasted_space as i128 - first.wasted_space as i128;
+        let trend = match delta.signum() {
+            1 => "growing",
+            -1 => "shrinking",
+            _ => "stable",
+        };
+        output.push_str(&format!(
+            "\nWasted space change since first run: {}{:.2} MB ({})\n",
+            if delta >= 0 { "+" } else { "-" },
+            delta.unsigned_abs() as f64 / 1_048_576.0,
+            trend
+        ));
+
+        output
+    }
+
+    fn format_timestamp(record: &HistoryRecord) -> String {
+        match record.timestamp.duration_since(UNIX_EPOCH) {
+            Ok(_) => humantime::format_rfc3339_seconds(record.timestamp).to_string(),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn record(days: u64, wasted_mb: u64) -> HistoryRecord {
+        HistoryRecord {
+            timestamp: UNIX_EPOCH + Duration::from_secs(1_700_000_000 + days * 86_400),
+            total_files_scanned: 100,
+            total_size_scanned: 10 << 20,
+            duplicate_groups: 3,
+            duplicate_files: 4,
+            wasted_space: wasted_mb << 20,
+        }
+    }
+
+    #[test]
+    fn records_round_trip_and_show_a_trend() {
+        let dir = tempfile::tempdir().unwrap();
+        // The parent directory is created on the first append
+        let path = dir.path().join("nested").join("history.jsonl");
+        let adapter = HistoryAdapter::new();
+        adapter.append_record(&path, &record(0, 2)).unwrap();
+        adapter.append_record(&path, &record(1, 5)).unwrap();
+
+        let records = adapter.load_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timestamp, record(0, 2).timestamp);
+        assert_eq!(records[1].wasted_space, 5 << 20);
+
+        let trend = adapter.format_trend(&records);
+        assert!(trend.contains("Runs recorded: 2"), "{}", trend);
+        assert!(trend.contains("First run: 2023-11-14T22:13:20Z"), "{}", trend);
+        assert!(trend.contains("Wasted space change since first run: +3.00 MB (growing)"), "{}", trend);
+    }
+
+    #[test]
+    fn empty_history_says_so() {
+        let adapter = HistoryAdapter::new();
+        assert_eq!(adapter.format_trend(&[]), "=== Duplicate History ===\nNo runs recorded yet.\n");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        std::fs::write(&path, "\n").unwrap();
+        assert!(adapter.load_records(&path).unwrap().is_empty());
+        assert!(adapter.load_records(&dir.path().join("missing.jsonl")).is_err());
+    }
+}
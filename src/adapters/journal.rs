@@ -0,0 +1,221 @@
+use crate::adapters::{quarantine, trash};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalAction {
+    // Removed for good; the journal can only say what was lost
+    Deleted,
+    Trashed,
+    Quarantined,
+}
+
+// One file removed during an interactive or bulk deletion session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub original: PathBuf,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    pub action: JournalAction,
+    // Where a quarantined file now lives; the trash keeps track of trashed files itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<PathBuf>,
+}
+
+// What undo did, or in a dry run would do, with one journal entry
+#[derive(Debug, PartialEq, Eq)]
+pub enum UndoOutcome {
+    // `from` is the quarantine location, or None for the trash
+    Restored { original: PathBuf, from: Option<PathBuf> },
+    Failed { original: PathBuf, error: String },
+    // Permanently deleted, so only the record is left
+    Lost { original: PathBuf, size: u64 },
+}
+
+#[derive(Debug, Default)]
+pub struct UndoSummary {
+    pub restored: usize,
+    pub failed: usize,
+    pub lost: usize,
+    pub lost_bytes: u64,
+    // One per entry, in journal order
+    pub outcomes: Vec<UndoOutcome>,
+}
+
+pub struct JournalAdapter;
+
+impl Default for JournalAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JournalAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // A fresh journal path for this session under `$XDG_STATE_HOME/rdupe/journals` (or
+    // `~/.local/state`), named by start time. Nothing is created until the first entry is
+    // appended, so sessions that remove nothing leave no journal behind.
+    pub fn session_path(&self) -> Option<PathBuf> {
+        let state_home = std::env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .filter(|home| !home.is_empty())
+                    .map(|home| PathBuf::from(home).join(".local/state"))
+            })?;
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Some(
+            state_home
+                .join("rdupe")
+                .join("journals")
+                .join(format!("{}-{}.jsonl", started.as_secs(), std::process::id())),
+        )
+    }
+
+    // Entries are appended one line at a time as files are removed, so a session that is
+    // interrupted part way still has everything it removed on record
+    pub fn append_entry(&self, journal_path: &Path, entry: &JournalEntry) -> Result<()> {
+        if let Some(parent) = journal_path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(journal_path)
+            .with_context(|| format!("Failed to open undo journal '{}'", journal_path.display()))?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_entries(&self, journal_path: &Path) -> Result<Vec<JournalEntry>> {
+        let contents = fs::read_to_string(journal_path)
+            .with_context(|| format!("Failed to read undo journal '{}'", journal_path.display()))?;
+
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).with_context(|| format!("Invalid journal entry on line {}", i + 1))
+            })
+            .collect()
+    }
+
+    // Moves every trashed or quarantined file back to its recorded path and lists the
+    // permanently deleted ones. Files whose original path is taken again are left where they are.
+    pub fn undo(&self, journal_path: &Path, dry_run: bool) -> Result<UndoSummary> {
+        let mut summary = UndoSummary::default();
+        for entry in self.load_entries(journal_path)? {
+            let location = match (entry.action, &entry.location) {
+                (JournalAction::Trashed, _) => None,
+                (JournalAction::Quarantined, Some(location)) => Some(location),
+                (JournalAction::Deleted, _) | (JournalAction::Quarantined, None) => {
+                    summary.lost += 1;
+                    summary.lost_bytes += entry.size;
+                    summary.outcomes.push(UndoOutcome::Lost { original: entry.original, size: entry.size });
+                    continue;
+                }
+            };
+
+            let restored = if dry_run {
+                Ok(())
+            } else {
+                match location {
+                    Some(location) => quarantine::restore_from_quarantine(location, &entry.original),
+                    None => trash::restore_from_trash(&entry.original),
+                }
+            };
+            match restored {
+                Ok(()) => {
+                    summary.restored += 1;
+                    summary.outcomes.push(UndoOutcome::Restored { original: entry.original, from: location.cloned() });
+                }
+                Err(e) => {
+                    summary.failed += 1;
+                    summary.outcomes.push(UndoOutcome::Failed { original: entry.original, error: e.to_string() });
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Quarantines a file holding `contents` and journals the move, returning its original path
+    fn quarantined(root: &Path, journal: &Path, contents: &str) -> PathBuf {
+        let original = root.join("docs").join("a.txt");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, contents).unwrap();
+        let location = quarantine::move_to_quarantine(&original, &root.join("quarantine")).unwrap();
+        let entry = JournalEntry {
+            original: original.clone(),
+            size: contents.len() as u64,
+            hash: None,
+            action: JournalAction::Quarantined,
+            location: Some(location),
+        };
+        JournalAdapter::new().append_entry(journal, &entry).unwrap();
+        original
+    }
+
+    #[test]
+    fn quarantined_files_are_restored_to_their_original_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal.jsonl");
+        let original = quarantined(dir.path(), &journal, "kept");
+        let lost = JournalEntry { original: dir.path().join("gone.txt"), size: 4, hash: None, action: JournalAction::Deleted, location: None };
+        JournalAdapter::new().append_entry(&journal, &lost).unwrap();
+        assert!(!original.exists());
+
+        let summary = JournalAdapter::new().undo(&journal, false).unwrap();
+        assert_eq!((summary.restored, summary.failed, summary.lost, summary.lost_bytes), (1, 0, 1, 4));
+        assert_eq!(fs::read_to_string(&original).unwrap(), "kept");
+        assert!(matches!(&summary.outcomes[1], UndoOutcome::Lost { original, .. } if original.ends_with("gone.txt")));
+    }
+
+    #[test]
+    fn an_existing_file_at_the_original_path_is_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal.jsonl");
+        let original = quarantined(dir.path(), &journal, "kept");
+        fs::write(&original, "newer").unwrap();
+
+        let summary = JournalAdapter::new().undo(&journal, false).unwrap();
+        assert_eq!((summary.restored, summary.failed), (0, 1));
+        assert!(matches!(&summary.outcomes[0], UndoOutcome::Failed { .. }));
+        assert_eq!(fs::read_to_string(&original).unwrap(), "newer");
+    }
+
+    #[test]
+    fn dry_run_moves_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal.jsonl");
+        let original = quarantined(dir.path(), &journal, "kept");
+        let location = quarantine::quarantine_target(&original, &dir.path().join("quarantine")).unwrap();
+
+        let summary = JournalAdapter::new().undo(&journal, true).unwrap();
+        assert_eq!(summary.restored, 1);
+        assert_eq!(summary.outcomes, [UndoOutcome::Restored { original: original.clone(), from: Some(location.clone()) }]);
+        assert!(!original.exists());
+        assert!(location.exists());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::adapters::FileCacheAdapter;
+use crate::domain::{FileCache, FileMetadata, ManifestDiff};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// A manifest is either a bare JSON array of file entries or a --cache file holding them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ManifestFile {
+    Entries(Vec<FileMetadata>),
+    Cache(FileCache),
+}
+
+pub struct ManifestAdapter;
+
+impl Default for ManifestAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManifestAdapter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn load_manifest(&self, manifest_path: &Path) -> Result<Vec<FileMetadata>> {
+        let contents = fs::read(manifest_path)
+            .map_err(anyhow::Error::from)
+            .and_then(FileCacheAdapter::decompress)
+            .with_context(|| format!("Failed to read manifest '{}'", manifest_path.display()))?;
+        if FileCacheAdapter::is_binary_cache(&contents) {
+            let cache = FileCacheAdapter::decode_binary(&contents)
+                .with_context(|| format!("'{}' is not a readable binary cache file", manifest_path.display()))?;
+            return Ok(cache.files);
+        }
+        let manifest: ManifestFile = serde_json::from_slice(&contents)
+            .with_context(|| format!("'{}' is not a file list or cache file", manifest_path.display()))?;
+        Ok(match manifest {
+            ManifestFile::Entries(files) => files,
+            ManifestFile::Cache(cache) => cache.files,
+        })
+    }
+
+    pub fn format_diff(&self, diff: &ManifestDiff) -> String {
+        let mut output = String::new();
+        output.push_str("=== Manifest Diff ===\n");
+        output.push_str(&format!(
+            "Added: {}  Removed: {}  Changed: {}\n",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        ));
+
+        if diff.is_empty() {
+            output.push_str("Manifests are identical.\n");
+            return output;
+        }
+
+        if !diff.added.is_empty() {
+            output.push_str("\nAdded:\n");
+            for file in &diff.added {
+                output.push_str(&format!("  + {}\n", file.path.display()));
+            }
+        }
+        if !diff.removed.is_empty() {
+            output.push_str("\nRemoved:\n");
+            for file in &diff.removed {
+                output.push_str(&format!("  - {}\n", file.path.display()));
+            }
+        }
+        if !diff.changed.is_empty() {
+            output.push_str("\nChanged:\n");
+            for (_, after) in &diff.changed {
+                output.push_str(&format!("  ~ {}\n", after.path.display()));
+            }
+        }
+
+        output
+    }
+}
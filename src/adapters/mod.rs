@@ -1,11 +1,27 @@
+pub mod bom;
 pub mod cache;
+pub mod comparator;
+pub mod config;
+pub mod content_type;
 pub mod filesystem;
+pub mod history;
+pub mod journal;
+pub mod manifest;
 pub mod multi_hasher;
+mod open_files;
 pub mod output;
+pub mod platform;
 pub mod progress;
+pub mod quarantine;
+pub mod trash;
 
-pub use cache::FileCacheAdapter;
+pub use cache::{CacheFormat, FileCacheAdapter, NoOpCache};
+pub use comparator::StreamingComparator;
+pub use config::{ConfigFile, ConfigFileAdapter};
 pub use filesystem::FileSystemAdapter;
+pub use history::HistoryAdapter;
+pub use journal::{JournalAdapter, UndoOutcome};
+pub use manifest::ManifestAdapter;
 pub use multi_hasher::MultiAlgorithmHasher;
-pub use output::{ConsoleOutputAdapter, CsvOutputAdapter, InteractiveOutputAdapter, JsonOutputAdapter, TreeOutputAdapter};
-pub use progress::ProgressBarAdapter;
\ No newline at end of file
+pub use output::{ConfirmationLevel, ConsoleOutputAdapter, CsvOutputAdapter, DeleteErrorPolicy, DeletionMode, DeletionScriptAdapter, DirectoryOutputAdapter, FdupesOutputAdapter, HardlinkReportAdapter, InteractiveOutputAdapter, InteractiveTheme, JsonOutputAdapter, JsonPerGroupOutputAdapter, MarkdownOutputAdapter, NdjsonOutputAdapter, SavingsMode, SavingsOutputAdapter, SqliteOutputAdapter, SubtreeOutputAdapter, TierOutputAdapter, TreeOutputAdapter};
+pub use progress::{MachineProgressAdapter, ProgressBarAdapter};
\ No newline at end of file
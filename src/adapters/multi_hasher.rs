@@ -1,3 +1,4 @@
+use crate::adapters::open_files::{OpenFileLimiter, OpenFilePermit};
 use crate::domain::HashAlgorithm;
 use crate::ports::HashingPort;
 use anyhow::Result;
@@ -8,21 +9,98 @@ use sha1::Sha1;
 use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::hash::Hasher;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 use twox_hash::XxHash64 as TwoXHash64Hasher;
 use wyhash::wyhash;
 use xxhash_rust::xxh3::Xxh3;
 use xxhash_rust::xxh64::Xxh64;
 
+// Incremental digest for every supported algorithm, shared by the mmap, buffered, and
+// in-memory paths so they all produce identical hex output
+enum StreamingDigest {
+    XxHash64(Xxh64),
+    XxHash3(Box<Xxh3>),
+    // wyhash has no streaming API, so input is collected and hashed at the end. Only buffered
+    // reads, which stay below the mmap threshold, go through here; mapped files and in-memory
+    // data are hashed in one call by `digest_slice` without copying.
+    WyHash(Vec<u8>),
+    TwoXHash64(TwoXHash64Hasher),
+    Blake3(Box<Blake3Hasher>),
+    Sha256(Sha256),
+    Md5(md5::Context),
+    Sha1(Sha1),
+}
+
+impl StreamingDigest {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::XxHash64 => Self::XxHash64(Xxh64::new(0)),
+            HashAlgorithm::XxHash3 => Self::XxHash3(Box::new(Xxh3::new())),
+            HashAlgorithm::WyHash => Self::WyHash(Vec::new()),
+            HashAlgorithm::TwoXHash64 => Self::TwoXHash64(TwoXHash64Hasher::default()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::new(Blake3Hasher::new())),
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::Md5 => Self::Md5(md5::Context::new()),
+            HashAlgorithm::Sha1 => Self::Sha1(Sha1::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::XxHash64(hasher) => hasher.update(data),
+            Self::XxHash3(hasher) => hasher.update(data),
+            Self::WyHash(buffer) => buffer.extend_from_slice(data),
+            Self::TwoXHash64(hasher) => hasher.write(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Md5(hasher) => hasher.consume(data),
+            Self::Sha1(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finish(self) -> String {
+        match self {
+            Self::XxHash64(hasher) => format!("{:016x}", hasher.finish()),
+            Self::XxHash3(hasher) => format!("{:016x}", hasher.finish()),
+            Self::WyHash(buffer) => format!("{:016x}", wyhash(&buffer, 0)),
+            Self::TwoXHash64(hasher) => format!("{:016x}", hasher.finish()),
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            Self::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Self::Md5(hasher) => format!("{:x}", hasher.compute()),
+            Self::Sha1(hasher) => format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+// Digest of data that is already in memory, e.g. a mapped file
+fn digest_slice(data: &[u8], algorithm: HashAlgorithm) -> String {
+    if algorithm == HashAlgorithm::WyHash {
+        return format!("{:016x}", wyhash(data, 0));
+    }
+    let mut digest = StreamingDigest::new(algorithm);
+    digest.update(data);
+    digest.finish()
+}
+
 pub struct MultiAlgorithmHasher {
     mmap_threshold: u64,
+    open_files: Option<OpenFileLimiter>,
+}
+
+impl Default for MultiAlgorithmHasher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MultiAlgorithmHasher {
     pub fn new() -> Self {
         Self {
             mmap_threshold: 64 * 1024 * 1024,
+            open_files: Self::default_max_open_files().map(OpenFileLimiter::new),
         }
     }
 
@@ -31,125 +109,51 @@ impl MultiAlgorithmHasher {
         self
     }
 
-    fn hash_with_mmap(&self, path: &Path, limit: Option<u64>, algorithm: HashAlgorithm) -> Result<String> {
+    pub fn with_max_open_files(mut self, max_open_files: Option<usize>) -> Self {
+        if let Some(max_open_files) = max_open_files {
+            self.open_files = Some(OpenFileLimiter::new(max_open_files));
+        }
+        self
+    }
+
+    // Half of the soft descriptor limit, leaving room for stdio, the directory walker, and
+    // whatever else the process has open. Unbounded if the limit can't be read or is infinite.
+    pub fn default_max_open_files() -> Option<usize> {
+        let (soft, _) = rlimit::getrlimit(rlimit::Resource::NOFILE).ok()?;
+        if soft == rlimit::INFINITY {
+            return None;
+        }
+        Some((soft / 2).max(1) as usize)
+    }
+
+    fn acquire_open_file(&self) -> Option<OpenFilePermit<'_>> {
+        self.open_files.as_ref().map(|limiter| limiter.acquire())
+    }
+
+    fn hash_with_mmap(&self, path: &Path, skip: u64, limit: Option<u64>, algorithm: HashAlgorithm) -> Result<String> {
         let file = File::open(path)?;
         let mmap = unsafe { MmapOptions::new().map(&file)? };
+        let content = &mmap[(skip as usize).min(mmap.len())..];
         
         let data = match limit {
-            Some(bytes) => &mmap[..bytes.min(mmap.len() as u64) as usize],
-            None => &mmap[..],
-        };
-        
-        let hash = match algorithm {
-            HashAlgorithm::XxHash64 => {
-                let mut hasher = Xxh64::new(0);
-                hasher.update(data);
-                format!("{:x}", hasher.finish())
-            }
-            HashAlgorithm::XxHash3 => {
-                let mut hasher = Xxh3::new();
-                hasher.update(data);
-                format!("{:x}", hasher.finish())
-            }
-            HashAlgorithm::WyHash => {
-                let hash = wyhash(data, 0);
-                format!("{:x}", hash)
-            }
-            HashAlgorithm::TwoXHash64 => {
-                let mut hasher = TwoXHash64Hasher::default();
-                hasher.write(data);
-                format!("{:x}", hasher.finish())
-            }
-            HashAlgorithm::Blake3 => {
-                let mut hasher = Blake3Hasher::new();
-                hasher.update(data);
-                hasher.finalize().to_hex().to_string()
-            }
-            HashAlgorithm::Sha256 => {
-                let mut hasher = Sha256::new();
-                hasher.update(data);
-                format!("{:x}", hasher.finalize())
-            }
-            HashAlgorithm::Md5 => {
-                let mut hasher = md5::Context::new();
-                hasher.consume(data);
-                format!("{:x}", hasher.compute())
-            }
-            HashAlgorithm::Sha1 => {
-                let mut hasher = Sha1::new();
-                hasher.update(data);
-                format!("{:x}", hasher.finalize())
-            }
+            Some(bytes) => &content[..bytes.min(content.len() as u64) as usize],
+            None => content,
         };
-        
-        Ok(hash)
+        Ok(digest_slice(data, algorithm))
     }
 
-    fn hash_with_buffered_io(&self, path: &Path, limit: Option<u64>, algorithm: HashAlgorithm) -> Result<String> {
-        let file = File::open(path)?;
+    fn hash_with_buffered_io(&self, path: &Path, skip: u64, limit: Option<u64>, algorithm: HashAlgorithm) -> Result<String> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(skip))?;
         let mut reader = BufReader::new(file);
         let mut buffer = [0; 8192];
         let mut bytes_processed = 0u64;
 
-        match algorithm {
-            HashAlgorithm::XxHash64 => {
-                let mut hasher = Xxh64::new(0);
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    hasher.update(data);
-                })?;
-                Ok(format!("{:x}", hasher.finish()))
-            }
-            HashAlgorithm::XxHash3 => {
-                let mut hasher = Xxh3::new();
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    hasher.update(data);
-                })?;
-                Ok(format!("{:x}", hasher.finish()))
-            }
-            HashAlgorithm::WyHash => {
-                let mut data_vec = Vec::new();
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    data_vec.extend_from_slice(data);
-                })?;
-                let hash = wyhash(&data_vec, 0);
-                Ok(format!("{:x}", hash))
-            }
-            HashAlgorithm::TwoXHash64 => {
-                let mut hasher = TwoXHash64Hasher::default();
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    hasher.write(data);
-                })?;
-                Ok(format!("{:x}", hasher.finish()))
-            }
-            HashAlgorithm::Blake3 => {
-                let mut hasher = Blake3Hasher::new();
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    hasher.update(data);
-                })?;
-                Ok(hasher.finalize().to_hex().to_string())
-            }
-            HashAlgorithm::Sha256 => {
-                let mut hasher = Sha256::new();
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    hasher.update(data);
-                })?;
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-            HashAlgorithm::Md5 => {
-                let mut hasher = md5::Context::new();
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    hasher.consume(data);
-                })?;
-                Ok(format!("{:x}", hasher.compute()))
-            }
-            HashAlgorithm::Sha1 => {
-                let mut hasher = Sha1::new();
-                self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
-                    hasher.update(data);
-                })?;
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-        }
+        let mut digest = StreamingDigest::new(algorithm);
+        self.process_buffered_data(&mut reader, &mut buffer, limit, &mut bytes_processed, |data| {
+            digest.update(data);
+        })?;
+        Ok(digest.finish())
     }
 
     fn process_buffered_data<F>(&self, reader: &mut BufReader<File>, buffer: &mut [u8], limit: Option<u64>, bytes_processed: &mut u64, mut update_fn: F) -> Result<()>
@@ -179,23 +183,99 @@ impl MultiAlgorithmHasher {
 }
 
 impl HashingPort for MultiAlgorithmHasher {
-    fn hash_file(&self, path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    fn hash_bytes(&self, data: &[u8], algorithm: HashAlgorithm) -> Result<String> {
+        Ok(digest_slice(data, algorithm))
+    }
+
+    fn hash_file(&self, path: &Path, skip: u64, algorithm: HashAlgorithm) -> Result<String> {
+        let _permit = self.acquire_open_file();
         let file_size = std::fs::metadata(path)?.len();
         
         if file_size >= self.mmap_threshold {
-            self.hash_with_mmap(path, None, algorithm)
+            self.hash_with_mmap(path, skip, None, algorithm)
         } else {
-            self.hash_with_buffered_io(path, None, algorithm)
+            self.hash_with_buffered_io(path, skip, None, algorithm)
         }
     }
 
-    fn hash_partial(&self, path: &Path, bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+    fn hash_partial(&self, path: &Path, skip: u64, bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+        let _permit = self.acquire_open_file();
         let file_size = std::fs::metadata(path)?.len();
         
         if file_size >= self.mmap_threshold {
-            self.hash_with_mmap(path, Some(bytes), algorithm)
+            self.hash_with_mmap(path, skip, Some(bytes), algorithm)
         } else {
-            self.hash_with_buffered_io(path, Some(bytes), algorithm)
+            self.hash_with_buffered_io(path, skip, Some(bytes), algorithm)
+        }
+    }
+
+    fn hash_head_tail(&self, path: &Path, skip: u64, bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+        let content_size = std::fs::metadata(path)?.len().saturating_sub(skip);
+        if content_size < bytes.saturating_mul(2) {
+            return self.hash_partial(path, skip, bytes, algorithm);
         }
+
+        let _permit = self.acquire_open_file();
+        let mut file = File::open(path)?;
+        let mut buffer = vec![0; bytes as usize];
+        let mut digest = StreamingDigest::new(algorithm);
+        file.seek(SeekFrom::Start(skip))?;
+        file.read_exact(&mut buffer)?;
+        digest.update(&buffer);
+        file.seek(SeekFrom::End(-(bytes as i64)))?;
+        file.read_exact(&mut buffer)?;
+        digest.update(&buffer);
+        Ok(digest.finish())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN_DIGESTS: &[(HashAlgorithm, &str)] = &[
+        (HashAlgorithm::XxHash64, "44bc2cf5ad770999"),
+        (HashAlgorithm::XxHash3, "78af5f94892f3950"),
+        (HashAlgorithm::TwoXHash64, "44bc2cf5ad770999"),
+        (HashAlgorithm::Blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+        (HashAlgorithm::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+        (HashAlgorithm::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+    ];
+
+    #[test]
+    fn hash_bytes_gives_known_digests() {
+        let hasher = MultiAlgorithmHasher::new();
+        for &(algorithm, expected) in KNOWN_DIGESTS {
+            assert_eq!(hasher.hash_bytes(b"abc", algorithm).unwrap(), expected, "{}", algorithm.as_str());
+        }
+        assert_eq!(
+            hasher.hash_bytes(b"abc", HashAlgorithm::WyHash).unwrap(),
+            format!("{:016x}", wyhash(b"abc", 0))
+        );
+    }
+
+    #[test]
+    fn mapped_buffered_and_in_memory_digests_agree() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let buffered = MultiAlgorithmHasher::new();
+        let mapped = MultiAlgorithmHasher::new().with_mmap_threshold(0);
+        for algorithm in KNOWN_DIGESTS.iter().map(|&(a, _)| a).chain([HashAlgorithm::WyHash]) {
+            let expected = buffered.hash_bytes(&data, algorithm).unwrap();
+            assert_eq!(buffered.hash_file(&path, 0, algorithm).unwrap(), expected);
+            assert_eq!(mapped.hash_file(&path, 0, algorithm).unwrap(), expected);
+
+            let head = buffered.hash_bytes(&data[..4096], algorithm).unwrap();
+            assert_eq!(buffered.hash_partial(&path, 0, 4096, algorithm).unwrap(), head);
+            assert_eq!(mapped.hash_partial(&path, 0, 4096, algorithm).unwrap(), head);
+
+            let skipped = buffered.hash_bytes(&data[3..], algorithm).unwrap();
+            assert_eq!(buffered.hash_file(&path, 3, algorithm).unwrap(), skipped);
+            assert_eq!(mapped.hash_file(&path, 3, algorithm).unwrap(), skipped);
+        }
+    }
+}
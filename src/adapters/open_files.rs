@@ -0,0 +1,52 @@
+use std::sync::{Condvar, Mutex};
+
+// Counting semaphore bounding how many files an adapter has open at once
+pub(crate) struct OpenFileLimiter {
+    capacity: usize,
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+pub(crate) struct OpenFilePermit<'a> {
+    limiter: &'a OpenFileLimiter,
+    count: usize,
+}
+
+impl OpenFileLimiter {
+    pub(crate) fn new(max_open: usize) -> Self {
+        let capacity = max_open.max(1);
+        Self {
+            capacity,
+            available: Mutex::new(capacity),
+            released: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn acquire(&self) -> OpenFilePermit<'_> {
+        self.acquire_many(1)
+    }
+
+    // Takes `count` slots together (at most the capacity), so callers that need several files
+    // at once never hold some slots while waiting for the rest
+    pub(crate) fn acquire_many(&self, count: usize) -> OpenFilePermit<'_> {
+        let count = count.min(self.capacity);
+        let mut available = self.available.lock().unwrap_or_else(|e| e.into_inner());
+        while *available < count {
+            available = self.released.wait(available).unwrap_or_else(|e| e.into_inner());
+        }
+        *available -= count;
+        OpenFilePermit { limiter: self, count }
+    }
+}
+
+impl Drop for OpenFilePermit<'_> {
+    fn drop(&mut self) {
+        let mut available = self.limiter.available.lock().unwrap_or_else(|e| e.into_inner());
+        *available += self.count;
+        self.limiter.released.notify_all();
+    }
+}
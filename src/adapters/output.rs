@@ -1,25 +1,115 @@
-use crate::domain::ScanResult;
+use crate::adapters::journal::{JournalAction, JournalAdapter, JournalEntry};
+use crate::adapters::{platform, quarantine, trash};
+use crate::domain::{ChunkOverlap, DuplicateSet, FileMetadata, KeepPolicy, KeepStrategy, MatchMode, SafetyTier, ScanConfig, ScanResult, VerificationLevel, DEFAULT_RISKY_DIRS};
 use crate::ports::OutputPort;
 use anyhow::Result;
 use console::{style, Term};
-use dialoguer::{theme::ColorfulTheme, Confirm, MultiSelect, Select};
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+use dialoguer::{Confirm, MultiSelect, Select};
+use regex::Regex;
+use serde::Serialize;
 use serde_json;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Sizes in binary units (1 KB = 1024 bytes, as the MB figures elsewhere), with more decimals
+// for small values so e.g. 1.25 KB and 512.3 MB both stay readable
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{:.2} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// Shortened digest for display, clamped so short or non-hex group keys never panic
+fn truncate_hash(hash: &str, max_chars: usize) -> &str {
+    match hash.char_indices().nth(max_chars) {
+        Some((end, _)) => &hash[..end],
+        None => hash,
+    }
+}
+
+// Splits the groups into those whose files are known to hold the same bytes and those that
+// may not: matched by name, or by content past a byte-order mark. Deleting, moving or
+// replacing all but one copy is only safe for the first kind.
+fn split_by_byte_identity(results: &ScanResult) -> (Vec<&DuplicateSet>, Vec<&DuplicateSet>) {
+    results.groups().partition(|group| group.is_byte_identical())
+}
+
+const UNVERIFIED_SKIP_NOTE: &str = "whose files may differ byte for byte (matched by name, or by content past a byte-order mark)";
+
+// `path` as shown in reports: relative to `base` when given and the path lies under it,
+// otherwise absolute. `base` is expected to be absolute already.
+fn relative_path<'a>(path: &'a Path, base: Option<&Path>) -> Cow<'a, Path> {
+    let Some(base) = base else {
+        return Cow::Borrowed(path);
+    };
+    match std::path::absolute(path) {
+        Ok(absolute) => match absolute.strip_prefix(base) {
+            Ok(rest) if rest.as_os_str().is_empty() => Cow::Borrowed(Path::new(".")),
+            Ok(rest) => Cow::Owned(rest.to_path_buf()),
+            Err(_) => Cow::Owned(absolute),
+        },
+        Err(_) => Cow::Borrowed(path),
+    }
+}
+
 struct OutputWriter {
     output_file: Option<String>,
+    relative_to: Option<PathBuf>,
 }
 
 impl OutputWriter {
     fn new() -> Self {
-        Self { output_file: None }
+        Self {
+            output_file: None,
+            relative_to: None,
+        }
+    }
+
+    fn display_path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        relative_path(path, self.relative_to.as_deref())
     }
 
     fn with_file(path: &Path) -> Result<Self> {
+        // "-" is the conventional spelling for stdout
+        if path == Path::new("-") {
+            return Ok(Self::new());
+        }
+
         Ok(Self {
             output_file: Some(path.to_string_lossy().to_string()),
+            relative_to: None,
+        })
+    }
+
+    // For adapters that write record by record instead of building the whole report first
+    fn open_stream(&self) -> Result<Box<dyn Write>> {
+        Ok(match &self.output_file {
+            Some(path) => Box::new(std::io::BufWriter::new(fs::File::create(path)?)),
+            None => Box::new(std::io::stdout().lock()),
         })
     }
 
@@ -38,12 +128,22 @@ impl OutputWriter {
 
 pub struct ConsoleOutputAdapter {
     summary_only: bool,
+    color: bool,
+    relative_to: Option<PathBuf>,
+}
+
+impl Default for ConsoleOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ConsoleOutputAdapter {
     pub fn new() -> Self {
         Self {
             summary_only: false,
+            color: console::colors_enabled(),
+            relative_to: None,
         }
     }
 
@@ -51,32 +151,118 @@ impl ConsoleOutputAdapter {
         self.summary_only = summary_only;
         self
     }
+
+    // Defaults to on when stdout is a terminal that supports it
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.relative_to = base;
+        self
+    }
+
+    fn display_path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        relative_path(path, self.relative_to.as_deref())
+    }
+
+    fn paint<D>(&self, styled: console::StyledObject<D>) -> console::StyledObject<D> {
+        styled.force_styling(self.color)
+    }
+
+    // Labels are padded so the values line up in one column
+    fn print_field(&self, label: &str, value: impl std::fmt::Display) {
+        println!("{:<24}{}", format!("{}:", label), self.paint(style(value).cyan()));
+    }
 }
 
 impl OutputPort for ConsoleOutputAdapter {
     fn write_results(&self, results: &ScanResult) -> Result<()> {
-        println!("\n=== Duplicate File Scan Results ===");
-        println!("Total files scanned: {}", results.total_files_scanned);
-        println!("Total size scanned: {:.2} MB", results.total_size_scanned as f64 / 1_048_576.0);
-        println!("Duplicate groups found: {}", results.duplicate_groups());
-        println!("Total duplicate files: {}", results.total_duplicate_files());
-        println!("Wasted space: {:.2} MB", results.total_wasted_space as f64 / 1_048_576.0);
-        
-        if results.duplicates.is_empty() {
-            println!("\nNo duplicates found!");
-            return Ok(());
+        println!("\n{}", self.paint(style("=== Duplicate File Scan Results ===").bold()));
+        self.print_field("Scanned at", humantime::format_rfc3339_seconds(results.scanned_at));
+        if let Some(config) = &results.scan_config {
+            self.print_field("Hash algorithm", config.hash_algorithm.as_str());
         }
-
-        if !self.summary_only {
-            println!("\n=== Duplicate Groups ===");
-            for (i, group) in results.duplicates.iter().enumerate() {
-                println!("\nGroup {} (Hash: {})", i + 1, &group.hash[..16]);
-                println!("  Size: {:.2} MB each", group.files[0].size as f64 / 1_048_576.0);
-                println!("  Wasted space: {:.2} MB", group.wasted_space() as f64 / 1_048_576.0);
+        self.print_field("Total files scanned", results.total_files_scanned);
+        self.print_field("Total size scanned", format_size(results.total_size_scanned));
+        self.print_field("Duplicate groups found", results.duplicate_groups());
+        self.print_field("Total duplicate files", results.total_duplicate_files());
+        println!("{:<24}{}", "Wasted space:", self.paint(style(format_size(results.total_wasted_space)).yellow().bold()));
+        println!("{:.1}% of scanned data is redundant", results.dedup_ratio() * 100.0);
+        println!("{:.1}% of scanned files are redundant copies", results.duplicate_file_ratio() * 100.0);
+        if !results.skipped.is_empty() {
+            println!(
+                "{}",
+                self.paint(style(format!(
+                    "Skipped {} files that could not be read (--verbose-errors prints them while scanning; --format json lists every one)",
+                    results.skipped.len()
+                )).yellow())
+            );
+        }
+        if results.cancelled {
+            println!("{}", self.paint(style("Scan was cancelled before it finished; these results are partial").yellow()));
+        }
+        if results.match_by.is_approximate() {
+            println!("\n{}", self.paint(style(format!("NOTE: files were grouped by {} without comparing content; these results are approximate.", results.match_by.as_str())).yellow()));
+        }
+        
+        if results.duplicate_groups() == 0 {
+            println!("\n{}", self.paint(style("No duplicates found!").green()));
+        } else if !self.summary_only {
+            println!("\n{}", self.paint(style("=== Duplicate Groups ===").bold()));
+            let shown_groups = results.groups().count();
+            if shown_groups < results.duplicate_groups() {
+                println!("Showing the top {} of {} groups by wasted space", shown_groups, results.duplicate_groups());
+            }
+            for (i, group) in results.groups().enumerate() {
+                println!("\n{} (Hash: {})", self.paint(style(format!("Group {}", i + 1)).bold()), truncate_hash(&group.hash, 16));
+                println!("  {:<14}{} each", "Size:", format_size(group.files[0].size));
+                println!("  {:<14}{}", "Wasted space:", self.paint(style(format_size(group.wasted_space())).yellow()));
+                println!("  {:<14}{}", "Verified by:", group.verification.as_str());
+                if !group.same_filesystem {
+                    println!("  {}", self.paint(style("Note: files span multiple filesystems and cannot be hardlinked together").dim()));
+                }
                 println!("  Files:");
                 
-                for file in &group.files {
-                    println!("    {}", file.path.display());
+                for (j, file) in group.files.iter().enumerate() {
+                    let target = file
+                        .symlink_target
+                        .as_ref()
+                        .map(|t| format!(" -> {}", t.display()))
+                        .unwrap_or_default();
+                    if j == 0 {
+                        println!("    {}{}  {}", self.display_path(&file.path).display(), target, self.paint(style("(kept)").green()));
+                    } else {
+                        println!("    {}{}", self.display_path(&file.path).display(), target);
+                    }
+                }
+            }
+        }
+
+        if !results.prefix_matches.is_empty() {
+            println!("\n{}", self.paint(style("=== Prefix Matches ===").bold()));
+            self.print_field("Prefix relationships found", results.prefix_matches.len());
+            if !self.summary_only {
+                for m in &results.prefix_matches {
+                    println!("\n  {} ({})", self.display_path(&m.prefix.path).display(), format_size(m.prefix.size));
+                    println!("    is a prefix of {} ({})", self.display_path(&m.container.path).display(), format_size(m.container.size));
+                }
+            }
+        }
+
+        if !results.chunk_overlaps.is_empty() {
+            println!("\n{}", self.paint(style("=== Chunk Overlaps ===").bold()));
+            self.print_field("Partially overlapping pairs found", results.chunk_overlaps.len());
+            if !self.summary_only {
+                for overlap in &results.chunk_overlaps {
+                    println!(
+                        "\n  {:.1}% shared ({})",
+                        overlap.similarity * 100.0,
+                        format_size(overlap.shared_bytes)
+                    );
+                    println!("    {}", self.display_path(&overlap.first).display());
+                    println!("    {}", self.display_path(&overlap.second).display());
                 }
             }
         }
@@ -87,64 +273,358 @@ impl OutputPort for ConsoleOutputAdapter {
 
 pub struct JsonOutputAdapter {
     writer: OutputWriter,
+    summary_only: bool,
+}
+
+impl Default for JsonOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl JsonOutputAdapter {
     pub fn new() -> Self {
-        Self { writer: OutputWriter::new() }
+        Self { writer: OutputWriter::new(), summary_only: false }
     }
 
     pub fn with_file(path: &Path) -> Result<Self> {
         Ok(Self {
             writer: OutputWriter::with_file(path)?,
+            summary_only: false,
         })
     }
 
     pub fn with_stdout() -> Self {
+        Self::new()
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    pub fn with_summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
+    }
+}
+
+// JSON shape of a scanned file. `SystemTime` serializes as an opaque struct, so the
+// modification time is rendered as RFC 3339 (null before the epoch) and the best known hash
+// is flattened into a single field.
+#[derive(Serialize)]
+struct JsonFileView<'a> {
+    path: Cow<'a, Path>,
+    size: u64,
+    modified: Option<String>,
+    hash: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nlink: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dev: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ino: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symlink_target: Option<&'a Path>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bom_len: Option<u64>,
+}
+
+impl<'a> JsonFileView<'a> {
+    fn new(file: &'a FileMetadata, base: Option<&Path>) -> Self {
+        let modified = file
+            .modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|_| humantime::format_rfc3339_seconds(file.modified).to_string());
         Self {
-            writer: OutputWriter::new(),
+            path: relative_path(&file.path, base),
+            size: file.size,
+            modified,
+            hash: file.get_best_hash().map(String::as_str),
+            nlink: file.nlink,
+            dev: file.dev,
+            ino: file.ino,
+            symlink_target: file.symlink_target.as_deref(),
+            bom_len: file.bom_len,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonGroupView<'a> {
+    hash: &'a str,
+    files: Vec<JsonFileView<'a>>,
+    total_size: u64,
+    same_filesystem: bool,
+    verification: VerificationLevel,
+}
+
+impl<'a> JsonGroupView<'a> {
+    fn new(group: &'a DuplicateSet, base: Option<&Path>) -> Self {
+        Self {
+            hash: &group.hash,
+            files: group.files.iter().map(|file| JsonFileView::new(file, base)).collect(),
+            total_size: group.total_size,
+            same_filesystem: group.same_filesystem,
+            verification: group.verification,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonPrefixView<'a> {
+    prefix: JsonFileView<'a>,
+    container: JsonFileView<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonResultView<'a> {
+    // Left out entirely in summary-only output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicates: Option<Vec<JsonGroupView<'a>>>,
+    duplicate_groups: usize,
+    total_files_scanned: usize,
+    total_size_scanned: u64,
+    total_wasted_space: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    prefix_matches: Vec<JsonPrefixView<'a>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    chunk_overlaps: &'a [ChunkOverlap],
+    match_by: MatchMode,
+    // Only written for an interrupted scan, whose groups are partial
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cancelled: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<JsonSkippedView<'a>>,
+    scanned_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scan_config: Option<&'a ScanConfig>,
+}
+
+#[derive(Serialize)]
+struct JsonSkippedView<'a> {
+    path: Cow<'a, Path>,
+    error: &'a str,
+}
+
+impl<'a> JsonResultView<'a> {
+    fn new(results: &'a ScanResult, base: Option<&Path>, summary_only: bool) -> Self {
+        Self {
+            duplicates: (!summary_only).then(|| results.groups().map(|group| JsonGroupView::new(group, base)).collect()),
+            duplicate_groups: results.duplicate_groups(),
+            total_files_scanned: results.total_files_scanned,
+            total_size_scanned: results.total_size_scanned,
+            total_wasted_space: results.total_wasted_space,
+            prefix_matches: results
+                .prefix_matches
+                .iter()
+                .filter(|_| !summary_only)
+                .map(|m| JsonPrefixView {
+                    prefix: JsonFileView::new(&m.prefix, base),
+                    container: JsonFileView::new(&m.container, base),
+                })
+                .collect(),
+            chunk_overlaps: if summary_only { &[] } else { &results.chunk_overlaps },
+            match_by: results.match_by,
+            cancelled: results.cancelled,
+            skipped: results
+                .skipped
+                .iter()
+                .map(|(path, error)| JsonSkippedView {
+                    path: relative_path(path, base),
+                    error,
+                })
+                .collect(),
+            scanned_at: humantime::format_rfc3339_seconds(results.scanned_at).to_string(),
+            scan_config: results.scan_config.as_ref(),
         }
     }
 }
 
 impl OutputPort for JsonOutputAdapter {
     fn write_results(&self, results: &ScanResult) -> Result<()> {
-        let json = serde_json::to_string_pretty(results)?;
+        let json = serde_json::to_string_pretty(&JsonResultView::new(
+            results,
+            self.writer.relative_to.as_deref(),
+            self.summary_only,
+        ))?;
         self.writer.write_content(&format!("{}\n", json))
     }
 }
 
-pub struct CsvOutputAdapter {
+#[derive(Serialize)]
+struct JsonSummaryView {
+    total_files_scanned: usize,
+    total_size_scanned: u64,
+    total_wasted_space: u64,
+    duplicate_groups: usize,
+    match_by: MatchMode,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cancelled: bool,
+}
+
+// Newline-delimited JSON: a summary object, then one object per duplicate group. Each line is
+// flushed as it is written so consumers like `jq` see groups without waiting for the rest.
+pub struct NdjsonOutputAdapter {
     writer: OutputWriter,
+    summary_only: bool,
 }
 
-impl CsvOutputAdapter {
+impl Default for NdjsonOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NdjsonOutputAdapter {
     pub fn new() -> Self {
-        Self { writer: OutputWriter::new() }
+        Self { writer: OutputWriter::new(), summary_only: false }
     }
 
     pub fn with_file(path: &Path) -> Result<Self> {
         Ok(Self {
             writer: OutputWriter::with_file(path)?,
+            summary_only: false,
         })
     }
 
     pub fn with_stdout() -> Self {
+        Self::new()
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    pub fn with_summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
+    }
+}
+
+impl OutputPort for NdjsonOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let mut out = self.writer.open_stream()?;
+        let summary = JsonSummaryView {
+            total_files_scanned: results.total_files_scanned,
+            total_size_scanned: results.total_size_scanned,
+            total_wasted_space: results.total_wasted_space,
+            duplicate_groups: results.duplicate_groups(),
+            match_by: results.match_by,
+            cancelled: results.cancelled,
+        };
+        serde_json::to_writer(&mut out, &summary)?;
+        writeln!(out)?;
+        out.flush()?;
+        if self.summary_only {
+            return Ok(());
+        }
+
+        for group in results.groups() {
+            serde_json::to_writer(&mut out, &JsonGroupView::new(group, self.writer.relative_to.as_deref()))?;
+            writeln!(out)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+pub struct JsonPerGroupOutputAdapter {
+    directory: PathBuf,
+}
+
+impl JsonPerGroupOutputAdapter {
+    pub fn new(directory: &Path) -> Self {
         Self {
-            writer: OutputWriter::new(),
+            directory: directory.to_path_buf(),
+        }
+    }
+}
+
+impl OutputPort for JsonPerGroupOutputAdapter {
+    // Files are named by zero-padded group id, padded to the width of the largest id so they
+    // sort in report order. They are created exclusively, so an existing file is an error
+    // rather than being overwritten.
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        let width = results.duplicate_groups().to_string().len().max(4);
+        for (i, group) in results.groups().enumerate() {
+            let path = self.directory.join(format!("group-{:0width$}.json", i + 1, width = width));
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to create '{}': {}", path.display(), e))?;
+            serde_json::to_writer_pretty(file, group)?;
         }
+        Ok(())
+    }
+}
+
+pub struct CsvOutputAdapter {
+    writer: OutputWriter,
+    summary_only: bool,
+}
+
+impl Default for CsvOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvOutputAdapter {
+    pub fn new() -> Self {
+        Self { writer: OutputWriter::new(), summary_only: false }
+    }
+
+    pub fn with_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: OutputWriter::with_file(path)?,
+            summary_only: false,
+        })
+    }
+
+    pub fn with_stdout() -> Self {
+        Self::new()
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    pub fn with_summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
     }
 
     fn format_csv_string(&self, results: &ScanResult) -> Result<String> {
         let mut output = String::new();
+        if self.summary_only {
+            output.push_str("total_files_scanned,total_size_scanned,duplicate_groups,total_duplicate_files,total_wasted_space\n");
+            output.push_str(&format!(
+                "{},{},{},{},{}\n",
+                results.total_files_scanned,
+                results.total_size_scanned,
+                results.duplicate_groups(),
+                results.total_duplicate_files(),
+                results.total_wasted_space
+            ));
+            return Ok(output);
+        }
         output.push_str("group_id,hash,file_path,file_size,group_size,wasted_space\n");
-        for (group_id, group) in results.duplicates.iter().enumerate() {
+        for (group_id, group) in results.groups().enumerate() {
             for file in &group.files {
                 output.push_str(&format!(
                     "{},{},{},{},{},{}\n",
                     group_id + 1,
                     group.hash,
-                    file.path.display(),
+                    self.writer.display_path(&file.path).display(),
                     file.size,
                     group.total_size,
                     group.wasted_space()
@@ -165,23 +645,40 @@ impl OutputPort for CsvOutputAdapter {
 
 pub struct TreeOutputAdapter {
     writer: OutputWriter,
+    summary_only: bool,
+}
+
+impl Default for TreeOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TreeOutputAdapter {
     pub fn new() -> Self {
-        Self { writer: OutputWriter::new() }
+        Self { writer: OutputWriter::new(), summary_only: false }
     }
 
     pub fn with_file(path: &Path) -> Result<Self> {
         Ok(Self {
             writer: OutputWriter::with_file(path)?,
+            summary_only: false,
         })
     }
 
     pub fn with_stdout() -> Self {
-        Self {
-            writer: OutputWriter::new(),
-        }
+        Self::new()
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    pub fn with_summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
     }
 
     fn format_tree_output(&self, results: &ScanResult) -> String {
@@ -192,13 +689,20 @@ impl TreeOutputAdapter {
         output.push_str(&format!("Duplicate groups found: {}\n", results.duplicate_groups()));
         output.push_str(&format!("Total duplicate files: {}\n", results.total_duplicate_files()));
         output.push_str(&format!("Wasted space: {:.2} MB\n\n", results.total_wasted_space as f64 / 1_048_576.0));
+        if results.match_by.is_approximate() {
+            output.push_str(&format!("NOTE: grouped by {} without comparing content; results are approximate.\n\n", results.match_by.as_str()));
+        }
         
-        if results.duplicates.is_empty() {
+        if results.duplicate_groups() == 0 {
             output.push_str("No duplicates found!\n");
             return output;
         }
+        if self.summary_only {
+            return output;
+        }
 
-        for (i, group) in results.duplicates.iter().enumerate() {
+        let shown_groups = results.groups().count();
+        for (i, group) in results.groups().enumerate() {
             output.push_str(&format!("Duplicate Group {} [{} files, {:.2} MB each, {:.2} MB wasted]\n", 
                 i + 1, 
                 group.files.len(),
@@ -206,10 +710,14 @@ impl TreeOutputAdapter {
                 group.wasted_space() as f64 / 1_048_576.0
             ));
             
-            output.push_str(&format!("|-- Hash: {}\n", &group.hash[..16]));
+            output.push_str(&format!("|-- Hash: {}\n", truncate_hash(&group.hash, 16)));
+            output.push_str(&format!("|-- Verified by: {}\n", group.verification.as_str()));
+            if !group.same_filesystem {
+                output.push_str("|-- Note: spans multiple filesystems, cannot be hardlinked\n");
+            }
             let mut dir_files: HashMap<PathBuf, Vec<&crate::domain::FileMetadata>> = HashMap::new();
             for file in &group.files {
-                if let Some(parent) = file.path.parent() {
+                if let Some(parent) = self.writer.display_path(&file.path).parent() {
                     dir_files.entry(parent.to_path_buf()).or_default().push(file);
                 }
             }
@@ -235,31 +743,998 @@ impl TreeOutputAdapter {
                 }
             }
             
-            if i < results.duplicates.len() - 1 {
+            if i + 1 < shown_groups {
                 output.push('\n');
             }
         }
 
-        output
+        output
+    }
+}
+
+impl OutputPort for TreeOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let output = self.format_tree_output(results);
+        self.writer.write_content(&output)
+    }
+}
+
+pub struct DirectoryOutputAdapter {
+    writer: OutputWriter,
+}
+
+impl Default for DirectoryOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DirectoryOutputAdapter {
+    pub fn new() -> Self {
+        Self { writer: OutputWriter::new() }
+    }
+
+    pub fn with_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: OutputWriter::with_file(path)?,
+        })
+    }
+
+    pub fn with_stdout() -> Self {
+        Self {
+            writer: OutputWriter::new(),
+        }
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    // Each directory is credited with the size of every duplicate copy it holds, as if it were
+    // cleaned up on its own. A directory holding all copies of a group keeps one, so that copy
+    // isn't counted. Directories are ranked independently, so the totals can exceed the overall
+    // wasted space when a group spans several directories.
+    fn directory_wasted_space(results: &ScanResult) -> Vec<(PathBuf, u64)> {
+        let mut wasted: HashMap<PathBuf, u64> = HashMap::new();
+        for group in results.groups() {
+            let mut per_dir: HashMap<&Path, Vec<u64>> = HashMap::new();
+            for file in &group.files {
+                if let Some(parent) = file.path.parent() {
+                    per_dir.entry(parent).or_default().push(file.size);
+                }
+            }
+
+            for (dir, sizes) in per_dir {
+                let credited: u64 = if sizes.len() == group.files.len() {
+                    sizes.iter().skip(1).sum()
+                } else {
+                    sizes.iter().sum()
+                };
+                *wasted.entry(dir.to_path_buf()).or_default() += credited;
+            }
+        }
+
+        let mut sorted: Vec<(PathBuf, u64)> = wasted.into_iter().filter(|(_, bytes)| *bytes > 0).collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        sorted
+    }
+}
+
+impl OutputPort for DirectoryOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let mut output = String::new();
+        for (dir, bytes) in Self::directory_wasted_space(results) {
+            output.push_str(&format!("{}  {}\n", bytes, self.writer.display_path(&dir).display()));
+        }
+        self.writer.write_content(&output)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SavingsMode {
+    // Deleting every copy but the kept one
+    #[default]
+    Delete,
+    // Replacing copies with hard links, limited to files sharing a device
+    Hardlink,
+}
+
+// Prints only the reclaimable byte count, for `SAVINGS=$(rdupe --report-savings-only .)`
+pub struct SavingsOutputAdapter {
+    mode: SavingsMode,
+}
+
+impl Default for SavingsOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SavingsOutputAdapter {
+    pub fn new() -> Self {
+        Self {
+            mode: SavingsMode::default(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: SavingsMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl OutputPort for SavingsOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let bytes = match self.mode {
+            SavingsMode::Delete => results.total_wasted_space,
+            SavingsMode::Hardlink => results.total_hardlink_savings(),
+        };
+        println!("{}", bytes);
+        Ok(())
+    }
+}
+
+pub struct SubtreeOutputAdapter {
+    writer: OutputWriter,
+    // Each scan root as given, followed by its canonical form when that differs
+    roots: Vec<(PathBuf, Option<PathBuf>)>,
+}
+
+impl Default for SubtreeOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SubtreeOutputAdapter {
+    pub fn new() -> Self {
+        Self {
+            writer: OutputWriter::new(),
+            roots: Vec::new(),
+        }
+    }
+
+    pub fn with_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: OutputWriter::with_file(path)?,
+            ..Self::new()
+        })
+    }
+
+    pub fn with_stdout() -> Self {
+        Self::new()
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    // Roots are canonicalized here, once, since cached scans report absolute paths
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots
+            .into_iter()
+            .map(|root| {
+                let canonical = fs::canonicalize(&root).ok().filter(|canonical| *canonical != root);
+                (root, canonical)
+            })
+            .collect();
+        self
+    }
+
+    // The immediate child of a scan root that holds `path`, or the root itself for files
+    // directly inside it
+    fn subtree_of(&self, path: &Path) -> Option<PathBuf> {
+        self.roots.iter().find_map(|(root, canonical)| {
+            let (base, rest) = std::iter::once(root)
+                .chain(canonical)
+                .find_map(|base| Some((base, path.strip_prefix(base).ok()?)))?;
+            let mut components = rest.components();
+            let first = components.next()?;
+            Some(if components.next().is_some() { base.join(first) } else { base.clone() })
+        })
+    }
+
+    // Each group keeps its representative (files[0]); every other copy's size is credited to
+    // the subtree it lives in, so the subtree totals add up to the overall wasted space.
+    fn subtree_wasted_space(&self, results: &ScanResult) -> Vec<(PathBuf, u64)> {
+        let mut wasted: HashMap<PathBuf, u64> = HashMap::new();
+        for group in results.groups() {
+            for file in group.files.iter().skip(1) {
+                let subtree = self.subtree_of(&file.path).unwrap_or_else(|| {
+                    file.path.parent().map(Path::to_path_buf).unwrap_or_default()
+                });
+                *wasted.entry(subtree).or_default() += file.size;
+            }
+        }
+
+        let mut sorted: Vec<(PathBuf, u64)> = wasted.into_iter().filter(|(_, bytes)| *bytes > 0).collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        sorted
+    }
+}
+
+impl OutputPort for SubtreeOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let mut output = String::new();
+        for (subtree, bytes) in self.subtree_wasted_space(results) {
+            output.push_str(&format!("{}  {}\n", bytes, self.writer.display_path(&subtree).display()));
+        }
+        self.writer.write_content(&output)
+    }
+}
+
+// Writes the deletions interactive mode would make as a script to review and run by hand:
+// POSIX sh, or PowerShell on Windows. Each group's first file, the one the keep policy chose,
+// is kept and the rest are removed.
+pub struct DeletionScriptAdapter {
+    path: PathBuf,
+}
+
+impl DeletionScriptAdapter {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    fn format_script(results: &ScanResult) -> Vec<u8> {
+        // The script keeps one copy per group, so groups whose copies may differ get no lines
+        let (groups, unverified) = split_by_byte_identity(results);
+        let reclaimable: u64 = groups.iter().map(|g| g.wasted_space()).sum();
+        let mut script = Vec::new();
+        if cfg!(windows) {
+            script.extend_from_slice(b"# PowerShell script generated by rdupe\r\n");
+        } else {
+            script.extend_from_slice(b"#!/bin/sh\n# Generated by rdupe\n");
+        }
+        let newline: &[u8] = if cfg!(windows) { b"\r\n" } else { b"\n" };
+        script.extend_from_slice(
+            format!(
+                "# {} groups, {} files to delete, {:.2} MB reclaimable",
+                groups.len(),
+                groups.iter().map(|g| g.duplicate_count()).sum::<usize>(),
+                reclaimable as f64 / 1_048_576.0
+            )
+            .as_bytes(),
+        );
+        script.extend_from_slice(newline);
+        if !unverified.is_empty() {
+            script.extend_from_slice(format!("# Skipped {} groups {}", unverified.len(), UNVERIFIED_SKIP_NOTE).as_bytes());
+            script.extend_from_slice(newline);
+        }
+
+        for group in groups {
+            script.extend_from_slice(newline);
+            // A line break in the path would end the comment and turn the rest into a command
+            let kept = Self::quote(&group.files[0].path);
+            script.extend_from_slice(b"# keep ");
+            script.extend(kept.into_iter().map(|b| if b == b'\n' || b == b'\r' { b'?' } else { b }));
+            script.extend_from_slice(newline);
+            for file in &group.files[1..] {
+                if cfg!(windows) {
+                    script.extend_from_slice(b"Remove-Item -LiteralPath ");
+                } else {
+                    script.extend_from_slice(b"rm -f -- ");
+                }
+                script.extend_from_slice(&Self::quote(&file.path));
+                script.extend_from_slice(newline);
+            }
+        }
+        script
+    }
+
+    // Single quotes disable every expansion in both shells. sh can't escape a quote inside
+    // them, so it closes, adds an escaped quote, and reopens; PowerShell doubles it.
+    #[cfg(unix)]
+    fn quote(path: &Path) -> Vec<u8> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut quoted = vec![b'\''];
+        for &byte in path.as_os_str().as_bytes() {
+            if byte == b'\'' {
+                quoted.extend_from_slice(b"'\\''");
+            } else {
+                quoted.push(byte);
+            }
+        }
+        quoted.push(b'\'');
+        quoted
+    }
+
+    #[cfg(not(unix))]
+    fn quote(path: &Path) -> Vec<u8> {
+        Self::powershell_quote(&path.to_string_lossy()).into_bytes()
+    }
+
+    // PowerShell also ends a single-quoted string at the typographic quotes U+2018 to U+201B,
+    // so those are doubled along with the ASCII one
+    #[cfg(any(not(unix), test))]
+    fn powershell_quote(text: &str) -> String {
+        let mut quoted = String::with_capacity(text.len() + 2);
+        quoted.push('\'');
+        for c in text.chars() {
+            if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+                quoted.push(c);
+            }
+            quoted.push(c);
+        }
+        quoted.push('\'');
+        quoted
+    }
+}
+
+impl OutputPort for DeletionScriptAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        fs::write(&self.path, Self::format_script(results))
+            .map_err(|e| anyhow::anyhow!("Failed to write script '{}': {}", self.path.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.path, fs::Permissions::from_mode(0o755))?;
+        }
+        eprintln!("Wrote deletion script to {}", self.path.display());
+        Ok(())
+    }
+}
+
+// fdupes' default format: one path per line with a blank line after each group, and nothing
+// else, so scripts written against fdupes can read it unchanged
+pub struct FdupesOutputAdapter {
+    writer: OutputWriter,
+}
+
+impl Default for FdupesOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FdupesOutputAdapter {
+    pub fn new() -> Self {
+        Self { writer: OutputWriter::new() }
+    }
+
+    pub fn with_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: OutputWriter::with_file(path)?,
+        })
+    }
+
+    pub fn with_stdout() -> Self {
+        Self {
+            writer: OutputWriter::new(),
+        }
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+}
+
+impl OutputPort for FdupesOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let mut output = String::new();
+        for group in results.groups() {
+            for file in &group.files {
+                output.push_str(&format!("{}\n", self.writer.display_path(&file.path).display()));
+            }
+            output.push('\n');
+        }
+        self.writer.write_content(&output)
+    }
+}
+
+// GitHub-flavored markdown for pasting into issues, PR descriptions, and wikis: the summary as
+// a bullet list, then one table per duplicate group
+pub struct MarkdownOutputAdapter {
+    writer: OutputWriter,
+    summary_only: bool,
+}
+
+impl Default for MarkdownOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownOutputAdapter {
+    pub fn new() -> Self {
+        Self { writer: OutputWriter::new(), summary_only: false }
+    }
+
+    pub fn with_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: OutputWriter::with_file(path)?,
+            summary_only: false,
+        })
+    }
+
+    pub fn with_stdout() -> Self {
+        Self::new()
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    pub fn with_summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
+    }
+
+    // A bare `|` ends the table cell even inside a code span, so it is escaped, and a line
+    // break ends the row, so it is written as a visible `\n` or `\r`. The fence is one backtick
+    // longer than any run in the path, and padded when the path starts or ends with one.
+    fn table_cell(path: &Path) -> String {
+        let text = path
+            .display()
+            .to_string()
+            .replace('|', "\\|")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat(longest_run + 1);
+        let padding = if text.starts_with('`') || text.ends_with('`') { " " } else { "" };
+        format!("{fence}{padding}{text}{padding}{fence}")
+    }
+
+    fn format_markdown(&self, results: &ScanResult) -> String {
+        let mut output = String::new();
+        output.push_str("## Duplicate File Scan Results\n\n");
+        output.push_str(&format!("- Total files scanned: {}\n", results.total_files_scanned));
+        output.push_str(&format!("- Total size scanned: {:.2} MB\n", results.total_size_scanned as f64 / 1_048_576.0));
+        output.push_str(&format!("- Duplicate groups found: {}\n", results.duplicate_groups()));
+        output.push_str(&format!("- Total duplicate files: {}\n", results.total_duplicate_files()));
+        output.push_str(&format!("- Wasted space: {:.2} MB\n", results.total_wasted_space as f64 / 1_048_576.0));
+        if results.match_by.is_approximate() {
+            output.push_str(&format!("\n> **Note:** grouped by {} without comparing content; results are approximate.\n", results.match_by.as_str()));
+        }
+        if self.summary_only {
+            return output;
+        }
+
+        for (i, group) in results.groups().enumerate() {
+            output.push_str(&format!(
+                "\n### Group {} ({:.2} MB wasted)\n\n",
+                i + 1,
+                group.wasted_space() as f64 / 1_048_576.0
+            ));
+            output.push_str("| Path | Size (bytes) | Modified |\n");
+            output.push_str("| --- | ---: | --- |\n");
+            for file in &group.files {
+                let modified = file
+                    .modified
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|_| humantime::format_rfc3339_seconds(file.modified).to_string())
+                    .unwrap_or_default();
+                output.push_str(&format!("| {} | {} | {} |\n", Self::table_cell(&self.writer.display_path(&file.path)), file.size, modified));
+            }
+        }
+        output
+    }
+}
+
+impl OutputPort for MarkdownOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        self.writer.write_content(&self.format_markdown(results))
+    }
+}
+
+// A SQLite database with a `groups` table and a `files` table, for ad-hoc SQL over large scans.
+// It is binary, so it is written straight to its path instead of through `OutputWriter`, and
+// an existing file there is replaced.
+pub struct SqliteOutputAdapter {
+    path: PathBuf,
+    relative_to: Option<PathBuf>,
+}
+
+impl SqliteOutputAdapter {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            relative_to: None,
+        }
+    }
+
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.relative_to = base;
+        self
+    }
+
+    fn write_database(&self, results: &ScanResult) -> rusqlite::Result<()> {
+        let mut db = rusqlite::Connection::open(&self.path)?;
+        db.execute_batch(
+            "CREATE TABLE groups (
+                 id INTEGER PRIMARY KEY,
+                 hash TEXT NOT NULL,
+                 total_size INTEGER NOT NULL,
+                 wasted_space INTEGER NOT NULL
+             );
+             CREATE TABLE files (
+                 group_id INTEGER NOT NULL REFERENCES groups(id),
+                 path TEXT NOT NULL,
+                 size INTEGER NOT NULL,
+                 modified TEXT
+             );
+             CREATE INDEX files_group_id ON files(group_id);
+             CREATE INDEX files_size ON files(size);",
+        )?;
+
+        // One transaction, as committing per row would make large scans crawl
+        let tx = db.transaction()?;
+        {
+            let mut insert_group = tx.prepare("INSERT INTO groups (id, hash, total_size, wasted_space) VALUES (?1, ?2, ?3, ?4)")?;
+            let mut insert_file = tx.prepare("INSERT INTO files (group_id, path, size, modified) VALUES (?1, ?2, ?3, ?4)")?;
+            for (i, group) in results.groups().enumerate() {
+                let id = i as i64 + 1;
+                insert_group.execute(rusqlite::params![id, group.hash, group.total_size as i64, group.wasted_space() as i64])?;
+                for file in &group.files {
+                    let modified = file
+                        .modified
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .ok()
+                        .map(|_| humantime::format_rfc3339_seconds(file.modified).to_string());
+                    let path = relative_path(&file.path, self.relative_to.as_deref());
+                    insert_file.execute(rusqlite::params![id, path.to_string_lossy(), file.size as i64, modified])?;
+                }
+            }
+        }
+        tx.commit()
+    }
+}
+
+impl OutputPort for SqliteOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(anyhow::anyhow!("Failed to replace '{}': {}", self.path.display(), e));
+            }
+            _ => {}
+        }
+        self.write_database(results)
+            .map_err(|e| anyhow::anyhow!("Failed to write database '{}': {}", self.path.display(), e))
+    }
+}
+
+pub struct HardlinkReportAdapter {
+    writer: OutputWriter,
+}
+
+impl Default for HardlinkReportAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HardlinkReportAdapter {
+    pub fn new() -> Self {
+        Self { writer: OutputWriter::new() }
+    }
+
+    pub fn with_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: OutputWriter::with_file(path)?,
+        })
+    }
+
+    pub fn with_stdout() -> Self {
+        Self {
+            writer: OutputWriter::new(),
+        }
+    }
+
+    // Only members sharing a device can be linked together. Within each device one copy is
+    // kept and every other copy becomes a link to it.
+    fn format_report(&self, results: &ScanResult) -> String {
+        #[derive(Default)]
+        struct DevicePlan {
+            groups: usize,
+            links: usize,
+            bytes: u64,
+        }
+
+        let mut plans: HashMap<Option<u64>, DevicePlan> = HashMap::new();
+        let mut cross_fs_groups = 0;
+        let mut stranded_files = 0;
+        for group in results.groups() {
+            let mut by_device: HashMap<Option<u64>, Vec<u64>> = HashMap::new();
+            for file in &group.files {
+                by_device.entry(file.dev).or_default().push(file.size);
+            }
+            if !group.same_filesystem {
+                cross_fs_groups += 1;
+            }
+
+            for (dev, sizes) in by_device {
+                if sizes.len() < 2 {
+                    stranded_files += sizes.len();
+                    continue;
+                }
+                let plan = plans.entry(dev).or_default();
+                plan.groups += 1;
+                plan.links += sizes.len() - 1;
+                plan.bytes += sizes.iter().skip(1).sum::<u64>();
+            }
+        }
+
+        let mut devices: Vec<_> = plans.into_iter().collect();
+        devices.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+
+        let total_links: usize = devices.iter().map(|(_, p)| p.links).sum();
+        let total_bytes: u64 = devices.iter().map(|(_, p)| p.bytes).sum();
+
+        let mut output = String::new();
+        output.push_str("=== Hardlink Feasibility Report ===\n");
+        output.push_str("(read-only: nothing has been modified)\n\n");
+        output.push_str(&format!("Duplicate groups: {}\n", results.duplicate_groups()));
+        output.push_str(&format!("Links to create: {}\n", total_links));
+        output.push_str(&format!("Reclaimable by hardlinking: {:.2} MB\n", total_bytes as f64 / 1_048_576.0));
+        output.push_str(&format!("Filesystems involved: {}\n", devices.len()));
+        output.push_str(&format!(
+            "Groups spanning filesystems: {} ({} files have no same-filesystem twin and cannot be linked)\n",
+            cross_fs_groups, stranded_files
+        ));
+
+        output.push_str(&format!("\n{:<20} {:>8} {:>8} {:>16}\n", "device", "groups", "links", "reclaimable (MB)"));
+        for (dev, plan) in &devices {
+            let device = dev.map_or_else(|| "unknown".to_string(), |d| d.to_string());
+            output.push_str(&format!(
+                "{:<20} {:>8} {:>8} {:>16.2}\n",
+                device,
+                plan.groups,
+                plan.links,
+                plan.bytes as f64 / 1_048_576.0
+            ));
+        }
+
+        output
+    }
+}
+
+impl OutputPort for HardlinkReportAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let output = self.format_report(results);
+        self.writer.write_content(&output)
+    }
+}
+
+pub struct TierOutputAdapter {
+    writer: OutputWriter,
+    risky_dirs: Vec<PathBuf>,
+}
+
+impl Default for TierOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TierOutputAdapter {
+    pub fn new() -> Self {
+        Self {
+            writer: OutputWriter::new(),
+            risky_dirs: DEFAULT_RISKY_DIRS.iter().map(PathBuf::from).collect(),
+        }
+    }
+
+    pub fn with_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: OutputWriter::with_file(path)?,
+            ..Self::new()
+        })
+    }
+
+    pub fn with_stdout() -> Self {
+        Self::new()
+    }
+
+    // Paths are rendered relative to `base`; the scanned metadata itself is left untouched
+    pub fn with_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.writer.relative_to = base;
+        self
+    }
+
+    pub fn with_risky_dirs(mut self, risky_dirs: Vec<PathBuf>) -> Self {
+        if !risky_dirs.is_empty() {
+            self.risky_dirs = risky_dirs;
+        }
+        self
+    }
+
+    fn format_tiers(&self, results: &ScanResult) -> String {
+        let mut tiers: HashMap<SafetyTier, Vec<&crate::domain::DuplicateSet>> = HashMap::new();
+        for group in results.groups() {
+            tiers.entry(group.safety_tier(&self.risky_dirs)).or_default().push(group);
+        }
+
+        let mut output = String::new();
+        output.push_str("=== Duplicates by Deletion Safety ===\n");
+        for tier in [SafetyTier::VerySafe, SafetyTier::Safe, SafetyTier::Risky] {
+            let groups = tiers.remove(&tier).unwrap_or_default();
+            let wasted: u64 = groups.iter().map(|g| g.wasted_space()).sum();
+            output.push_str(&format!(
+                "\n## {} ({}): {} groups, {:.2} MB reclaimable\n",
+                tier.as_str().to_uppercase(),
+                tier.description(),
+                groups.len(),
+                wasted as f64 / 1_048_576.0
+            ));
+
+            for group in groups {
+                output.push_str(&format!("\n  [{} files, {:.2} MB wasted]\n", group.files.len(), group.wasted_space() as f64 / 1_048_576.0));
+                for file in &group.files {
+                    output.push_str(&format!("    {}\n", self.writer.display_path(&file.path).display()));
+                }
+            }
+        }
+
+        output
+    }
+}
+
+impl OutputPort for TierOutputAdapter {
+    fn write_results(&self, results: &ScanResult) -> Result<()> {
+        let output = self.format_tiers(results);
+        self.writer.write_content(&output)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InteractiveTheme {
+    #[default]
+    Colorful,
+    // No color and only ASCII markers, for low-color terminals and screen readers
+    Plain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfirmationLevel {
+    // Three escalating prompts before any bulk delete
+    #[default]
+    Full,
+    Single,
+    // No prompt at all; only reachable with an explicit --force
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeleteErrorPolicy {
+    // Report the failure and keep deleting
+    #[default]
+    Continue,
+    // Stop all deletion at the first failure
+    Abort,
+    // Leave the rest of the failing group untouched and move on to the next group
+    SkipGroup,
+}
+
+// Where deleted duplicates go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeletionMode {
+    #[default]
+    Permanent,
+    // Moved to the system trash, where they can still be restored
+    Trash,
+}
+
+impl DeletionMode {
+    fn verb(&self) -> &'static str {
+        match self {
+            DeletionMode::Permanent => "permanently delete",
+            DeletionMode::Trash => "move to the trash",
+        }
+    }
+
+    fn past_tense(&self) -> &'static str {
+        match self {
+            DeletionMode::Permanent => "Deleted",
+            DeletionMode::Trash => "Trashed",
+        }
+    }
+
+    fn journal_action(&self) -> JournalAction {
+        match self {
+            DeletionMode::Permanent => JournalAction::Deleted,
+            DeletionMode::Trash => JournalAction::Trashed,
+        }
+    }
+
+    // Returns where the file went, for modes that keep it around
+    fn remove(&self, path: &Path) -> std::io::Result<Option<PathBuf>> {
+        match self {
+            DeletionMode::Permanent => fs::remove_file(path).map(|_| None),
+            DeletionMode::Trash => trash::move_to_trash(path).map(|_| None),
+        }
+    }
+}
+
+// What a duplicate is replaced with when space is reclaimed without removing its path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkKind {
+    Hard,
+    // Works across filesystems, but breaks if the kept file later moves
+    Symbolic,
+    // Copy-on-write clone sharing the kept file's blocks; needs btrfs, XFS, or similar
+    #[cfg(target_os = "linux")]
+    Reflink,
+}
+
+impl LinkKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LinkKind::Hard => "hardlinks",
+            LinkKind::Symbolic => "symlinks",
+            #[cfg(target_os = "linux")]
+            LinkKind::Reflink => "reflinks",
+        }
+    }
+
+    fn create(&self, target: &Path, link: &Path) -> std::io::Result<()> {
+        match self {
+            LinkKind::Hard => fs::hard_link(target, link),
+            #[cfg(unix)]
+            LinkKind::Symbolic => std::os::unix::fs::symlink(target, link),
+            #[cfg(windows)]
+            LinkKind::Symbolic => std::os::windows::fs::symlink_file(target, link),
+            #[cfg(target_os = "linux")]
+            LinkKind::Reflink => reflink::reflink(target, link),
+        }
+    }
+}
+
+// FICLONE fails with EOPNOTSUPP on filesystems without reflink support and with EINVAL when
+// the filesystem can't clone these particular files
+#[cfg(target_os = "linux")]
+fn is_reflink_unsupported(error: &std::io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(libc::EOPNOTSUPP) | Some(libc::EINVAL))
+}
+
+pub struct InteractiveOutputAdapter {
+    term: Term,
+    keep_policy: KeepPolicy,
+    preselected_strategy: Option<KeepStrategy>,
+    theme: Box<dyn Theme>,
+    confirmations: ConfirmationLevel,
+    on_delete_error: DeleteErrorPolicy,
+    deletion_mode: DeletionMode,
+    journal_path: Option<PathBuf>,
+    preferred_dirs: Vec<PathBuf>,
+    keep_regex: Option<Regex>,
+    dry_run: bool,
+}
+
+impl Default for InteractiveOutputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InteractiveOutputAdapter {
+    pub fn new() -> Self {
+        Self {
+            term: Term::stdout(),
+            keep_policy: KeepPolicy::default(),
+            preselected_strategy: None,
+            theme: Box::new(ColorfulTheme::default()),
+            confirmations: ConfirmationLevel::default(),
+            on_delete_error: DeleteErrorPolicy::default(),
+            deletion_mode: DeletionMode::default(),
+            journal_path: None,
+            preferred_dirs: Vec::new(),
+            keep_regex: None,
+            dry_run: false,
+        }
+    }
+
+    // Every action only reports what it would change
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_deletion_mode(mut self, mode: DeletionMode) -> Self {
+        self.deletion_mode = mode;
+        self
+    }
+
+    // Every file removed is recorded here so `--undo` can restore it later
+    pub fn with_journal(mut self, journal_path: Option<PathBuf>) -> Self {
+        self.journal_path = journal_path;
+        self
+    }
+
+    fn record_removal(&self, file: &FileMetadata, action: JournalAction, location: Option<PathBuf>) {
+        let Some(journal_path) = &self.journal_path else {
+            return;
+        };
+        let entry = JournalEntry {
+            original: std::path::absolute(&file.path).unwrap_or_else(|_| file.path.clone()),
+            size: file.size,
+            hash: file.get_best_hash().cloned(),
+            action,
+            location,
+        };
+        if let Err(e) = JournalAdapter::new().append_entry(journal_path, &entry) {
+            println!("{}", style(format!("Warning: could not record {} in the undo journal: {:#}", file.path.display(), e)).yellow());
+        }
+    }
+
+    pub fn with_delete_error_policy(mut self, policy: DeleteErrorPolicy) -> Self {
+        self.on_delete_error = policy;
+        self
+    }
+
+    pub fn with_confirmations(mut self, confirmations: ConfirmationLevel) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: InteractiveTheme) -> Self {
+        self.theme = match theme {
+            InteractiveTheme::Colorful => Box::new(ColorfulTheme::default()),
+            InteractiveTheme::Plain => {
+                // The banners and group listings are styled too, so strip color globally
+                console::set_colors_enabled(false);
+                Box::new(SimpleTheme)
+            }
+        };
+        self
+    }
+
+    pub fn with_keep_policy(mut self, keep_policy: KeepPolicy) -> Self {
+        self.keep_policy = keep_policy;
+        self
+    }
+
+    // Ranked directories for the directory priority action, which is then preselected
+    pub fn with_preferred_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.preferred_dirs = dirs;
+        self
+    }
+
+    // Pattern for the keep-by-regex action, which is then preselected
+    pub fn with_keep_regex(mut self, pattern: Option<Regex>) -> Self {
+        self.keep_regex = pattern;
+        self
+    }
+
+    pub fn with_preselected_strategy(mut self, strategy: Option<KeepStrategy>) -> Self {
+        self.preselected_strategy = strategy;
+        self
+    }
+
+    fn print_freed(&self, bytes: u64) {
+        if self.dry_run {
+            println!("Would free {:.2} MB (re-run without --dry-run to apply)", bytes as f64 / 1_048_576.0);
+        } else {
+            println!("Freed {:.2} MB", bytes as f64 / 1_048_576.0);
+        }
     }
-}
 
-impl OutputPort for TreeOutputAdapter {
-    fn write_results(&self, results: &ScanResult) -> Result<()> {
-        let output = self.format_tree_output(results);
-        self.writer.write_content(&output)
+    fn dry_run_suffix(&self) -> &'static str {
+        if self.dry_run { " (dry run)" } else { "" }
     }
-}
 
-pub struct InteractiveOutputAdapter {
-    term: Term,
-}
+    fn print_dry_run_note(&self) {
+        if self.dry_run {
+            println!("{}", style("DRY RUN: nothing will be deleted or changed; actions are only previewed.").cyan().bold());
+        }
+    }
 
-impl InteractiveOutputAdapter {
-    pub fn new() -> Self {
-        Self {
-            term: Term::stdout(),
+    // The groups a bulk action may touch. Groups not known to be byte-identical are reported
+    // and left alone, since only one of their copies would survive.
+    fn actionable_groups<'a>(&self, results: &'a ScanResult) -> Vec<&'a DuplicateSet> {
+        let (actionable, unverified) = split_by_byte_identity(results);
+        if !unverified.is_empty() {
+            println!("{}", style(format!("Skipping {} groups {}", unverified.len(), UNVERIFIED_SKIP_NOTE)).yellow());
         }
+        actionable
     }
 
     fn ensure_cursor_visible(&self) {
@@ -267,18 +1742,43 @@ impl InteractiveOutputAdapter {
     }
 
     fn get_bulk_deletion_confirmation(&self, file_count: usize, operation_description: &str) -> Result<bool> {
-        println!("\n{}", style("WARNING! BULK DELETE MAY BREAK THINGS!").bold().red());
-        println!("{}", operation_description);
-        println!();
-        println!("Deleting duplicates can cause data loss, break applications that reference");
-        println!("these files, or remove important backups and versioned copies. Files in");
-        println!("different locations may serve different purposes even if they appear identical.");
-        println!("This could range from losing personal documents to breaking system components.");
-        println!();
-        println!("This action CANNOT be undone!");
-        
-        let first_confirm = Confirm::with_theme(&ColorfulTheme::default())
-            .with_prompt("Do you understand that this will permanently delete files?")
+        self.print_dry_run_note();
+        if self.deletion_mode == DeletionMode::Trash {
+            println!("\n{}", style("WARNING! BULK DELETE MAY BREAK THINGS!").bold().yellow());
+            println!("{}", operation_description);
+            println!();
+            println!("Removing duplicates can break applications that reference these files.");
+            println!("Trashed files can be restored from the system trash until it is emptied.");
+        } else {
+            println!("\n{}", style("WARNING! BULK DELETE MAY BREAK THINGS!").bold().red());
+            println!("{}", operation_description);
+            println!();
+            println!("Deleting duplicates can cause data loss, break applications that reference");
+            println!("these files, or remove important backups and versioned copies. Files in");
+            println!("different locations may serve different purposes even if they appear identical.");
+            println!("This could range from losing personal documents to breaking system components.");
+            println!();
+            println!("This action CANNOT be undone!");
+        }
+
+        match self.confirmations {
+            ConfirmationLevel::Full => {}
+            ConfirmationLevel::Single => {
+                let confirm = Confirm::with_theme(self.theme.as_ref())
+                    .with_prompt(format!("{} {} files?{}", capitalize(self.deletion_mode.verb()), file_count, self.dry_run_suffix()))
+                    .default(false)
+                    .interact()?;
+                if !confirm {
+                    println!("Operation cancelled.");
+                    self.ensure_cursor_visible();
+                }
+                return Ok(confirm);
+            }
+            ConfirmationLevel::None => return Ok(true),
+        }
+
+        let first_confirm = Confirm::with_theme(self.theme.as_ref())
+            .with_prompt(format!("Do you understand that this will {} files?", self.deletion_mode.verb()))
             .default(false)
             .interact()?;
 
@@ -288,7 +1788,7 @@ impl InteractiveOutputAdapter {
             return Ok(false);
         }
 
-        let second_confirm = Confirm::with_theme(&ColorfulTheme::default())
+        let second_confirm = Confirm::with_theme(self.theme.as_ref())
             .with_prompt(format!("Are you SURE you want to delete {} files?", file_count))
             .default(false)
             .interact()?;
@@ -299,7 +1799,7 @@ impl InteractiveOutputAdapter {
             return Ok(false);
         }
 
-        let final_confirm = Confirm::with_theme(&ColorfulTheme::default())
+        let final_confirm = Confirm::with_theme(self.theme.as_ref())
             .with_prompt("Last chance, really delete these files?")
             .default(false)
             .interact()?;
@@ -313,13 +1813,371 @@ impl InteractiveOutputAdapter {
         Ok(true)
     }
 
+    // Deletes one group's files under the configured error policy. Returns false when the
+    // policy says to stop deleting altogether.
+    fn delete_group_files(&self, files: &[&FileMetadata], deleted_count: &mut usize, deleted_size: &mut u64) -> bool {
+        for file in files {
+            if self.dry_run {
+                println!("{} {}", style("Would delete:").cyan(), file.path.display());
+                *deleted_count += 1;
+                *deleted_size += file.size;
+                continue;
+            }
+            match self.deletion_mode.remove(&file.path) {
+                Ok(location) => {
+                    self.record_removal(file, self.deletion_mode.journal_action(), location);
+                    println!("{} {}", style(format!("{}:", self.deletion_mode.past_tense())).green(), file.path.display());
+                    *deleted_count += 1;
+                    *deleted_size += file.size;
+                }
+                Err(e) => {
+                    println!("{} {}: {}", style("Error deleting").red(), file.path.display(), e);
+                    match self.on_delete_error {
+                        DeleteErrorPolicy::Continue => {}
+                        DeleteErrorPolicy::SkipGroup => {
+                            println!("{}", style("Leaving the rest of this group untouched.").yellow());
+                            return true;
+                        }
+                        DeleteErrorPolicy::Abort => {
+                            println!("{}", style("Aborting all further deletion.").red());
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // Swaps `duplicate` for a link to `target`. The link is made under a temporary name and
+    // renamed over the duplicate, so the path never goes missing even if we are interrupted.
+    fn replace_with_link(kind: LinkKind, target: &Path, duplicate: &Path) -> std::io::Result<()> {
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(duplicate.file_name().unwrap_or_default());
+        temp_name.push(format!(".rdupe-link-{}", std::process::id()));
+        let temp_path = duplicate.with_file_name(temp_name);
+
+        kind.create(target, &temp_path)?;
+        // A clone is a new file, so carry over the duplicate's permissions rather than the
+        // defaults it was created with
+        #[cfg(target_os = "linux")]
+        if kind == LinkKind::Reflink
+            && let Err(e) = fs::metadata(duplicate).and_then(|m| fs::set_permissions(&temp_path, m.permissions()))
+        {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        fs::rename(&temp_path, duplicate).inspect_err(|_| {
+            let _ = fs::remove_file(&temp_path);
+        })
+    }
+
+    // Links one group's duplicates to its kept file under the configured error policy. A group
+    // spanning filesystems can't be hardlinked and is skipped whole, before any file is touched.
+    // Returns false when the policy says to stop.
+    fn link_group_files(&self, kind: LinkKind, kept: &FileMetadata, files: &[&FileMetadata], linked_count: &mut usize, freed_size: &mut u64) -> bool {
+        if kind != LinkKind::Symbolic {
+            let kept_dev = Self::current_device(kept);
+            if let Some(other) = files.iter().find(|f| Self::current_device(f) != kept_dev) {
+                println!("{}", style(format!("Skipping group: {} is on a different filesystem than {}", other.path.display(), kept.path.display())).yellow());
+                return true;
+            }
+        }
+
+        // Symlinks must name the real file: a relative target would resolve against the
+        // link's directory, and a kept path that is itself a link could form a loop
+        let target = match kind {
+            LinkKind::Symbolic => match fs::canonicalize(&kept.path) {
+                Ok(target) => target,
+                Err(e) => {
+                    println!("{} {}: {}", style("Error resolving").red(), kept.path.display(), e);
+                    return true;
+                }
+            },
+            _ => kept.path.clone(),
+        };
+
+        for file in files {
+            // Already the same inode, so there is nothing to reclaim
+            if file.inode_key().is_some() && file.inode_key() == kept.inode_key() {
+                continue;
+            }
+            if kind == LinkKind::Symbolic && fs::canonicalize(&file.path).is_ok_and(|real| real == target) {
+                println!("{}", style(format!("Skipping {}: the kept file resolves to it", file.path.display())).yellow());
+                continue;
+            }
+            // A duplicate with other hardlinks keeps its blocks through them
+            let frees = if Self::current_link_count(file).is_none_or(|nlink| nlink <= 1) { file.size } else { 0 };
+            if self.dry_run {
+                println!("{} {} -> {}", style("Would link:").cyan(), file.path.display(), target.display());
+                *linked_count += 1;
+                *freed_size += frees;
+                continue;
+            }
+            match Self::replace_with_link(kind, &target, &file.path) {
+                Ok(_) => {
+                    println!("{} {} -> {}", style("Linked:").green(), file.path.display(), target.display());
+                    *linked_count += 1;
+                    *freed_size += frees;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                    println!("{}", style(format!("Skipping group: {} is on a different filesystem than {}", file.path.display(), kept.path.display())).yellow());
+                    return true;
+                }
+                // Never fall back to a full copy, which would reclaim nothing
+                #[cfg(target_os = "linux")]
+                Err(e) if kind == LinkKind::Reflink && is_reflink_unsupported(&e) => {
+                    println!("{}", style(format!("Skipping group: the filesystem holding {} does not support reflinks", file.path.display())).yellow());
+                    return true;
+                }
+                Err(e) => {
+                    println!("{} {}: {}", style("Error linking").red(), file.path.display(), e);
+                    match self.on_delete_error {
+                        DeleteErrorPolicy::Continue => {}
+                        DeleteErrorPolicy::SkipGroup => {
+                            println!("{}", style("Leaving the rest of this group untouched.").yellow());
+                            return true;
+                        }
+                        DeleteErrorPolicy::Abort => {
+                            println!("{}", style("Aborting all further linking.").red());
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // Device and link count are read again rather than taken from the scan, which may be stale
+    fn current_device(file: &FileMetadata) -> Option<u64> {
+        fs::metadata(&file.path).ok().and_then(|m| platform::device_id(&m)).or(file.dev)
+    }
+
+    fn current_link_count(file: &FileMetadata) -> Option<u64> {
+        fs::metadata(&file.path).ok().and_then(|m| platform::link_count(&m)).or(file.nlink)
+    }
+
+    fn replace_with_links(&self, results: &ScanResult, kind: LinkKind, strategy: KeepStrategy) -> Result<()> {
+        let policy = self.keep_policy.clone().with_strategy(strategy);
+        // A link makes every path show the kept file's bytes, so groups not known to be
+        // byte-identical are never linked
+        let groups = self.actionable_groups(results);
+        let file_count: usize = groups.iter().map(|group| group.duplicate_count()).sum();
+        println!("\nThis will replace {} duplicate files with {} to the {} file in each group.",
+                 file_count, kind.as_str(), strategy.as_str());
+        match kind {
+            LinkKind::Hard => {
+                println!("Every path keeps working, but linked paths share one copy: editing any of them");
+                println!("changes all of them.");
+            }
+            LinkKind::Symbolic => {
+                println!("Every path keeps working as long as the kept file stays where it is; moving or");
+                println!("deleting it breaks every link pointing at it.");
+            }
+            #[cfg(target_os = "linux")]
+            LinkKind::Reflink => {
+                println!("Every path stays an independent file; the copies share disk blocks until one of");
+                println!("them is edited. Groups on filesystems without reflink support are skipped.");
+            }
+        }
+
+        self.print_dry_run_note();
+        if self.confirmations != ConfirmationLevel::None {
+            let confirm = Confirm::with_theme(self.theme.as_ref())
+                .with_prompt(format!("Replace {} files with {}?{}", file_count, kind.as_str(), self.dry_run_suffix()))
+                .default(false)
+                .interact()?;
+            if !confirm {
+                println!("Operation cancelled.");
+                self.ensure_cursor_visible();
+                return Ok(());
+            }
+        }
+
+        let mut linked_count = 0;
+        let mut freed_size = 0u64;
+
+        for group in groups {
+            let Some(kept) = group.canonical_file(&policy) else {
+                continue;
+            };
+            let files_to_link: Vec<_> = group.files.iter()
+                .filter(|f| f.path != kept.path)
+                .collect();
+
+            if !self.link_group_files(kind, kept, &files_to_link, &mut linked_count, &mut freed_size) {
+                break;
+            }
+        }
+
+        println!("\n{}", style("LINK SUMMARY:").bold().green());
+        if self.dry_run {
+            println!("Would replace {} files with {}", linked_count, kind.as_str());
+        } else {
+            println!("Replaced {} files with {}", linked_count, kind.as_str());
+        }
+        self.print_freed(freed_size);
+
+        Ok(())
+    }
+
+    // Non-interactive entry point for --reflink: clones each group's kept file, chosen by the
+    // preselected strategy, over its duplicates without showing the action menu
+    #[cfg(target_os = "linux")]
+    pub fn reflink_duplicates(&self, results: &ScanResult) -> Result<()> {
+        if results.duplicate_groups() == 0 {
+            println!("No duplicates found.");
+            return Ok(());
+        }
+        let strategy = self.preselected_strategy.unwrap_or(KeepStrategy::Newest);
+        self.replace_with_links(results, LinkKind::Reflink, strategy)?;
+        self.ensure_cursor_visible();
+        Ok(())
+    }
+
+    // Moves one group's duplicates into the quarantine under the configured error policy.
+    // Returns false when the policy says to stop.
+    fn quarantine_group_files(&self, quarantine_dir: &Path, files: &[&FileMetadata], moved_count: &mut usize, moved_size: &mut u64) -> bool {
+        for file in files {
+            if self.dry_run {
+                let target = quarantine::quarantine_target(&file.path, quarantine_dir).unwrap_or_else(|_| quarantine_dir.to_path_buf());
+                println!("{} {} -> {}", style("Would quarantine:").cyan(), file.path.display(), target.display());
+                *moved_count += 1;
+                *moved_size += file.size;
+                continue;
+            }
+            match quarantine::move_to_quarantine(&file.path, quarantine_dir) {
+                Ok(target) => {
+                    println!("{} {} -> {}", style("Quarantined:").green(), file.path.display(), target.display());
+                    self.record_removal(file, JournalAction::Quarantined, Some(target));
+                    *moved_count += 1;
+                    *moved_size += file.size;
+                }
+                Err(e) => {
+                    println!("{} {}: {}", style("Error quarantining").red(), file.path.display(), e);
+                    match self.on_delete_error {
+                        DeleteErrorPolicy::Continue => {}
+                        DeleteErrorPolicy::SkipGroup => {
+                            println!("{}", style("Leaving the rest of this group untouched.").yellow());
+                            return true;
+                        }
+                        DeleteErrorPolicy::Abort => {
+                            println!("{}", style("Aborting all further moves.").red());
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn quarantine_by_strategy(&self, results: &ScanResult, quarantine_dir: &Path, strategy: KeepStrategy) -> Result<()> {
+        let policy = self.keep_policy.clone().with_strategy(strategy);
+        let groups = self.actionable_groups(results);
+        let file_count: usize = groups.iter().map(|group| group.duplicate_count()).sum();
+        println!("\nThis will move {} duplicate files into {}, keeping the {} file in each group in place.",
+                 file_count, quarantine_dir.display(), strategy.as_str());
+        println!("Each file keeps its full original path under the quarantine directory, and {} there", quarantine::MANIFEST_NAME);
+        println!("records where it came from. Delete the directory once you are sure nothing is missing.");
+
+        self.print_dry_run_note();
+        if self.confirmations != ConfirmationLevel::None {
+            let confirm = Confirm::with_theme(self.theme.as_ref())
+                .with_prompt(format!("Move {} files into the quarantine?{}", file_count, self.dry_run_suffix()))
+                .default(false)
+                .interact()?;
+            if !confirm {
+                println!("Operation cancelled.");
+                self.ensure_cursor_visible();
+                return Ok(());
+            }
+        }
+
+        let mut moved_count = 0;
+        let mut moved_size = 0u64;
+
+        for group in groups {
+            let Some(kept) = group.canonical_file(&policy) else {
+                continue;
+            };
+            let files_to_move: Vec<_> = group.files.iter()
+                .filter(|f| f.path != kept.path)
+                .collect();
+
+            if !self.quarantine_group_files(quarantine_dir, &files_to_move, &mut moved_count, &mut moved_size) {
+                break;
+            }
+        }
+
+        println!("\n{}", style("QUARANTINE SUMMARY:").bold().green());
+        if self.dry_run {
+            println!("Would move {} files ({:.2} MB) into {}", moved_count, moved_size as f64 / 1_048_576.0, quarantine_dir.display());
+        } else {
+            println!("Moved {} files ({:.2} MB) into {}", moved_count, moved_size as f64 / 1_048_576.0, quarantine_dir.display());
+        }
+
+        Ok(())
+    }
+
+    fn quarantine_by_prompt(&self, results: &ScanResult) -> Result<()> {
+        println!("Enter the quarantine directory (created if missing):");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        let quarantine_dir = input.trim();
+
+        if quarantine_dir.is_empty() {
+            println!("No directory specified. Operation cancelled.");
+            self.ensure_cursor_visible();
+            return Ok(());
+        }
+        let quarantine_dir = std::path::absolute(quarantine_dir)?;
+        self.quarantine_by_strategy(results, &quarantine_dir, KeepStrategy::Newest)
+    }
+
+    // Non-interactive entry point for --quarantine: moves every duplicate but the file chosen
+    // by the preselected strategy into `quarantine_dir` without showing the action menu
+    pub fn quarantine_duplicates(&self, results: &ScanResult, quarantine_dir: &Path) -> Result<()> {
+        if results.duplicate_groups() == 0 {
+            println!("No duplicates found.");
+            return Ok(());
+        }
+        let quarantine_dir = std::path::absolute(quarantine_dir)?;
+        let strategy = self.preselected_strategy.unwrap_or(KeepStrategy::Newest);
+        self.quarantine_by_strategy(results, &quarantine_dir, strategy)?;
+        self.ensure_cursor_visible();
+        Ok(())
+    }
+
+    // The files the preselected bulk action would keep, so review marks the same copies that
+    // accepting the default would spare. Matches the precedence of the menu's default.
+    fn preselected_kept<'a>(&self, group: &'a DuplicateSet) -> Vec<&'a FileMetadata> {
+        if !self.preferred_dirs.is_empty() {
+            let dirs: Vec<PathBuf> = self
+                .preferred_dirs
+                .iter()
+                .map(|dir| std::path::absolute(dir).unwrap_or_else(|_| dir.clone()))
+                .collect();
+            return group.keep_by_directory_priority(&dirs, &self.keep_policy).into_iter().collect();
+        }
+        if let Some(pattern) = &self.keep_regex {
+            return group.files.iter().filter(|f| pattern.is_match(&f.path.to_string_lossy())).collect();
+        }
+        let strategy = self.preselected_strategy.unwrap_or(self.keep_policy.strategy);
+        group.canonical_file(&self.keep_policy.clone().with_strategy(strategy)).into_iter().collect()
+    }
+
     fn review_all_groups(&self, results: &ScanResult) -> Result<()> {
-        for (i, group) in results.duplicates.iter().enumerate() {
-            println!("\n{}", style(format!("Group {} of {}", i + 1, results.duplicates.len())).bold());
+        for (i, group) in results.groups().enumerate() {
+            println!("\n{}", style(format!("Group {} of {}", i + 1, results.duplicate_groups())).bold());
             println!("Size: {:.2} MB each ({:.2} MB wasted)", 
                      group.files[0].size as f64 / 1_048_576.0,
                      group.wasted_space() as f64 / 1_048_576.0);
+            println!("Verified by: {}", group.verification.as_str());
             
+            let kept_files = self.preselected_kept(group);
+            let is_kept = |file: &FileMetadata| kept_files.iter().any(|kept| kept.path == file.path);
             for (j, file) in group.files.iter().enumerate() {
                 let metadata = fs::metadata(&file.path).ok();
                 let modified = metadata
@@ -355,15 +2213,16 @@ impl InteractiveOutputAdapter {
                     })
                     .unwrap_or_else(|| "unknown".to_string());
                 
-                println!("  [{}] {} ({})", j + 1, file.path.display(), modified);
+                let kept = if is_kept(file) { "  (kept)" } else { "" };
+                println!("  [{}] {} ({}){}", j + 1, file.path.display(), modified, kept);
             }
 
             let file_names: Vec<String> = group.files.iter()
                 .enumerate()
-                .map(|(idx, f)| format!("[{}] {}", idx + 1, f.path.display()))
+                .map(|(idx, f)| format!("[{}] {}{}", idx + 1, f.path.display(), if is_kept(f) { "  (kept)" } else { "" }))
                 .collect();
 
-            let selections = MultiSelect::with_theme(&ColorfulTheme::default())
+            let selections = MultiSelect::with_theme(self.theme.as_ref())
                 .with_prompt("Select files to delete (space to select, enter to confirm)")
                 .items(&file_names)
                 .interact()?;
@@ -373,21 +2232,19 @@ impl InteractiveOutputAdapter {
                 continue;
             }
 
-            let files_to_delete: Vec<&Path> = selections.iter()
-                .map(|&idx| group.files[idx].path.as_path())
+            let files_to_delete: Vec<&FileMetadata> = selections.iter()
+                .map(|&idx| &group.files[idx])
                 .collect();
 
-            let confirm = Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt(format!("Delete {} selected files?", files_to_delete.len()))
+            let confirm = Confirm::with_theme(self.theme.as_ref())
+                .with_prompt(format!("{} {} selected files?{}", capitalize(self.deletion_mode.verb()), files_to_delete.len(), self.dry_run_suffix()))
                 .default(false)
                 .interact()?;
 
             if confirm {
-                for path in files_to_delete {
-                    match fs::remove_file(path) {
-                        Ok(_) => println!("Deleted: {}", path.display()),
-                        Err(e) => println!("Failed to delete {}: {}", path.display(), e),
-                    }
+                let (mut deleted_count, mut deleted_size) = (0, 0);
+                if !self.delete_group_files(&files_to_delete, &mut deleted_count, &mut deleted_size) {
+                    break;
                 }
             } else {
                 println!("Skipped.");
@@ -397,45 +2254,45 @@ impl InteractiveOutputAdapter {
         Ok(())
     }
 
-    fn auto_delete_by_age(&self, results: &ScanResult, keep_oldest: bool) -> Result<()> {
-        let age_type = if keep_oldest { "oldest" } else { "newest" };
-        let description = format!("This will permanently delete {} duplicate files. Only the {} file in each group will be kept.", 
-                                 results.total_duplicate_files(), age_type);
+    fn auto_delete_by_strategy(&self, results: &ScanResult, strategy: KeepStrategy) -> Result<()> {
+        let policy = self.keep_policy.clone().with_strategy(strategy);
+        let groups = self.actionable_groups(results);
+        let file_count: usize = groups.iter().map(|group| group.duplicate_count()).sum();
+        let description = if policy.prefer_globs.is_some() {
+            format!("This will {} {} duplicate files. In each group the {} file matching a preferred glob will be kept, or the {} file overall if none match.", 
+                    self.deletion_mode.verb(), file_count, strategy.as_str(), strategy.as_str())
+        } else {
+            format!("This will {} {} duplicate files. Only the {} file in each group will be kept.", 
+                    self.deletion_mode.verb(), file_count, strategy.as_str())
+        };
         
-        if !self.get_bulk_deletion_confirmation(results.total_duplicate_files(), &description)? {
+        if !self.get_bulk_deletion_confirmation(file_count, &description)? {
             return Ok(());
         }
 
         let mut deleted_count = 0;
         let mut deleted_size = 0u64;
 
-        for group in &results.duplicates {
-            let mut sorted_files = group.files.clone();
-            sorted_files.sort_by_key(|f| f.modified);
-            
-            let files_to_delete = if keep_oldest {
-                &sorted_files[1..]
-            } else {
-                &sorted_files[..sorted_files.len() - 1]
+        for group in groups {
+            let Some(kept) = group.canonical_file(&policy) else {
+                continue;
             };
+            let files_to_delete: Vec<_> = group.files.iter()
+                .filter(|f| f.path != kept.path)
+                .collect();
 
-            for file in files_to_delete {
-                match fs::remove_file(&file.path) {
-                    Ok(_) => {
-                        println!("{} {}", style("Deleted:").green(), file.path.display());
-                        deleted_count += 1;
-                        deleted_size += file.size;
-                    }
-                    Err(e) => {
-                        println!("{} {}: {}", style("Error deleting").red(), file.path.display(), e);
-                    }
-                }
+            if !self.delete_group_files(&files_to_delete, &mut deleted_count, &mut deleted_size) {
+                break;
             }
         }
 
         println!("\n{}", style("DELETION SUMMARY:").bold().green());
-        println!("Deleted {} files", deleted_count);
-        println!("Freed {:.2} MB", deleted_size as f64 / 1_048_576.0);
+        if self.dry_run {
+            println!("Would {} {} files", self.deletion_mode.verb(), deleted_count);
+        } else {
+            println!("{} {} files", self.deletion_mode.past_tense(), deleted_count);
+        }
+        self.print_freed(deleted_size);
 
         Ok(())
     }
@@ -452,15 +2309,16 @@ impl InteractiveOutputAdapter {
             return Ok(());
         }
 
+        let groups = self.actionable_groups(results);
         let mut files_to_delete_count = 0;
-        for group in &results.duplicates {
+        for group in &groups {
             files_to_delete_count += group.files.iter()
                 .filter(|f| !f.path.starts_with(preferred_dir))
                 .count();
         }
 
-        let description = format!("This will delete {} files outside of '{}'. Files inside '{}' will be kept. Deleting files outside your chosen directory can remove critical system files, application dependencies, or important documents stored elsewhere.", 
-                                 files_to_delete_count, preferred_dir, preferred_dir);
+        let description = format!("This will {} {} files outside of '{}'. Files inside '{}' will be kept. Deleting files outside your chosen directory can remove critical system files, application dependencies, or important documents stored elsewhere.", 
+                                 self.deletion_mode.verb(), files_to_delete_count, preferred_dir, preferred_dir);
         
         if !self.get_bulk_deletion_confirmation(files_to_delete_count, &description)? {
             return Ok(());
@@ -469,12 +2327,12 @@ impl InteractiveOutputAdapter {
         let mut deleted_count = 0;
         let mut deleted_size = 0u64;
 
-        for group in &results.duplicates {
+        for group in groups {
             let preferred_file = group.files.iter()
                 .find(|f| f.path.starts_with(preferred_dir));
 
             if preferred_file.is_none() {
-                println!("{}", style(format!("No files in preferred directory for group with hash {}...", &group.hash[..8])).yellow());
+                println!("{}", style(format!("No files in preferred directory for group with hash {}...", truncate_hash(&group.hash, 8))).yellow());
                 continue;
             }
 
@@ -482,23 +2340,169 @@ impl InteractiveOutputAdapter {
                 .filter(|f| !f.path.starts_with(preferred_dir))
                 .collect();
 
-            for file in files_to_delete {
-                match fs::remove_file(&file.path) {
-                    Ok(_) => {
-                        println!("{} {}", style("Deleted:").green(), file.path.display());
-                        deleted_count += 1;
-                        deleted_size += file.size;
-                    }
+            if !self.delete_group_files(&files_to_delete, &mut deleted_count, &mut deleted_size) {
+                break;
+            }
+        }
+
+        println!("\n{}", style("DELETION SUMMARY:").bold().green());
+        if self.dry_run {
+            println!("Would {} {} files", self.deletion_mode.verb(), deleted_count);
+        } else {
+            println!("{} {} files", self.deletion_mode.past_tense(), deleted_count);
+        }
+        self.print_freed(deleted_size);
+
+        Ok(())
+    }
+
+    // Directories come from --prefer, or are asked for one per line, highest priority first
+    fn auto_delete_by_directory_priority(&self, results: &ScanResult) -> Result<()> {
+        let dirs = if self.preferred_dirs.is_empty() {
+            println!("Enter directories to keep from, highest priority first, one per line (empty line to finish):");
+            let mut dirs = Vec::new();
+            loop {
+                let mut input = String::new();
+                if std::io::stdin().read_line(&mut input)? == 0 || input.trim().is_empty() {
+                    break;
+                }
+                dirs.push(PathBuf::from(input.trim()));
+            }
+            dirs
+        } else {
+            self.preferred_dirs.clone()
+        };
+
+        if dirs.is_empty() {
+            println!("No directory specified. Operation cancelled.");
+            self.ensure_cursor_visible();
+            return Ok(());
+        }
+        let dirs: Vec<PathBuf> = dirs
+            .iter()
+            .map(|dir| std::path::absolute(dir).unwrap_or_else(|_| dir.clone()))
+            .collect();
+
+        let groups = self.actionable_groups(results);
+        let files_to_delete_count: usize = groups.iter()
+            .filter(|group| group.keep_by_directory_priority(&dirs, &self.keep_policy).is_some())
+            .map(|group| group.files.len() - 1)
+            .sum();
+
+        let ranking = dirs.iter().map(|dir| format!("'{}'", dir.display())).collect::<Vec<_>>().join(", then ");
+        let description = format!("This will {} {} files. In each group one file is kept from the first of {} that holds a copy; groups with no copy in any of them are left alone.",
+                                 self.deletion_mode.verb(), files_to_delete_count, ranking);
+
+        if !self.get_bulk_deletion_confirmation(files_to_delete_count, &description)? {
+            return Ok(());
+        }
+
+        let mut deleted_count = 0;
+        let mut deleted_size = 0u64;
+
+        for group in groups {
+            let Some(kept) = group.keep_by_directory_priority(&dirs, &self.keep_policy) else {
+                println!("{}", style(format!("No files in any preferred directory for group with hash {}...", truncate_hash(&group.hash, 8))).yellow());
+                continue;
+            };
+
+            let files_to_delete: Vec<_> = group.files.iter()
+                .filter(|f| f.path != kept.path)
+                .collect();
+
+            if !self.delete_group_files(&files_to_delete, &mut deleted_count, &mut deleted_size) {
+                break;
+            }
+        }
+
+        println!("\n{}", style("DELETION SUMMARY:").bold().green());
+        if self.dry_run {
+            println!("Would {} {} files", self.deletion_mode.verb(), deleted_count);
+        } else {
+            println!("{} {} files", self.deletion_mode.past_tense(), deleted_count);
+        }
+        self.print_freed(deleted_size);
+
+        Ok(())
+    }
+
+    // Keeps every file whose path matches the pattern and deletes the rest of its group. A group
+    // where nothing matches is skipped rather than losing every copy, as is one where
+    // everything matches and there is nothing to delete.
+    fn auto_delete_by_regex(&self, results: &ScanResult) -> Result<()> {
+        let pattern = match &self.keep_regex {
+            Some(pattern) => pattern.clone(),
+            None => {
+                println!("Enter a regex; files whose path matches it are kept and the rest of each group deleted:");
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+                let input = input.trim();
+                if input.is_empty() {
+                    println!("No pattern specified. Operation cancelled.");
+                    self.ensure_cursor_visible();
+                    return Ok(());
+                }
+                match Regex::new(input) {
+                    Ok(pattern) => pattern,
                     Err(e) => {
-                        println!("{} {}: {}", style("Error deleting").red(), file.path.display(), e);
+                        println!("{} {}", style("Invalid regex:").red(), e);
+                        self.ensure_cursor_visible();
+                        return Ok(());
                     }
                 }
             }
+        };
+
+        let split = |group: &'_ DuplicateSet| -> (usize, usize) {
+            let kept = group.files.iter().filter(|f| pattern.is_match(&f.path.to_string_lossy())).count();
+            (kept, group.files.len() - kept)
+        };
+        let groups = self.actionable_groups(results);
+        let files_to_delete_count: usize = groups.iter()
+            .map(|group| split(group))
+            .filter(|&(kept, _)| kept > 0)
+            .map(|(_, deleted)| deleted)
+            .sum();
+
+        let description = format!("This will {} {} files whose path does not match '{}'. Every matching file is kept, and groups with no matching file are left alone.",
+                                 self.deletion_mode.verb(), files_to_delete_count, pattern.as_str());
+
+        if !self.get_bulk_deletion_confirmation(files_to_delete_count, &description)? {
+            return Ok(());
+        }
+
+        let mut deleted_count = 0;
+        let mut deleted_size = 0u64;
+
+        for group in groups {
+            match split(group) {
+                (0, _) => {
+                    println!("{}", style(format!("Skipping group with hash {}...: no file matches, so no copy would survive", truncate_hash(&group.hash, 8))).yellow());
+                    continue;
+                }
+                (_, 0) => {
+                    println!("{}", style(format!("Skipping group with hash {}...: every file matches, nothing to delete", truncate_hash(&group.hash, 8))).yellow());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let files_to_delete: Vec<_> = group.files.iter()
+                .filter(|f| !pattern.is_match(&f.path.to_string_lossy()))
+                .collect();
+
+            if !self.delete_group_files(&files_to_delete, &mut deleted_count, &mut deleted_size) {
+                break;
+            }
         }
 
         println!("\n{}", style("DELETION SUMMARY:").bold().green());
-        println!("Deleted {} files", deleted_count);
-        println!("Freed {:.2} MB", deleted_size as f64 / 1_048_576.0);
+        if self.dry_run {
+            println!("Would {} {} files", self.deletion_mode.verb(), deleted_count);
+        } else {
+            println!("{} {} files", self.deletion_mode.past_tense(), deleted_count);
+        }
+        self.print_freed(deleted_size);
 
         Ok(())
     }
@@ -515,46 +2519,296 @@ impl OutputPort for InteractiveOutputAdapter {
         self.term.clear_screen()?;
         
         println!("{}", style("rdupe - Duplicate File Manager").bold());
+        self.print_dry_run_note();
         println!("Found {} duplicate groups ({} files, {:.2} MB wasted space)",
                  results.duplicate_groups(),
                  results.total_duplicate_files(),
                  results.total_wasted_space as f64 / 1_048_576.0);
         
-        if results.duplicates.is_empty() {
+        if results.duplicate_groups() == 0 {
             println!("No duplicates found.");
             self.ensure_cursor_visible();
             return Ok(());
         }
 
         println!("\nActions:");
-        let actions = vec![
+        let mut actions = vec![
             "Review each group individually",
             "Delete all duplicates (keep newest)",
             "Delete all duplicates (keep oldest)", 
+            "Delete all duplicates (keep most hardlinked)",
+            "Delete all duplicates (keep first alphabetically)",
+            "Delete all duplicates (keep last alphabetically)",
+            "Delete all duplicates (keep shortest path)",
             "Delete duplicates outside directory",
-            "Exit",
+            "Delete duplicates by directory priority list",
+            "Keep files matching a regex, delete the rest",
+            "Replace duplicates with hardlinks (keep newest)",
+            "Replace duplicates with symlinks (keep newest)",
+            "Move duplicates to a quarantine directory (keep newest)",
         ];
-
-        let selection = Select::with_theme(&ColorfulTheme::default())
+        #[cfg(target_os = "linux")]
+        actions.push("Replace duplicates with reflinks (keep newest)");
+        actions.push("Exit");
+
+        // --prefer, --keep-regex, or else an explicit --keep, preselects the matching bulk action
+        let default_action = match self.preselected_strategy {
+            _ if !self.preferred_dirs.is_empty() => 8,
+            _ if self.keep_regex.is_some() => 9,
+            Some(KeepStrategy::Newest) => 1,
+            Some(KeepStrategy::Oldest) => 2,
+            Some(KeepStrategy::MostLinked) => 3,
+            Some(KeepStrategy::FirstAlphabetical) => 4,
+            Some(KeepStrategy::LastAlphabetical) => 5,
+            Some(KeepStrategy::ShortestPath) => 6,
+            None => 0,
+        };
+
+        let selection = Select::with_theme(self.theme.as_ref())
             .with_prompt("What would you like to do?")
             .items(&actions)
-            .default(0)
+            .default(default_action)
             .interact()?;
 
         match selection {
             0 => self.review_all_groups(results)?,
-            1 => self.auto_delete_by_age(results, false)?,
-            2 => self.auto_delete_by_age(results, true)?,
-            3 => self.auto_delete_by_directory(results)?,
-            4 => {
+            1 => self.auto_delete_by_strategy(results, KeepStrategy::Newest)?,
+            2 => self.auto_delete_by_strategy(results, KeepStrategy::Oldest)?,
+            3 => self.auto_delete_by_strategy(results, KeepStrategy::MostLinked)?,
+            4 => self.auto_delete_by_strategy(results, KeepStrategy::FirstAlphabetical)?,
+            5 => self.auto_delete_by_strategy(results, KeepStrategy::LastAlphabetical)?,
+            6 => self.auto_delete_by_strategy(results, KeepStrategy::ShortestPath)?,
+            7 => self.auto_delete_by_directory(results)?,
+            8 => self.auto_delete_by_directory_priority(results)?,
+            9 => self.auto_delete_by_regex(results)?,
+            10 => self.replace_with_links(results, LinkKind::Hard, KeepStrategy::Newest)?,
+            11 => self.replace_with_links(results, LinkKind::Symbolic, KeepStrategy::Newest)?,
+            12 => self.quarantine_by_prompt(results)?,
+            #[cfg(target_os = "linux")]
+            13 => self.replace_with_links(results, LinkKind::Reflink, KeepStrategy::Newest)?,
+            _ => {
                 println!("Exiting without changes.");
                 self.ensure_cursor_visible();
                 return Ok(());
             }
-            _ => unreachable!(),
         }
 
         self.ensure_cursor_visible();
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn write_file(dir: &Path, name: &str, contents: &str) -> FileMetadata {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        FileMetadata::new(path, contents.len() as u64, SystemTime::now())
+    }
+
+    #[cfg(unix)]
+    fn inode(path: &Path) -> u64 {
+        platform::inode(&fs::metadata(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn short_hashes_render_without_panicking() {
+        let dir = tempfile::tempdir().unwrap();
+        let group = DuplicateSet::new("deadbeef".into(), vec![write_file(dir.path(), "a.txt", "same"), write_file(dir.path(), "b.txt", "same")]);
+        let results = ScanResult::new(vec![group], 2, 8);
+
+        ConsoleOutputAdapter::new().with_color(false).write_results(&results).unwrap();
+        assert!(TreeOutputAdapter::new().format_tree_output(&results).contains("|-- Hash: deadbeef\n"));
+        assert_eq!(truncate_hash("deadbeef", 16), "deadbeef");
+        assert_eq!(truncate_hash("ééééé", 2), "éé");
+    }
+
+    #[test]
+    fn delete_error_policy_decides_what_follows_a_failed_removal() {
+        let run = |policy: DeleteErrorPolicy| {
+            let dir = tempfile::tempdir().unwrap();
+            let first = write_file(dir.path(), "first.txt", "same");
+            // Never written, so removing it fails
+            let missing = FileMetadata::new(dir.path().join("missing.txt"), 4, SystemTime::now());
+            let last = write_file(dir.path(), "last.txt", "same");
+
+            let adapter = InteractiveOutputAdapter::new().with_delete_error_policy(policy);
+            let (mut deleted, mut freed) = (0, 0);
+            let keep_going = adapter.delete_group_files(&[&first, &missing, &last], &mut deleted, &mut freed);
+            (keep_going, deleted, first.path.exists(), last.path.exists())
+        };
+
+        assert_eq!(run(DeleteErrorPolicy::Continue), (true, 2, false, false));
+        assert_eq!(run(DeleteErrorPolicy::SkipGroup), (true, 1, false, true));
+        assert_eq!(run(DeleteErrorPolicy::Abort), (false, 1, false, true));
+    }
+
+    #[test]
+    fn name_matched_groups_are_never_deleted_or_scripted() {
+        let dir = tempfile::tempdir().unwrap();
+        for sub in ["a", "b"] {
+            fs::create_dir(dir.path().join(sub)).unwrap();
+        }
+        let named = DuplicateSet::new(
+            "notes.txt".into(),
+            vec![write_file(&dir.path().join("a"), "notes.txt", "one"), write_file(&dir.path().join("b"), "notes.txt", "two222")],
+        )
+        .with_verification(VerificationLevel::Metadata);
+        let hashed = DuplicateSet::new("hash".into(), vec![write_file(dir.path(), "c.txt", "same"), write_file(dir.path(), "d.txt", "same")]);
+        let results = ScanResult::new(vec![named.clone(), hashed], 4, 17);
+
+        let script = String::from_utf8(DeletionScriptAdapter::format_script(&results)).unwrap();
+        assert!(!script.contains("notes.txt"));
+        assert!(script.contains("# Skipped 1 groups"));
+
+        InteractiveOutputAdapter::new()
+            .with_deletion_mode(DeletionMode::Permanent)
+            .with_confirmations(ConfirmationLevel::None)
+            .auto_delete_by_strategy(&results, KeepStrategy::Newest)
+            .unwrap();
+        assert!(named.files.iter().all(|f| f.path.exists()));
+        assert_eq!(["c.txt", "d.txt"].iter().filter(|name| dir.path().join(name).exists()).count(), 1);
+    }
+
+    #[test]
+    fn json_reports_mark_a_cancelled_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let group = DuplicateSet::new("hash".into(), vec![write_file(dir.path(), "a.txt", "same"), write_file(dir.path(), "b.txt", "same")]);
+        let complete = ScanResult::new(vec![group.clone()], 2, 8);
+        let cancelled = ScanResult::new(vec![group], 2, 8).with_cancelled(true);
+
+        let json = |results: &ScanResult| serde_json::to_value(JsonResultView::new(results, None, false)).unwrap();
+        assert_eq!(json(&cancelled)["cancelled"], true);
+        assert!(json(&complete).get("cancelled").is_none());
+
+        let path = dir.path().join("out.ndjson");
+        NdjsonOutputAdapter::with_file(&path).unwrap().write_results(&cancelled).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let summary: serde_json::Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+        assert_eq!(summary["cancelled"], true);
+    }
+
+    #[test]
+    fn dash_output_path_means_stdout() {
+        assert_eq!(OutputWriter::with_file(Path::new("-")).unwrap().output_file, None);
+        assert_eq!(JsonOutputAdapter::with_file(Path::new("-")).unwrap().writer.output_file, None);
+        assert_eq!(OutputWriter::with_file(Path::new("out.json")).unwrap().output_file.as_deref(), Some("out.json"));
+    }
+
+    #[test]
+    fn summary_only_leaves_out_the_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let group = DuplicateSet::new("hash".into(), vec![write_file(dir.path(), "a.txt", "same"), write_file(dir.path(), "b.txt", "same")]);
+        let results = ScanResult::new(vec![group], 2, 8);
+
+        let full = MarkdownOutputAdapter::new().format_markdown(&results);
+        let summary = MarkdownOutputAdapter::new().with_summary_only(true).format_markdown(&results);
+        assert!(full.contains("a.txt"));
+        assert!(!summary.contains("a.txt"));
+        assert!(summary.contains("Duplicate groups found: 1"));
+    }
+
+    #[test]
+    fn markdown_cells_fence_backticks_and_escape_line_breaks() {
+        let cell = |path: &str| MarkdownOutputAdapter::table_cell(Path::new(path));
+        assert_eq!(cell("plain/file.txt"), "`plain/file.txt`");
+        assert_eq!(cell("a``b|c"), "```a``b\\|c```");
+        assert_eq!(cell("`tick`"), "`` `tick` ``");
+        assert_eq!(cell("two\nlines\r"), "`two\\nlines\\r`");
+    }
+
+    #[test]
+    fn nested_duplicates_are_credited_to_their_top_level_subtree() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        for sub in ["a/x", "b/deep/er"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        let group = DuplicateSet::new(
+            "hash".into(),
+            ["top.txt", "a/x/one.txt", "a/two.txt", "b/deep/er/three.txt"]
+                .iter()
+                .map(|name| write_file(&root, name, "same"))
+                .collect(),
+        );
+        // A copy sitting directly in the root is credited to the root itself
+        let loose = DuplicateSet::new(
+            "other".into(),
+            ["a/x/keep.txt", "loose.txt"].iter().map(|name| write_file(&root, name, "other")).collect(),
+        );
+        let results = ScanResult::new(vec![group, loose], 6, 26);
+
+        // Given with a `..` detour, the root only matches the scanned paths in canonical form
+        let adapter = SubtreeOutputAdapter::new().with_roots(vec![root.join("a").join("..")]);
+        assert_eq!(
+            adapter.subtree_wasted_space(&results),
+            vec![(root.join("a"), 8), (root.clone(), 5), (root.join("b"), 4)]
+        );
+    }
+
+    #[test]
+    fn review_marks_the_copy_the_preselected_action_keeps() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("archive")).unwrap();
+        let mut old = write_file(&dir.path().join("archive"), "old.txt", "same");
+        old.modified = SystemTime::UNIX_EPOCH;
+        let new = write_file(dir.path(), "new.txt", "same");
+        let group = DuplicateSet::new("hash".into(), vec![old.clone(), new.clone()]);
+        let kept = |adapter: InteractiveOutputAdapter| -> Vec<PathBuf> {
+            adapter.preselected_kept(&group).into_iter().map(|f| f.path.clone()).collect()
+        };
+
+        assert_eq!(kept(InteractiveOutputAdapter::new()), vec![new.path.clone()]);
+        assert_eq!(kept(InteractiveOutputAdapter::new().with_preselected_strategy(Some(KeepStrategy::Oldest))), vec![old.path.clone()]);
+        assert_eq!(kept(InteractiveOutputAdapter::new().with_preferred_dirs(vec![dir.path().join("archive")])), vec![old.path.clone()]);
+        assert_eq!(kept(InteractiveOutputAdapter::new().with_keep_regex(Some(Regex::new("new").unwrap()))), vec![new.path]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn group_spanning_filesystems_is_skipped_before_linking_anything() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = write_file(dir.path(), "kept.txt", "same");
+        let local = write_file(dir.path(), "local.txt", "same");
+        // Gone from disk, so the recorded device is the only one known
+        let mut elsewhere = FileMetadata::new(dir.path().join("elsewhere.txt"), 4, SystemTime::now());
+        elsewhere.dev = Some(u64::MAX);
+
+        let adapter = InteractiveOutputAdapter::new();
+        let (mut linked, mut freed) = (0, 0);
+        assert!(adapter.link_group_files(LinkKind::Hard, &kept, &[&local, &elsewhere], &mut linked, &mut freed));
+        assert_eq!((linked, freed), (0, 0));
+        assert_ne!(inode(&local.path), inode(&kept.path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn duplicates_with_other_links_free_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = write_file(dir.path(), "kept.txt", "same");
+        let single = write_file(dir.path(), "single.txt", "same");
+        let shared = write_file(dir.path(), "shared.txt", "same");
+        fs::hard_link(&shared.path, dir.path().join("shared-link.txt")).unwrap();
+
+        let adapter = InteractiveOutputAdapter::new();
+        let (mut linked, mut freed) = (0, 0);
+        assert!(adapter.link_group_files(LinkKind::Hard, &kept, &[&single, &shared], &mut linked, &mut freed));
+        assert_eq!((linked, freed), (2, 4));
+        assert_eq!(inode(&single.path), inode(&kept.path));
+        assert_eq!(inode(&shared.path), inode(&kept.path));
+    }
+
+    #[test]
+    fn powershell_quote_doubles_every_single_quote_form() {
+        assert_eq!(DeletionScriptAdapter::powershell_quote(r"C:\a b\c.txt"), r"'C:\a b\c.txt'");
+        assert_eq!(DeletionScriptAdapter::powershell_quote("it's"), "'it''s'");
+        assert_eq!(
+            DeletionScriptAdapter::powershell_quote("\u{2018}a\u{2019}b\u{201A}c\u{201B}"),
+            "'\u{2018}\u{2018}a\u{2019}\u{2019}b\u{201A}\u{201A}c\u{201B}\u{201B}'"
+        );
+        assert_eq!(DeletionScriptAdapter::powershell_quote("\u{201C}x\u{201D}"), "'\u{201C}x\u{201D}'");
+    }
+}
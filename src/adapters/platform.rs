@@ -0,0 +1,39 @@
+use std::fs::Metadata;
+
+// Identity details the OS only exposes on some platforms. Callers treat None as "unknown" and
+// skip whatever check needed the value rather than failing.
+
+#[cfg(unix)]
+pub fn device_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(unix)]
+pub fn inode(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(unix)]
+pub fn link_count(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.nlink())
+}
+
+// Windows keeps the volume serial number and file index behind the unstable
+// `windows_by_handle` API, so they are reported as unknown there
+#[cfg(not(unix))]
+pub fn device_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(not(unix))]
+pub fn inode(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(not(unix))]
+pub fn link_count(_metadata: &Metadata) -> Option<u64> {
+    None
+}
@@ -1,24 +1,50 @@
 use crate::ports::ProgressPort;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::sync::Arc;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+// Skipped files reported per scan before the rest are only counted, so a tree full of
+// unreadable files can't flood the terminal
+const MAX_SKIPPED_REPORTED: usize = 100;
+
+// The message for the `reported`-th skipped file of a scan, or `None` once past the cap
+fn skipped_message(reported: usize, path: Option<&Path>, reason: &str) -> Option<String> {
+    match reported {
+        n if n < MAX_SKIPPED_REPORTED => Some(match path {
+            Some(path) => format!("skipped {}: {}", path.display(), reason),
+            None => format!("skipped: {}", reason),
+        }),
+        MAX_SKIPPED_REPORTED => Some("further skipped files are not reported".to_string()),
+        _ => None,
+    }
+}
+
+// Clones share one bar, so the filesystem walk and the hashing stages can report on the same line
+#[derive(Clone)]
 pub struct ProgressBarAdapter {
     bar: Arc<ProgressBar>,
     quiet: bool,
+    skipped_reported: Arc<AtomicUsize>,
+}
+
+impl Default for ProgressBarAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProgressBarAdapter {
     pub fn new() -> Self {
-        let bar = ProgressBar::new(0);
-        bar.set_style(
-            ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {percent:>3}% {msg} (ETA: {eta})")
-                .unwrap()
-                .progress_chars("█▉▊▋▌▍▎▏ "),
-        );
+        // Drawn on stderr so results piped from stdout (e.g. `-f json | jq`) stay clean
+        let bar = ProgressBar::with_draw_target(Some(0), ProgressDrawTarget::stderr());
+        bar.set_style(bar_style());
         Self { 
             bar: Arc::new(bar),
             quiet: false,
+            skipped_reported: Arc::default(),
         }
     }
 
@@ -27,6 +53,7 @@ impl ProgressBarAdapter {
         Self { 
             bar: Arc::new(bar),
             quiet: true,
+            skipped_reported: Arc::default(),
         }
     }
 
@@ -39,13 +66,33 @@ impl ProgressBarAdapter {
     }
 }
 
+fn bar_style() -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {percent:>3}% {msg} (ETA: {eta})")
+        .unwrap()
+        .progress_chars("█▉▊▋▌▍▎▏ ")
+}
+
+fn spinner_style() -> ProgressStyle {
+    ProgressStyle::default_spinner()
+        .template("[{elapsed_precise}] {spinner:.cyan} {msg}")
+        .unwrap()
+}
+
 impl ProgressPort for ProgressBarAdapter {
     fn start(&self, total: u64) {
         if self.quiet {
             return;
         }
         
+        // The bar may have been used as the scan spinner first
+        self.bar.reset();
+        self.bar.set_style(bar_style());
         self.bar.set_length(total);
+        if total == 0 {
+            self.bar.set_message("Nothing to hash");
+            return;
+        }
         self.bar.set_message("Scanning files...");
         self.bar.enable_steady_tick(std::time::Duration::from_millis(100));
     }
@@ -56,7 +103,10 @@ impl ProgressPort for ProgressBarAdapter {
         }
         
         self.bar.set_position(processed);
-        let total = self.bar.length().unwrap_or(1);
+        let total = match self.bar.length() {
+            Some(total) if total > 0 => total,
+            _ => return,
+        };
         let percent = (processed as f64 / total as f64) * 100.0;
         
         if percent < 50.0 {
@@ -74,4 +124,191 @@ impl ProgressPort for ProgressBarAdapter {
         self.bar.disable_steady_tick();
         self.bar.finish_with_message("✓ Scan complete!");
     }
-}
\ No newline at end of file
+
+    fn scan_started(&self) {
+        self.skipped_reported.store(0, Ordering::Relaxed);
+        if self.quiet {
+            return;
+        }
+
+        self.bar.set_style(spinner_style());
+        self.bar.set_message("Scanning directories...");
+        self.bar.enable_steady_tick(std::time::Duration::from_millis(100));
+    }
+
+    fn scan_progress(&self, files_found: u64) {
+        if self.quiet {
+            return;
+        }
+
+        self.bar.set_message(format!("Scanning directories... {} files found", files_found));
+    }
+
+    fn scan_finished(&self, _files_found: u64) {
+        if self.quiet {
+            return;
+        }
+
+        self.bar.disable_steady_tick();
+        self.bar.finish_and_clear();
+    }
+
+    // Printed above the bar so it isn't overdrawn; `--quiet` hides progress, not these
+    fn file_skipped(&self, path: Option<&Path>, reason: &str) {
+        let Some(message) = skipped_message(self.skipped_reported.fetch_add(1, Ordering::Relaxed), path, reason) else {
+            return;
+        };
+        if self.bar.is_hidden() {
+            eprintln!("{}", message);
+        } else {
+            self.bar.println(message);
+        }
+    }
+}
+
+// Minimum gap between JSON progress lines, unless a full percent has passed since the last one
+const MACHINE_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct LastReport {
+    at: Option<Instant>,
+    percent: u64,
+}
+
+// One compact JSON object per line on stderr, e.g. `{"phase":"hashing","done":10,"total":40}`,
+// for GUI and web frontends that capture the stream. The walk reports `"phase":"scanning"`
+// without a total, since the file count isn't known until it ends. Phase boundaries are always
+// reported; updates in between are throttled.
+pub struct MachineProgressAdapter {
+    total: Mutex<u64>,
+    last: Mutex<LastReport>,
+    skipped_reported: AtomicUsize,
+}
+
+impl Default for MachineProgressAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MachineProgressAdapter {
+    pub fn new() -> Self {
+        Self {
+            total: Mutex::new(0),
+            last: Mutex::new(LastReport::default()),
+            skipped_reported: AtomicUsize::new(0),
+        }
+    }
+
+    fn emit(&self, phase: &str, done: u64, total: Option<u64>) {
+        let line = match total {
+            Some(total) => format!("{{\"phase\":\"{}\",\"done\":{},\"total\":{}}}\n", phase, done, total),
+            None => format!("{{\"phase\":\"{}\",\"done\":{}}}\n", phase, done),
+        };
+        let _ = std::io::stderr().lock().write_all(line.as_bytes());
+    }
+
+    fn emit_throttled(&self, phase: &str, done: u64, total: Option<u64>) {
+        let percent = match total {
+            Some(total) if total > 0 => done * 100 / total,
+            _ => 0,
+        };
+        {
+            let mut last = self.last.lock().unwrap();
+            let due = last.at.is_none_or(|at| at.elapsed() >= MACHINE_PROGRESS_INTERVAL) || percent > last.percent;
+            if !due {
+                return;
+            }
+            *last = LastReport {
+                at: Some(Instant::now()),
+                percent,
+            };
+        }
+        self.emit(phase, done, total);
+    }
+
+    fn reset_throttle(&self) {
+        *self.last.lock().unwrap() = LastReport::default();
+    }
+}
+
+impl ProgressPort for MachineProgressAdapter {
+    fn start(&self, total: u64) {
+        *self.total.lock().unwrap() = total;
+        self.reset_throttle();
+        self.emit("hashing", 0, Some(total));
+    }
+
+    fn update(&self, processed: u64) {
+        let total = *self.total.lock().unwrap();
+        self.emit_throttled("hashing", processed, Some(total));
+    }
+
+    fn finish(&self) {
+        let total = *self.total.lock().unwrap();
+        self.emit("hashing", total, Some(total));
+    }
+
+    fn scan_started(&self) {
+        self.reset_throttle();
+        self.skipped_reported.store(0, Ordering::Relaxed);
+        self.emit("scanning", 0, None);
+    }
+
+    fn scan_progress(&self, files_found: u64) {
+        self.emit_throttled("scanning", files_found, None);
+    }
+
+    fn scan_finished(&self, files_found: u64) {
+        self.emit("scanning", files_found, None);
+    }
+
+    // e.g. `{"phase":"skipped","path":"/a/b","error":"Permission denied (os error 13)"}`
+    fn file_skipped(&self, path: Option<&Path>, reason: &str) {
+        if self.skipped_reported.fetch_add(1, Ordering::Relaxed) >= MAX_SKIPPED_REPORTED {
+            return;
+        }
+        let event = serde_json::json!({ "phase": "skipped", "path": path, "error": reason });
+        let _ = writeln!(std::io::stderr().lock(), "{}", event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_messages_stop_after_the_cap() {
+        let path = Path::new("/a/b");
+        assert_eq!(skipped_message(0, Some(path), "gone").as_deref(), Some("skipped /a/b: gone"));
+        assert_eq!(skipped_message(1, None, "walk failed").as_deref(), Some("skipped: walk failed"));
+        assert_eq!(
+            skipped_message(MAX_SKIPPED_REPORTED, Some(path), "gone").as_deref(),
+            Some("further skipped files are not reported")
+        );
+        assert_eq!(skipped_message(MAX_SKIPPED_REPORTED + 1, Some(path), "gone"), None);
+    }
+
+    #[test]
+    fn skipped_count_starts_over_with_each_scan() {
+        let progress = ProgressBarAdapter::new_quiet();
+        let clone = progress.clone();
+        for _ in 0..3 {
+            clone.file_skipped(None, "unreadable");
+        }
+        assert_eq!(progress.skipped_reported.load(Ordering::Relaxed), 3);
+
+        progress.scan_started();
+        assert_eq!(clone.skipped_reported.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn empty_hashing_phase_reports_nothing_to_do() {
+        let progress = ProgressBarAdapter::new();
+        progress.start(0);
+        progress.update(0);
+        assert_eq!(progress.bar.length(), Some(0));
+        assert_eq!(progress.bar.message(), "Nothing to hash");
+        progress.finish();
+    }
+}
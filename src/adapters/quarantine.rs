@@ -0,0 +1,155 @@
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+// Appended to as files are moved, so an interrupted run still records everything it moved
+pub const MANIFEST_NAME: &str = "rdupe-quarantine.jsonl";
+
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    original: &'a Path,
+    quarantined: &'a Path,
+    moved_at: String,
+}
+
+// Where `path` lands inside the quarantine: its absolute path re-rooted under `quarantine_dir`,
+// so same-named files from different directories never collide and restoring is a plain move
+pub fn quarantine_target(path: &Path, quarantine_dir: &Path) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let relative: PathBuf = path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    Ok(quarantine_dir.join(relative))
+}
+
+// Moves `path` into `quarantine_dir` and records it in the manifest there, returning the new
+// location. An existing file at the target is never overwritten. Moves across filesystems
+// fall back to copying and removing the original.
+pub fn move_to_quarantine(path: &Path, quarantine_dir: &Path) -> io::Result<PathBuf> {
+    let original = std::path::absolute(path)?;
+    let target = quarantine_target(&original, quarantine_dir)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if target.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is already in the quarantine", target.display()),
+        ));
+    }
+
+    move_file(&original, &target)?;
+    if let Err(e) = append_manifest(quarantine_dir, &original, &target) {
+        // Without a manifest entry the file would be hard to find again, so put it back
+        let _ = move_file(&target, &original);
+        return Err(e);
+    }
+    Ok(target)
+}
+
+// Moves a quarantined file back to `original`. An existing file there is never overwritten.
+pub fn restore_from_quarantine(quarantined: &Path, original: &Path) -> io::Result<()> {
+    if original.symlink_metadata().is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", original.display())));
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_file(quarantined, original)
+}
+
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            // `fs::copy` carries the permissions over and the modification time is copied by
+            // hand. The copy is dropped if the source can't be removed, so the file is never
+            // left in both places.
+            fs::copy(from, to)?;
+            if let Ok(modified) = fs::metadata(from).and_then(|m| m.modified()) {
+                let _ = fs::File::options().write(true).open(to).and_then(|f| f.set_modified(modified));
+            }
+            fs::remove_file(from).inspect_err(|_| {
+                let _ = fs::remove_file(to);
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn append_manifest(quarantine_dir: &Path, original: &Path, quarantined: &Path) -> io::Result<()> {
+    let entry = ManifestEntry {
+        original,
+        quarantined,
+        moved_at: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+    };
+    let mut line = serde_json::to_string(&entry).map_err(io::Error::other)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(quarantine_dir.join(MANIFEST_NAME))?
+        .write_all(line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moved_files_keep_their_path_under_the_quarantine() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("photos").join("2024").join("a.jpg");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, "pixels").unwrap();
+        let quarantine_dir = dir.path().join("quarantine");
+
+        let target = move_to_quarantine(&original, &quarantine_dir).unwrap();
+        assert_eq!(target, quarantine_target(&original, &quarantine_dir).unwrap());
+        assert!(target.starts_with(&quarantine_dir));
+        assert!(target.ends_with(Path::new("photos").join("2024").join("a.jpg")));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "pixels");
+        assert!(!original.exists());
+
+        restore_from_quarantine(&target, &original).unwrap();
+        assert_eq!(fs::read_to_string(&original).unwrap(), "pixels");
+    }
+
+    #[test]
+    fn every_move_adds_a_manifest_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let quarantine_dir = dir.path().join("quarantine");
+        for name in ["a.txt", "b.txt"] {
+            fs::write(dir.path().join(name), name).unwrap();
+            move_to_quarantine(&dir.path().join(name), &quarantine_dir).unwrap();
+        }
+
+        let manifest = fs::read_to_string(quarantine_dir.join(MANIFEST_NAME)).unwrap();
+        let lines: Vec<serde_json::Value> = manifest.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["original"].as_str().map(PathBuf::from), Some(dir.path().join("b.txt")));
+        assert_eq!(
+            lines[1]["quarantined"].as_str().map(PathBuf::from),
+            Some(quarantine_target(&dir.path().join("b.txt"), &quarantine_dir).unwrap())
+        );
+    }
+
+    #[test]
+    fn an_existing_target_is_never_clobbered() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("a.txt");
+        fs::write(&original, "new").unwrap();
+        let quarantine_dir = dir.path().join("quarantine");
+        let target = quarantine_target(&original, &quarantine_dir).unwrap();
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "old").unwrap();
+
+        let error = move_to_quarantine(&original, &quarantine_dir).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&original).unwrap(), "new");
+        assert!(!quarantine_dir.join(MANIFEST_NAME).exists());
+    }
+}
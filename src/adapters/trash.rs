@@ -0,0 +1,44 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Moves `path` to the system trash: the freedesktop.org trash, the macOS Trash, or the Windows
+// Recycle Bin, so it can be restored later. On Linux a file on another filesystem than the home
+// trash goes to that filesystem's own trash directory.
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    trash::delete(path).map_err(io::Error::other)
+}
+
+// Puts back the most recently trashed file that came from `original`. An existing file at
+// `original` is never overwritten.
+#[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))))]
+pub fn restore_from_trash(original: &Path) -> io::Result<()> {
+    if original.symlink_metadata().is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", original.display())));
+    }
+
+    // The trash records the path with its parent directory resolved
+    let resolved: Option<PathBuf> = original
+        .parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .zip(original.file_name())
+        .map(|(parent, name)| parent.join(name));
+    let item = trash::os_limited::list()
+        .map_err(io::Error::other)?
+        .into_iter()
+        .filter(|item| {
+            let path = item.original_path();
+            path == original || resolved.as_ref().is_some_and(|resolved| &path == resolved)
+        })
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} is no longer in the trash", original.display())))?;
+    trash::os_limited::restore_all([item]).map_err(io::Error::other)
+}
+
+// macOS gives programs no way to list the Trash, so files have to be put back from Finder
+#[cfg(not(any(target_os = "windows", all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))))]
+pub fn restore_from_trash(original: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("restore {} from the Trash with Finder's Put Back", original.display()),
+    ))
+}
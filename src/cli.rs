@@ -1,6 +1,10 @@
-use crate::domain::{HashAlgorithm, ScanConfig};
-use clap::{Parser, ValueEnum};
-use std::path::PathBuf;
+use crate::domain::{HashAlgorithm, KeepStrategy, MatchMode, PartialMode, ScanConfig, SortKey};
+use crate::adapters::{ConfigFile, ConfigFileAdapter};
+use anyhow::{bail, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum HashAlgorithmChoice {
@@ -22,12 +26,167 @@ pub enum HashAlgorithmChoice {
     Sha1,
 }
 
+#[derive(Debug, Clone, ValueEnum)]
+pub enum MatchByChoice {
+    #[value(help = "Identical file content (exact)")]
+    Content,
+    #[value(help = "Same file name and size, without hashing (approximate)")]
+    NameSize,
+    #[value(help = "Same file name only, without hashing (approximate)")]
+    Name,
+}
+
+impl From<MatchByChoice> for MatchMode {
+    fn from(choice: MatchByChoice) -> Self {
+        match choice {
+            MatchByChoice::Content => MatchMode::Content,
+            MatchByChoice::NameSize => MatchMode::NameSize,
+            MatchByChoice::Name => MatchMode::Name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ProgressFormat {
+    #[value(help = "A progress bar drawn on the terminal")]
+    Bar,
+    #[value(help = "One JSON object per update on stderr, for frontends that capture it")]
+    Json,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum PartialModeChoice {
+    #[value(help = "Sample the start of each file")]
+    Head,
+    #[value(help = "Sample the start and the end, for files with long shared headers")]
+    HeadTail,
+}
+
+impl From<PartialModeChoice> for PartialMode {
+    fn from(choice: PartialModeChoice) -> Self {
+        match choice {
+            PartialModeChoice::Head => PartialMode::Head,
+            PartialModeChoice::HeadTail => PartialMode::HeadTail,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum SortChoice {
+    #[value(help = "Most wasted bytes first")]
+    Wasted,
+    #[value(help = "Largest files first")]
+    Size,
+    #[value(help = "Most copies first")]
+    Count,
+    #[value(help = "By the path of each group's kept file")]
+    Path,
+}
+
+impl From<SortChoice> for SortKey {
+    fn from(choice: SortChoice) -> Self {
+        match choice {
+            SortChoice::Wasted => SortKey::Wasted,
+            SortChoice::Size => SortKey::Size,
+            SortChoice::Count => SortKey::Count,
+            SortChoice::Path => SortKey::Path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum KeepChoice {
+    #[value(help = "Keep the most recently modified file")]
+    Newest,
+    #[value(help = "Keep the least recently modified file")]
+    Oldest,
+    #[value(help = "Keep the file with the most hard links")]
+    MostLinked,
+    #[value(help = "Keep the file whose path sorts first alphabetically")]
+    First,
+    #[value(help = "Keep the file whose path sorts last alphabetically")]
+    Last,
+    #[value(help = "Keep the file with the fewest path components")]
+    Shortest,
+}
+
+impl From<KeepChoice> for KeepStrategy {
+    fn from(choice: KeepChoice) -> Self {
+        match choice {
+            KeepChoice::Newest => KeepStrategy::Newest,
+            KeepChoice::Oldest => KeepStrategy::Oldest,
+            KeepChoice::MostLinked => KeepStrategy::MostLinked,
+            KeepChoice::First => KeepStrategy::FirstAlphabetical,
+            KeepChoice::Last => KeepStrategy::LastAlphabetical,
+            KeepChoice::Shortest => KeepStrategy::ShortestPath,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ThemeChoice {
+    #[value(help = "Colored prompts with Unicode markers")]
+    Colorful,
+    #[value(help = "No color and plain ASCII markers")]
+    Plain,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ConfirmationChoice {
+    #[value(help = "Three escalating prompts before a bulk delete")]
+    Full,
+    #[value(help = "One prompt before a bulk delete")]
+    Single,
+    #[value(help = "No prompt; requires --force")]
+    None,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum DeleteErrorChoice {
+    #[value(help = "Report the failure and keep deleting")]
+    Continue,
+    #[value(help = "Stop all deletion at the first failure")]
+    Abort,
+    #[value(help = "Leave the rest of the failing group untouched")]
+    SkipGroup,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum SavingsChoice {
+    #[value(help = "Bytes freed by deleting every copy but the kept one")]
+    Delete,
+    #[value(help = "Bytes freed by hardlinking copies that share a filesystem")]
+    Hardlink,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum CacheFormatChoice {
+    #[value(help = "Pretty-printed JSON, easy to inspect")]
+    Json,
+    #[value(help = "Compact bincode, faster on large trees")]
+    Binary,
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum OutputFormat {
     Text,
     Json,
+    #[value(help = "Newline-delimited JSON: a summary line, then one duplicate group per line")]
+    Ndjson,
     Csv,
     Tree,
+    #[value(help = "fdupes-compatible: one path per line, a blank line after each group")]
+    Fdupes,
+    #[value(help = "GitHub-flavored markdown: a summary list and a table per duplicate group")]
+    Markdown,
+    #[value(help = "SQLite database with groups and files tables; needs --output")]
+    Sqlite,
+    #[value(help = "Directories containing duplicates, ranked by wasted bytes")]
+    Dirs,
+    #[value(help = "Duplicate groups bucketed by how safe they are to delete")]
+    Tiers,
+    #[value(help = "Immediate subdirectories of each scan root, ranked by wasted bytes anywhere beneath them")]
+    Subtrees,
 }
 
 impl From<HashAlgorithmChoice> for HashAlgorithm {
@@ -45,14 +204,68 @@ impl From<HashAlgorithmChoice> for HashAlgorithm {
     }
 }
 
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Print a trend summary from a history file written by --history")]
+    History {
+        #[arg(help = "History file to summarize")]
+        file: PathBuf,
+    },
+    #[command(about = "Check a --cache file against the filesystem and report stale or missing entries")]
+    VerifyCache {
+        #[arg(help = "Cache file to verify")]
+        cache_file: PathBuf,
+
+        #[arg(long = "prune", help = "Rewrite the cache keeping only entries that are still valid")]
+        prune: bool,
+    },
+    #[command(about = "Compare two manifests (file lists or cache files) and list added, removed, and changed files")]
+    ManifestDiff {
+        #[arg(help = "Earlier manifest")]
+        old: PathBuf,
+
+        #[arg(help = "Later manifest")]
+        new: PathBuf,
+
+        #[arg(long = "json", help = "Print the diff as JSON")]
+        json: bool,
+    },
+}
+
 #[derive(Parser)]
 #[command(name = "rdupe")]
 #[command(about = "A fast duplicate file finder")]
 #[command(version)]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(after_help = "Exit status: 0 if the scan ran cleanly, 1 on a scan, I/O, or usage error, \
+2 if --fail-on-duplicates or --any is set and duplicates were found.")]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(help = "Paths to scan for duplicates")]
     pub paths: Vec<PathBuf>,
 
+    #[arg(
+        long = "paths-from",
+        value_name = "FILE",
+        help = "Also scan the newline-separated paths listed in FILE ('-' for stdin)"
+    )]
+    pub paths_from: Option<PathBuf>,
+
+    #[arg(
+        long = "dump-config",
+        help = "Print the scan settings resolved from defaults, the config file, and flags as JSON, then exit without scanning"
+    )]
+    pub dump_config: bool,
+
+    #[arg(
+        long = "config",
+        value_name = "FILE",
+        help = "Read settings from this TOML file, overridden by command-line flags (default: $XDG_CONFIG_HOME/rdupe/rdupe.toml if it exists)"
+    )]
+    pub config: Option<PathBuf>,
+
     #[arg(
         short = 's',
         long = "min-size",
@@ -61,6 +274,39 @@ pub struct Cli {
     )]
     pub min_size: u64,
 
+    #[arg(
+        long = "skip-empty",
+        help = "Ignore zero-length files, which all match each other but free no space, whatever --min-size is"
+    )]
+    pub skip_empty: bool,
+
+    #[arg(
+        short = 'S',
+        long = "max-size",
+        help = "Maximum file size in bytes to consider"
+    )]
+    pub max_size: Option<u64>,
+
+    #[arg(
+        long = "min-group-size",
+        help = "Only report groups with at least this many files (the default of 2 reports every pair)",
+        default_value = "2",
+        value_parser = clap::value_parser!(u64).range(2..)
+    )]
+    pub min_group_size: u64,
+
+    #[arg(
+        long = "min-nlink",
+        help = "Skip files with fewer hard links than this (unix only)"
+    )]
+    pub min_nlink: Option<u64>,
+
+    #[arg(
+        long = "max-nlink",
+        help = "Skip files with more hard links than this, e.g. 1 to ignore already-linked files (unix only)"
+    )]
+    pub max_nlink: Option<u64>,
+
     #[arg(
         short = 'd',
         long = "max-depth",
@@ -75,14 +321,88 @@ pub struct Cli {
     )]
     pub follow_symlinks: bool,
 
+    #[arg(
+        long = "resolve-symlinks-in-output",
+        help = "Show the real path next to files reached through a symlink"
+    )]
+    pub resolve_symlinks_in_output: bool,
+
     #[arg(
         short = 'i',
         long = "ignore",
-        help = "Ignore files containing this pattern in their name",
+        help = "Skip files and directories matching this gitignore-style pattern, relative to each scanned path, e.g. '*.tmp' or 'build/' (see --glob-exclude for globs on the absolute path)",
         action = clap::ArgAction::Append
     )]
     pub ignore_patterns: Vec<String>,
 
+    #[arg(
+        long = "hidden",
+        help = "Also scan hidden files and directories (names starting with '.'), which are skipped by default"
+    )]
+    pub hidden: bool,
+
+    #[arg(
+        long = "no-ignore",
+        help = "Also scan files excluded by .gitignore, .ignore, and git's exclude files (by default they are skipped, .gitignore only inside a git repository)",
+        overrides_with = "respect_gitignore"
+    )]
+    pub no_ignore: bool,
+
+    #[arg(
+        long = "respect-gitignore",
+        help = "Skip files excluded by .gitignore and .ignore files, the default; undoes an earlier --no-ignore",
+        overrides_with = "no_ignore"
+    )]
+    pub respect_gitignore: bool,
+
+    #[arg(
+        long = "glob-exclude",
+        value_name = "GLOB",
+        help = "Skip files and directories whose absolute path matches this shell glob, where * also matches '/', e.g. '*.tmp' or '*/node_modules' (repeatable)",
+        action = clap::ArgAction::Append
+    )]
+    pub glob_excludes: Vec<String>,
+
+    #[arg(
+        long = "modified-after",
+        value_name = "TIME",
+        help = "Only scan files modified after TIME: an RFC 3339 timestamp or an age such as 7d or 12h"
+    )]
+    pub modified_after: Option<String>,
+
+    #[arg(
+        long = "modified-before",
+        value_name = "TIME",
+        help = "Only scan files modified before TIME: an RFC 3339 timestamp or an age such as 7d or 12h"
+    )]
+    pub modified_before: Option<String>,
+
+    #[arg(
+        long = "ext",
+        value_name = "EXT",
+        help = "Only scan files with one of these extensions, case-insensitive (comma-separated, repeatable)",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    pub include_extensions: Vec<String>,
+
+    #[arg(
+        long = "exclude-ext",
+        value_name = "EXT",
+        help = "Skip files with any of these extensions, case-insensitive (comma-separated, repeatable)",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    pub exclude_extensions: Vec<String>,
+
+    #[arg(
+        long = "include-type",
+        value_name = "MIME",
+        help = "Only scan files whose content is of this MIME type, e.g. image/jpeg, image/* or text/plain (repeatable)",
+        action = clap::ArgAction::Append
+    )]
+    pub include_types: Vec<String>,
+
     #[arg(
         short = 'q',
         long = "quiet",
@@ -90,6 +410,34 @@ pub struct Cli {
     )]
     pub quiet: bool,
 
+    #[arg(
+        long = "progress-format",
+        value_enum,
+        default_value = "bar",
+        help = "How progress is reported on stderr (--quiet turns it off entirely)"
+    )]
+    pub progress_format: ProgressFormat,
+
+    #[arg(
+        long = "verbose-errors",
+        help = "Print each file skipped because of an error to stderr as it happens"
+    )]
+    pub verbose_errors: bool,
+
+    #[arg(
+        long = "ordered",
+        help = "Hash candidates in path order, in fixed-size batches, so an interrupted scan reports the same partial results on every run and at any thread count (see --sort-by for report order)"
+    )]
+    pub ordered: bool,
+
+    #[arg(
+        long = "sort-by",
+        help = "Order in which duplicate groups are reported",
+        value_enum,
+        default_value = "wasted"
+    )]
+    pub sort_by: SortChoice,
+
     #[arg(
         short = 'j',
         long = "threads",
@@ -97,6 +445,19 @@ pub struct Cli {
     )]
     pub threads: Option<usize>,
 
+    #[arg(
+        long = "auto-threads",
+        help = "Pick the hashing thread count from the storage type: few for spinning disks, many for SSDs (Linux only)",
+        conflicts_with = "threads"
+    )]
+    pub auto_threads: bool,
+
+    #[arg(
+        long = "max-open-files",
+        help = "Maximum number of files open at once while hashing (default: half the soft descriptor limit)"
+    )]
+    pub max_open_files: Option<usize>,
+
     #[arg(
         long = "partial-hash-size",
         help = "Size in bytes for partial hash",
@@ -104,6 +465,14 @@ pub struct Cli {
     )]
     pub partial_hash_size: u64,
 
+    #[arg(
+        long = "partial-mode",
+        help = "Which bytes the partial hash samples before files are hashed in full",
+        value_enum,
+        default_value = "head"
+    )]
+    pub partial_mode: PartialModeChoice,
+
     #[arg(
         long = "mmap-threshold",
         help = "File size threshold for using memory mapping",
@@ -120,31 +489,214 @@ pub struct Cli {
     )]
     pub hash_algorithm: HashAlgorithmChoice,
 
+    #[arg(
+        long = "match-by",
+        help = "What files must share to be grouped as duplicates",
+        value_enum,
+        default_value = "content"
+    )]
+    pub match_by: MatchByChoice,
+
+    #[arg(
+        long = "ignore-bom",
+        help = "Ignore a leading UTF-8/UTF-16 byte-order mark on text files when comparing content"
+    )]
+    pub ignore_bom: bool,
+
+    #[arg(
+        long = "same-extension-only",
+        help = "Only group identical files that also share a file extension (case-insensitive)"
+    )]
+    pub same_extension_only: bool,
+
+    #[arg(
+        long = "compare-threshold",
+        help = "Compare files byte by byte instead of hashing when fewer than N candidates share a size (0 always hashes)",
+        value_name = "N",
+        default_value = "0"
+    )]
+    pub compare_threshold: usize,
+
+    #[arg(
+        long = "keep-hardlinks",
+        help = "List every hardlinked path as a duplicate instead of counting paths to one inode once"
+    )]
+    pub keep_hardlinks: bool,
+
+    #[arg(
+        long = "verify",
+        help = "Compare files byte by byte after hashing so hash collisions are never reported as duplicates"
+    )]
+    pub verify: bool,
+
+    #[arg(
+        long = "bloom",
+        help = "Pre-filter partial hashes with a bloom filter to cut memory on scans with many unique candidates"
+    )]
+    pub bloom: bool,
+
     #[arg(
         long = "no-cross-filesystem",
         help = "Do not cross filesystem boundaries"
     )]
     pub no_cross_filesystem: bool,
 
+    #[arg(
+        long = "link-only-same-fs",
+        help = "Split duplicate groups that span filesystems so every reported group can be hardlinked"
+    )]
+    pub link_only_same_fs: bool,
+
     #[arg(
         short = 'c',
         long = "cache",
-        help = "Cache file path for resume capability"
+        help = "Cache file path for resume capability (sets both --cache-in and --cache-out)"
     )]
     pub cache_file: Option<PathBuf>,
 
+    #[arg(
+        long = "cache-in",
+        value_name = "FILE",
+        help = "Load cached hashes from this file, which is never written (overrides --cache for loading)"
+    )]
+    pub cache_in: Option<PathBuf>,
+
+    #[arg(
+        long = "cache-out",
+        value_name = "FILE",
+        help = "Save the updated cache to this file (overrides --cache for saving)"
+    )]
+    pub cache_out: Option<PathBuf>,
+
+    #[arg(
+        long = "cache-max-age",
+        value_name = "AGE",
+        help = "Ignore caches older than AGE, such as 12h or 7d; 0 never expires them [default: 24h]"
+    )]
+    pub cache_max_age: Option<String>,
+
+    #[arg(
+        long = "cache-format",
+        value_enum,
+        help = "Format for writing the cache (default: binary if the file name ends in .bin, otherwise JSON); both are read. A .gz suffix also compresses it"
+    )]
+    pub cache_format: Option<CacheFormatChoice>,
+
     #[arg(
         long = "incremental",
         help = "Perform incremental scan using cached data"
     )]
     pub incremental: bool,
 
+    #[arg(
+        long = "find-prefixes",
+        help = "Also report files whose entire content is a prefix of a larger file"
+    )]
+    pub find_prefixes: bool,
+
+    #[cfg(feature = "chunk-dedup")]
+    #[arg(
+        long = "chunk-dedup",
+        help = "Also report different files sharing large content-defined chunks (reads every file)"
+    )]
+    pub chunk_dedup: bool,
+
+    #[cfg(feature = "chunk-dedup")]
+    #[arg(
+        long = "chunk-threshold",
+        help = "Minimum fraction of the smaller file covered by shared chunks to report a pair, from 0 to 1",
+        default_value = "0.5",
+        value_parser = parse_fraction,
+        requires = "chunk_dedup"
+    )]
+    pub chunk_threshold: f64,
+
+    #[arg(
+        long = "trust-dir-mtimes",
+        help = "With --incremental, skip walking directories whose mtime is unchanged since the cached scan"
+    )]
+    pub trust_dir_mtimes: bool,
+
+    #[arg(
+        long,
+        value_name = "BASE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ".",
+        help = "Show paths relative to BASE (default: the current directory); paths outside BASE stay absolute"
+    )]
+    pub relative: Option<PathBuf>,
+
+    #[arg(
+        long = "no-color",
+        help = "Disable colored text output (it is already off when stdout is not a terminal)"
+    )]
+    pub no_color: bool,
+
+    #[arg(
+        long = "top",
+        value_name = "N",
+        help = "Only show the N groups wasting the most space in text, tree and markdown output; summary statistics still cover the whole scan, and other formats, --emit-script and --json-per-group list every group"
+    )]
+    pub top: Option<usize>,
+
     #[arg(
         long = "summary-only",
-        help = "Show only summary statistics, not detailed duplicate groups"
+        help = "Show only summary statistics, not detailed duplicate groups (text, json, ndjson, csv, tree, and markdown output)"
     )]
     pub summary_only: bool,
 
+    #[arg(
+        long = "fail-on-duplicates",
+        help = "Exit with status 2 when any duplicate group is found, e.g. to fail a CI job"
+    )]
+    pub fail_on_duplicates: bool,
+
+    #[arg(
+        long = "any",
+        visible_alias = "exists",
+        help = "Stop at the first confirmed duplicate group and report only it; exits with status 2 if one was found"
+    )]
+    pub any: bool,
+
+    #[arg(
+        long = "hardlink-report",
+        help = "Instead of listing duplicates, report how much hardlinking them would reclaim per filesystem (read-only)",
+        conflicts_with = "interactive"
+    )]
+    pub hardlink_report: bool,
+
+    #[arg(
+        long = "report-savings-only",
+        help = "Print only the reclaimable byte count as a single integer",
+        conflicts_with_all = ["interactive", "hardlink_report", "json_per_group"]
+    )]
+    pub report_savings_only: bool,
+
+    #[arg(
+        long = "savings-mode",
+        value_enum,
+        default_value = "delete",
+        help = "How --report-savings-only reclaims space"
+    )]
+    pub savings_mode: SavingsChoice,
+
+    #[arg(
+        long = "emit-script",
+        value_name = "FILE",
+        help = "Write a shell script (PowerShell on Windows) removing all but the kept file of each group, chosen by --keep, instead of listing duplicates",
+        conflicts_with_all = ["interactive", "hardlink_report", "json_per_group", "report_savings_only"]
+    )]
+    pub emit_script: Option<PathBuf>,
+
+    #[arg(
+        long = "json-per-group",
+        value_name = "DIR",
+        help = "Write each duplicate group as its own JSON file (group-0001.json, ...) into DIR, which must be empty or absent",
+        conflicts_with_all = ["interactive", "hardlink_report"]
+    )]
+    pub json_per_group: Option<PathBuf>,
+
     #[arg(
         short = 'f',
         long = "format",
@@ -157,28 +709,474 @@ pub struct Cli {
     #[arg(
         short = 'o',
         long = "output",
-        help = "Output file path (stdout if not specified)"
+        help = "Output file path (stdout if not specified or '-')"
     )]
     pub output_file: Option<PathBuf>,
 
+    #[arg(
+        long = "history",
+        help = "Append a summary record of this run to a JSON-lines history file"
+    )]
+    pub history_file: Option<PathBuf>,
+
+    #[arg(
+        long = "keep",
+        help = "Which file represents each group in output and is kept when deleting",
+        value_enum
+    )]
+    pub keep: Option<KeepChoice>,
+
+    #[arg(
+        long = "prefer",
+        value_name = "DIR",
+        help = "In interactive mode, keep each group's copy from the first of these directories that holds one and delete the rest (repeatable, highest priority first)",
+        action = clap::ArgAction::Append,
+        requires = "interactive"
+    )]
+    pub prefer_dirs: Vec<PathBuf>,
+
+    #[arg(
+        long = "keep-regex",
+        value_name = "PATTERN",
+        help = "In interactive mode, keep every file whose path matches this regex and delete the rest of its group; groups with no match are skipped",
+        requires = "interactive"
+    )]
+    pub keep_regex: Option<String>,
+
+    #[arg(
+        long = "prefer-keep-glob",
+        help = "When auto-deleting, prefer keeping files whose path matches this glob (repeatable)",
+        action = clap::ArgAction::Append
+    )]
+    pub prefer_keep_globs: Vec<String>,
+
+    #[arg(
+        long = "stdin-name",
+        value_name = "NAME",
+        help = "Read content from stdin and report files in the scanned paths identical to it, labelled NAME",
+        conflicts_with = "interactive"
+    )]
+    pub stdin_name: Option<String>,
+
+    #[arg(
+        long = "confirmations",
+        value_enum,
+        default_value = "full",
+        requires_if("none", "force"),
+        help = "How many prompts guard bulk deletes in interactive mode"
+    )]
+    pub confirmations: ConfirmationChoice,
+
+    #[arg(
+        long = "on-delete-error",
+        value_enum,
+        default_value = "continue",
+        help = "What to do when deleting a file fails in interactive mode"
+    )]
+    pub on_delete_error: DeleteErrorChoice,
+
+    #[arg(
+        long = "dry-run",
+        help = "In interactive mode, show what each action would delete or link without changing anything (with --undo, what would be restored)"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long = "trash",
+        help = "Move files deleted in interactive mode to the system trash instead of removing them"
+    )]
+    pub trash: bool,
+
+    #[arg(
+        long = "force",
+        help = "Allow --confirmations none to delete without any prompt"
+    )]
+    pub force: bool,
+
+    #[arg(
+        long = "interactive-theme",
+        value_enum,
+        default_value = "colorful",
+        help = "Prompt theme for interactive mode"
+    )]
+    pub interactive_theme: ThemeChoice,
+
+    #[arg(
+        long = "risky-dir",
+        help = "Directory whose duplicates are classed as risky in tiers output (repeatable, replaces the built-in list)",
+        action = clap::ArgAction::Append
+    )]
+    pub risky_dirs: Vec<PathBuf>,
+
     #[arg(
         long = "interactive",
         help = "Interactive mode for duplicate resolution"
     )]
     pub interactive: bool,
+
+    #[arg(
+        long = "undo",
+        value_name = "JOURNAL",
+        help = "Restore the trashed or quarantined files recorded in an undo journal instead of scanning, and list any that were deleted for good; honors --dry-run",
+        conflicts_with_all = ["paths", "paths_from", "interactive", "quarantine", "stdin_name"]
+    )]
+    pub undo: Option<PathBuf>,
+
+    #[arg(
+        long = "quarantine",
+        value_name = "DIR",
+        help = "Move every duplicate but the file chosen by --keep into DIR, under its original path, with a manifest for restoring; honors --dry-run and --confirmations",
+        conflicts_with_all = ["interactive", "hardlink_report", "report_savings_only", "emit_script", "json_per_group", "stdin_name"]
+    )]
+    pub quarantine: Option<PathBuf>,
+
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long = "reflink",
+        help = "Replace each duplicate with a copy-on-write clone of the file chosen by --keep (btrfs, XFS); honors --dry-run and --confirmations",
+        conflicts_with_all = ["interactive", "hardlink_report", "report_savings_only", "emit_script", "json_per_group", "stdin_name", "quarantine"]
+    )]
+    pub reflink: bool,
+}
+
+// An absolute RFC 3339 timestamp, or an age such as `7d` or `2h 30m` counted back from now
+fn parse_time_bound(flag: &str, value: &str) -> Result<SystemTime> {
+    if let Ok(time) = humantime::parse_rfc3339_weak(value) {
+        return Ok(time);
+    }
+    match humantime::parse_duration(value) {
+        Ok(age) => SystemTime::now()
+            .checked_sub(age)
+            .ok_or_else(|| anyhow::anyhow!("{} {}: age reaches too far into the past", flag, value)),
+        Err(_) => bail!(
+            "{} {}: expected an RFC 3339 timestamp like 2024-01-31T12:00:00Z or an age like 7d",
+            flag,
+            value
+        ),
+    }
+}
+
+// Parses an enum setting from the config file with the same spellings the flag accepts
+fn parse_choice<T: ValueEnum>(key: &str, value: &str) -> Result<T> {
+    T::from_str(value, true).map_err(|_| {
+        let accepted: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value().map(|p| p.get_name().to_string()))
+            .collect();
+        anyhow::anyhow!("config key '{}': invalid value '{}' (expected one of: {})", key, value, accepted.join(", "))
+    })
+}
+
+// Value parser for flags that take a fraction from 0 to 1
+#[cfg(feature = "chunk-dedup")]
+fn parse_fraction(value: &str) -> std::result::Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(fraction) if (0.0..=1.0).contains(&fraction) => Ok(fraction),
+        _ => Err(format!("expected a fraction from 0 to 1, got '{}'", value)),
+    }
 }
 
 impl Cli {
-    pub fn to_scan_config(&self) -> ScanConfig {
-        let paths = if self.paths.is_empty() {
-            vec![PathBuf::from(".")]
+    // Precedence is built-in defaults < config file < command line. `matches` tells which
+    // flags were typed, since a clap default is otherwise indistinguishable from an explicit
+    // value. Config lists replace rather than extend the defaults, and are themselves replaced
+    // by any use of the flag.
+    pub fn apply_config_file(&mut self, matches: &ArgMatches) -> Result<()> {
+        let adapter = ConfigFileAdapter::new();
+        let Some(path) = self.config.clone().or_else(|| adapter.default_path()) else {
+            return Ok(());
+        };
+        let file: ConfigFile = adapter.load_config(&path)?;
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+        if let Some(value) = &file.algorithm
+            && unset("hash_algorithm")
+        {
+            self.hash_algorithm = parse_choice("algorithm", value)?;
+        }
+        if let Some(value) = &file.match_by
+            && unset("match_by")
+        {
+            self.match_by = parse_choice("match-by", value)?;
+        }
+        if let Some(value) = &file.sort_by
+            && unset("sort_by")
+        {
+            self.sort_by = parse_choice("sort-by", value)?;
+        }
+        if let Some(value) = &file.partial_mode
+            && unset("partial_mode")
+        {
+            self.partial_mode = parse_choice("partial-mode", value)?;
+        }
+        if let Some(value) = &file.keep
+            && unset("keep")
+        {
+            self.keep = Some(parse_choice("keep", value)?);
+        }
+
+        if let Some(value) = file.min_size
+            && unset("min_size")
+        {
+            self.min_size = value;
+        }
+        if let Some(value) = file.max_size
+            && unset("max_size")
+        {
+            self.max_size = Some(value);
+        }
+        if let Some(value) = file.min_group_size
+            && unset("min_group_size")
+        {
+            if value < 2 {
+                bail!("config key 'min-group-size': {} is below the minimum of 2", value);
+            }
+            self.min_group_size = value;
+        }
+        if let Some(value) = file.min_nlink
+            && unset("min_nlink")
+        {
+            self.min_nlink = Some(value);
+        }
+        if let Some(value) = file.max_nlink
+            && unset("max_nlink")
+        {
+            self.max_nlink = Some(value);
+        }
+        if let Some(value) = file.max_depth
+            && unset("max_depth")
+        {
+            self.max_depth = Some(value);
+        }
+        if let Some(value) = file.threads
+            && unset("threads")
+            && unset("auto_threads")
+        {
+            self.threads = Some(value);
+        }
+        if let Some(value) = file.auto_threads
+            && unset("auto_threads")
+            && unset("threads")
+        {
+            self.auto_threads = value;
+        }
+        if let Some(value) = file.max_open_files
+            && unset("max_open_files")
+        {
+            self.max_open_files = Some(value);
+        }
+        if let Some(value) = file.partial_hash_size
+            && unset("partial_hash_size")
+        {
+            self.partial_hash_size = value;
+        }
+        if let Some(value) = file.mmap_threshold
+            && unset("mmap_threshold")
+        {
+            self.mmap_threshold = value;
+        }
+        if let Some(value) = file.compare_threshold
+            && unset("compare_threshold")
+        {
+            self.compare_threshold = value;
+        }
+        if let Some(value) = &file.cache
+            && unset("cache_file")
+        {
+            self.cache_file = Some(value.clone());
+        }
+        if let Some(value) = &file.cache_max_age
+            && unset("cache_max_age")
+        {
+            self.cache_max_age = Some(value.clone());
+        }
+
+        // Boolean flags can only be switched on from the command line, so a config `true`
+        // can't be overridden there; a config `false` just leaves the default in place
+        let flags = [
+            (file.follow_symlinks, "follow_symlinks", &mut self.follow_symlinks),
+            (file.link_only_same_fs, "link_only_same_fs", &mut self.link_only_same_fs),
+            (file.same_extension_only, "same_extension_only", &mut self.same_extension_only),
+            (file.ignore_bom, "ignore_bom", &mut self.ignore_bom),
+            (file.keep_hardlinks, "keep_hardlinks", &mut self.keep_hardlinks),
+            (file.verify, "verify", &mut self.verify),
+            (file.bloom, "bloom", &mut self.bloom),
+            (file.incremental, "incremental", &mut self.incremental),
+            (file.quiet, "quiet", &mut self.quiet),
+            (file.verbose_errors, "verbose_errors", &mut self.verbose_errors),
+            (file.ordered, "ordered", &mut self.ordered),
+            (file.hidden, "hidden", &mut self.hidden),
+            (file.skip_empty, "skip_empty", &mut self.skip_empty),
+            (file.cross_filesystem.map(|cross| !cross), "no_cross_filesystem", &mut self.no_cross_filesystem),
+        ];
+        for (value, id, flag) in flags {
+            if let Some(value) = value
+                && unset(id)
+            {
+                *flag = value;
+            }
+        }
+
+        // --respect-gitignore exists to undo a `no-ignore = true` from the config file
+        if let Some(value) = file.no_ignore
+            && unset("no_ignore")
+            && unset("respect_gitignore")
+        {
+            self.no_ignore = value;
+        }
+
+        let lists = [
+            (&file.ignore, "ignore_patterns", &mut self.ignore_patterns),
+            (&file.glob_exclude, "glob_excludes", &mut self.glob_excludes),
+            (&file.ext, "include_extensions", &mut self.include_extensions),
+            (&file.exclude_ext, "exclude_extensions", &mut self.exclude_extensions),
+            (&file.include_type, "include_types", &mut self.include_types),
+            (&file.prefer_keep_glob, "prefer_keep_globs", &mut self.prefer_keep_globs),
+        ];
+        for (value, id, list) in lists {
+            if let Some(value) = value
+                && unset(id)
+            {
+                *list = value.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_output_path(&self) -> Result<()> {
+        // Checked here rather than with clap so a --match-by from the config file counts too
+        if !matches!(self.match_by, MatchByChoice::Content) {
+            #[cfg(target_os = "linux")]
+            let reflink = self.reflink;
+            #[cfg(not(target_os = "linux"))]
+            let reflink = false;
+            let action = if self.interactive {
+                Some("--interactive")
+            } else if self.quarantine.is_some() {
+                Some("--quarantine")
+            } else if reflink {
+                Some("--reflink")
+            } else if self.emit_script.is_some() {
+                Some("--emit-script")
+            } else {
+                None
+            };
+            if let Some(action) = action {
+                bail!(
+                    "{} acts on duplicates, but --match-by {} groups files without comparing their contents",
+                    action,
+                    MatchMode::from(self.match_by.clone()).as_str()
+                );
+            }
+        }
+
+        if let Some(dir) = &self.json_per_group {
+            if dir.exists() && !dir.is_dir() {
+                bail!("JSON group directory '{}' is not a directory", dir.display());
+            }
+            if dir.is_dir() && std::fs::read_dir(dir)?.next().is_some() {
+                bail!("JSON group directory '{}' is not empty; refusing to mix with existing files", dir.display());
+            }
+        }
+
+        let Some(path) = self.output_file.as_ref().filter(|p| p.as_os_str() != "-") else {
+            if matches!(self.output_format, OutputFormat::Sqlite) {
+                bail!("--format sqlite writes a database file and needs --output FILE");
+            }
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+            && !parent.is_dir()
+        {
+            bail!(
+                "Output directory '{}' does not exist (needed for '{}')",
+                parent.display(),
+                path.display()
+            );
+        }
+
+        if path.is_dir() {
+            bail!("Output path '{}' is a directory", path.display());
+        }
+
+        Ok(())
+    }
+
+    // How many groups `--top` lets the output show. It only trims what a person reads: exports,
+    // reports and deletion scripts always cover every group.
+    pub fn display_limit(&self) -> Option<usize> {
+        let display_format = matches!(self.output_format, OutputFormat::Text | OutputFormat::Tree | OutputFormat::Markdown);
+        let export = self.report_savings_only || self.hardlink_report || self.emit_script.is_some() || self.json_per_group.is_some();
+        if display_format && !export { self.top } else { None }
+    }
+
+    // One path per line; trailing whitespace and blank lines are ignored, missing paths are not
+    fn read_path_list(&self, list: &Path) -> Result<Vec<PathBuf>> {
+        let contents = if list == Path::new("-") {
+            if self.stdin_name.is_some() {
+                bail!("--paths-from - and --stdin-name can't both read stdin");
+            }
+            std::io::read_to_string(std::io::stdin())?
         } else {
-            self.paths.clone()
+            std::fs::read_to_string(list)
+                .map_err(|e| anyhow::anyhow!("Failed to read path list '{}': {}", list.display(), e))?
         };
 
+        let mut paths = Vec::new();
+        for line in contents.lines().map(str::trim_end).filter(|line| !line.is_empty()) {
+            let path = PathBuf::from(line);
+            if !path.exists() {
+                bail!("Path '{}' listed in '{}' does not exist", path.display(), list.display());
+            }
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    pub fn to_scan_config(&self) -> Result<ScanConfig> {
+        if let Some(max_size) = self.max_size
+            && max_size < self.min_size
+        {
+            bail!("--max-size ({}) is smaller than --min-size ({}); no file could match", max_size, self.min_size);
+        }
+
+        let modified_after = self.modified_after.as_deref().map(|v| parse_time_bound("--modified-after", v)).transpose()?;
+        let modified_before = self.modified_before.as_deref().map(|v| parse_time_bound("--modified-before", v)).transpose()?;
+        if let (Some(after), Some(before)) = (modified_after, modified_before)
+            && after >= before
+        {
+            bail!("--modified-after must be earlier than --modified-before; no file could match");
+        }
+
+        let cache_max_age = self
+            .cache_max_age
+            .as_deref()
+            .map(|age| {
+                humantime::parse_duration(age)
+                    .map_err(|_| anyhow::anyhow!("--cache-max-age {}: expected an age like 12h or 7d, or 0", age))
+            })
+            .transpose()?;
+
+        let mut paths = self.paths.clone();
+        if let Some(list) = &self.paths_from {
+            paths.extend(self.read_path_list(list)?);
+            if paths.is_empty() {
+                bail!("No paths to scan: '{}' lists none", list.display());
+            }
+        } else if paths.is_empty() {
+            paths.push(PathBuf::from("."));
+        }
+
         let mut config = ScanConfig::new()
             .with_paths(paths)
             .with_min_size(self.min_size)
+            .with_max_size(self.max_size)
+            .with_extension_filters(&self.include_extensions, &self.exclude_extensions)
+            .with_modified_range(modified_after, modified_before)
+            .with_nlink_range(self.min_nlink, self.max_nlink)
             .with_follow_symlinks(self.follow_symlinks);
 
         if let Some(max_depth) = self.max_depth {
@@ -186,14 +1184,109 @@ impl Cli {
         }
 
         config.ignore_patterns.extend(self.ignore_patterns.iter().cloned());
+        config.exclude_globs = self.glob_excludes.clone();
+        config.respect_ignore_files = !self.no_ignore;
+        config.include_hidden = self.hidden;
+        config.skip_empty = self.skip_empty;
+        config.include_types = self.include_types.clone();
+        config.resolve_symlinks = self.resolve_symlinks_in_output;
         config.partial_hash_size = self.partial_hash_size;
+        config.partial_mode = self.partial_mode.clone().into();
         config.use_mmap_threshold = self.mmap_threshold;
         config.thread_count = self.threads;
+        config.auto_threads = self.auto_threads;
+        config.max_open_files = self.max_open_files;
         config.hash_algorithm = self.hash_algorithm.clone().into();
         config.cross_filesystem = !self.no_cross_filesystem;
-        config.cache_file = self.cache_file.clone();
+        config.link_only_same_fs = self.link_only_same_fs;
+        config.same_extension_only = self.same_extension_only;
+        config.ignore_bom = self.ignore_bom;
+        config.bloom_prefilter = self.bloom;
+        config.verify_bytes = self.verify;
+        config.min_group_size = self.min_group_size as usize;
+        config.keep_hardlinks = self.keep_hardlinks;
+        config.compare_threshold = self.compare_threshold;
+        config.verbose_errors = self.verbose_errors;
+        config.ordered = self.ordered;
+        config.sort_by = self.sort_by.clone().into();
+        config.stop_at_first = self.any;
+        config = config
+            .with_cache_in(self.cache_in.clone().or_else(|| self.cache_file.clone()))
+            .with_cache_out(self.cache_out.clone().or_else(|| self.cache_file.clone()));
+        config.cache_max_age = cache_max_age;
         config.incremental = self.incremental;
+        config.trust_dir_mtimes = self.trust_dir_mtimes;
+        config.find_prefixes = self.find_prefixes;
+        #[cfg(feature = "chunk-dedup")]
+        {
+            config.chunk_dedup = self.chunk_dedup;
+            config.chunk_similarity_threshold = self.chunk_threshold;
+        }
+        config.match_by = self.match_by.clone().into();
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::parse_from(std::iter::once("rdupe").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn top_only_limits_display_formats() {
+        for format in ["text", "tree", "markdown"] {
+            assert_eq!(parse(&["--top", "3", "--format", format]).display_limit(), Some(3), "{}", format);
+        }
+        assert_eq!(parse(&["--top", "3", "--format", "json", "-o", "out.json"]).display_limit(), None);
+        assert_eq!(parse(&["--top", "3", "--format", "csv"]).display_limit(), None);
+        assert_eq!(parse(&["--top", "3", "--emit-script", "rm.sh"]).display_limit(), None);
+        assert_eq!(parse(&["--format", "text"]).display_limit(), None);
+    }
 
-        config
+    #[test]
+    fn hidden_flag_includes_hidden_files() {
+        assert!(!parse(&[]).to_scan_config().unwrap().include_hidden);
+        assert!(parse(&["--hidden"]).to_scan_config().unwrap().include_hidden);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn output_under_a_missing_directory_is_rejected_before_scanning() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("missing").join("deeper").join("out.json");
+        let error = parse(&["-o", nested.to_str().unwrap()]).validate_output_path().unwrap_err();
+        assert!(error.to_string().contains("does not exist"), "{}", error);
+
+        let existing = dir.path().join("out.json");
+        assert!(parse(&["-o", existing.to_str().unwrap()]).validate_output_path().is_ok());
+        assert!(parse(&["-o", dir.path().to_str().unwrap()]).validate_output_path().is_err());
+        assert!(parse(&["-o", "-"]).validate_output_path().is_ok());
+    }
+
+    #[test]
+    fn approximate_match_modes_refuse_destructive_actions() {
+        for mode in ["name", "name-size"] {
+            for action in [&["--interactive"][..], &["--quarantine", "q"], &["--emit-script", "rm.sh"]] {
+                let args: Vec<&str> = ["--match-by", mode].iter().chain(action).copied().collect();
+                let error = parse(&args).validate_output_path().unwrap_err();
+                assert!(error.to_string().contains(action[0]), "{}", error);
+            }
+            assert!(parse(&["--match-by", mode]).validate_output_path().is_ok());
+        }
+        assert!(parse(&["--match-by", "content", "--interactive"]).validate_output_path().is_ok());
+    }
+
+    #[cfg(feature = "chunk-dedup")]
+    #[test]
+    fn chunk_threshold_must_be_a_fraction() {
+        for valid in ["0", "0.5", "1"] {
+            assert!(Cli::try_parse_from(["rdupe", "--chunk-dedup", "--chunk-threshold", valid]).is_ok(), "{}", valid);
+        }
+        for invalid in ["-0.1", "1.5", "NaN", "half"] {
+            assert!(Cli::try_parse_from(["rdupe", "--chunk-dedup", "--chunk-threshold", invalid]).is_err(), "{}", invalid);
+        }
+    }
+}
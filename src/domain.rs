@@ -1,7 +1,11 @@
+use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::PathBuf;
-use std::time::SystemTime;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum HashAlgorithm {
@@ -31,6 +35,24 @@ impl HashAlgorithm {
             HashAlgorithm::Sha1 => "sha1",
         }
     }
+
+    pub fn digest_hex_len(&self) -> usize {
+        match self {
+            HashAlgorithm::XxHash64
+            | HashAlgorithm::XxHash3
+            | HashAlgorithm::WyHash
+            | HashAlgorithm::TwoXHash64 => 16,
+            HashAlgorithm::Blake3 | HashAlgorithm::Sha256 => 64,
+            HashAlgorithm::Md5 => 32,
+            HashAlgorithm::Sha1 => 40,
+        }
+    }
+
+    pub fn is_valid_digest(&self, digest: &str) -> bool {
+        // Every digest is zero-padded to its full width, so an unpadded 64-bit digest written by
+        // an older version is rejected and its file re-hashed
+        digest.len() == self.digest_hex_len() && digest.bytes().all(|b| b.is_ascii_hexdigit())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -40,6 +62,21 @@ pub struct FileMetadata {
     pub partial_hash: Option<String>,
     pub full_hash: Option<String>,
     pub modified: SystemTime,
+    // Hard link count, only known on unix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nlink: Option<u64>,
+    // Device id of the containing filesystem, only known on unix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dev: Option<u64>,
+    // Inode number, only known on unix; paths sharing `dev` and `ino` are hardlinks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ino: Option<u64>,
+    // Real location when the path was reached through a symlink, only recorded on request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<PathBuf>,
+    // Byte-order mark length excluded from comparison, only recorded with `ignore_bom`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bom_len: Option<u64>,
 }
 
 impl FileMetadata {
@@ -50,9 +87,54 @@ impl FileMetadata {
             partial_hash: None,
             full_hash: None,
             modified,
+            nlink: None,
+            dev: None,
+            ino: None,
+            symlink_target: None,
+            bom_len: None,
         }
     }
 
+    pub fn with_bom_len(mut self, bom_len: u64) -> Self {
+        self.bom_len = Some(bom_len);
+        self
+    }
+
+    // Size of the bytes actually compared, which excludes an ignored byte-order mark
+    pub fn content_size(&self) -> u64 {
+        self.size - self.content_offset()
+    }
+
+    // Where the compared bytes start: past an ignored byte-order mark, otherwise 0
+    pub fn content_offset(&self) -> u64 {
+        self.bom_len.unwrap_or(0)
+    }
+
+    pub fn with_symlink_target(mut self, target: PathBuf) -> Self {
+        self.symlink_target = Some(target);
+        self
+    }
+
+    pub fn with_dev(mut self, dev: u64) -> Self {
+        self.dev = Some(dev);
+        self
+    }
+
+    pub fn with_ino(mut self, ino: u64) -> Self {
+        self.ino = Some(ino);
+        self
+    }
+
+    // Identifies the physical file behind this path, when the platform reports one
+    pub fn inode_key(&self) -> Option<(u64, u64)> {
+        self.dev.zip(self.ino)
+    }
+
+    pub fn with_nlink(mut self, nlink: u64) -> Self {
+        self.nlink = Some(nlink);
+        self
+    }
+
     pub fn with_partial_hash(mut self, hash: String) -> Self {
         self.partial_hash = Some(hash);
         self
@@ -68,50 +150,573 @@ impl FileMetadata {
     }
 }
 
+// How a group's members were shown to match, weakest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum VerificationLevel {
+    // Only names (and possibly sizes) agree; no content was read
+    Metadata,
+    #[default]
+    FullHash,
+    // The bytes were compared directly
+    ByteVerified,
+}
+
+impl VerificationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationLevel::Metadata => "metadata only",
+            VerificationLevel::FullHash => "full hash",
+            VerificationLevel::ByteVerified => "byte comparison",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DuplicateSet {
     pub hash: String,
     pub files: Vec<FileMetadata>,
     pub total_size: u64,
+    // False when members live on different filesystems and so can't be hardlinked together.
+    // Results saved before this was recorded load as true, like a set with no device ids.
+    #[serde(default = "default_same_filesystem")]
+    pub same_filesystem: bool,
+    #[serde(default)]
+    pub verification: VerificationLevel,
+}
+
+fn default_same_filesystem() -> bool {
+    true
 }
 
 impl DuplicateSet {
     pub fn new(hash: String, files: Vec<FileMetadata>) -> Self {
         let total_size = files.iter().map(|f| f.size).sum();
+        let mut devices = files.iter().filter_map(|f| f.dev);
+        let same_filesystem = match devices.next() {
+            Some(first) => devices.all(|dev| dev == first),
+            None => true,
+        };
         Self {
             hash,
             files,
             total_size,
+            same_filesystem,
+            verification: VerificationLevel::default(),
         }
     }
 
+    pub fn with_verification(mut self, verification: VerificationLevel) -> Self {
+        self.verification = verification;
+        self
+    }
+
+    pub fn safety_tier(&self, risky_dirs: &[PathBuf]) -> SafetyTier {
+        let in_risky_dir = self.files.iter().any(|file| {
+            let path = std::path::absolute(&file.path).unwrap_or_else(|_| file.path.clone());
+            risky_dirs.iter().any(|dir| path.starts_with(dir))
+        });
+        if in_risky_dir {
+            return SafetyTier::Risky;
+        }
+
+        let mut names = self.files.iter().map(|f| f.path.file_name());
+        let first = names.next().flatten();
+        if first.is_some() && names.all(|name| name == first) {
+            SafetyTier::VerySafe
+        } else {
+            SafetyTier::Safe
+        }
+    }
+
+    // Splits into one set per filesystem, dropping members left without a twin on their device
+    pub fn split_by_filesystem(self) -> Vec<DuplicateSet> {
+        if self.same_filesystem {
+            return vec![self];
+        }
+
+        let mut by_device: HashMap<Option<u64>, Vec<FileMetadata>> = HashMap::new();
+        for file in self.files {
+            by_device.entry(file.dev).or_default().push(file);
+        }
+
+        by_device
+            .into_values()
+            .filter(|files| files.len() > 1)
+            .map(|files| DuplicateSet::new(self.hash.clone(), files).with_verification(self.verification))
+            .collect()
+    }
+
+    // Whether the members are the same byte for byte, so any one can stand in for another on
+    // disk. Name-matched sets were never read, and with `ignore_bom` members may differ in their
+    // byte-order marks: equal mark lengths mean equal marks, except for UTF-16's two byte orders.
+    pub fn is_byte_identical(&self) -> bool {
+        const UTF16_BOM_LEN: u64 = 2;
+        self.verification != VerificationLevel::Metadata
+            && self.files.windows(2).all(|pair| pair[0].bom_len == pair[1].bom_len)
+            && self.files.iter().all(|f| f.bom_len != Some(UTF16_BOM_LEN))
+    }
+
+    // A set needs at least two members to be a duplicate group
+    pub fn is_duplicate(&self) -> bool {
+        self.files.len() > 1
+    }
+
+    // Moves the file the policy keeps to the front and sorts the rest by path. Once ordered,
+    // `files[0]` is the representative everywhere: wasted space, output, and deletion.
+    pub fn order_by_representative(&mut self, policy: &KeepPolicy) {
+        let kept = self.canonical_file(policy).map(|f| f.path.clone());
+        self.files.sort_by(|a, b| {
+            (Some(&a.path) != kept.as_ref())
+                .cmp(&(Some(&b.path) != kept.as_ref()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+    }
+
+    pub fn representative(&self) -> Option<&FileMetadata> {
+        self.files.first()
+    }
+
+    // Splits into one set per file extension, dropping members left without a twin
+    pub fn split_by_extension(self) -> Vec<DuplicateSet> {
+        let mut by_extension: HashMap<Option<std::ffi::OsString>, Vec<FileMetadata>> = HashMap::new();
+        for file in self.files {
+            by_extension
+                .entry(file.path.extension().map(|e| e.to_ascii_lowercase()))
+                .or_default()
+                .push(file);
+        }
+
+        by_extension
+            .into_values()
+            .filter(|files| files.len() > 1)
+            .map(|files| DuplicateSet::new(self.hash.clone(), files).with_verification(self.verification))
+            .collect()
+    }
+
+    // Members with the first path of each inode only, so hardlinked paths count once
+    fn physical_files(&self) -> impl Iterator<Item = &FileMetadata> {
+        let mut seen = HashSet::new();
+        self.files.iter().filter(move |f| f.inode_key().is_none_or(|key| seen.insert(key)))
+    }
+
+    // Hardlinks to the kept file or to each other occupy no extra space, so they are not waste
     pub fn wasted_space(&self) -> u64 {
-        if self.files.len() <= 1 {
+        if !self.is_duplicate() {
             0
         } else {
-            self.total_size - self.files[0].size
+            self.physical_files().skip(1).map(|f| f.size).sum()
         }
     }
 
+    // Bytes freed by hardlinking every copy to one kept file per device; copies without a
+    // same-device twin can't be linked and free nothing
+    pub fn hardlink_savings(&self) -> u64 {
+        let mut by_device: HashMap<Option<u64>, Vec<u64>> = HashMap::new();
+        for file in self.physical_files() {
+            by_device.entry(file.dev).or_default().push(file.size);
+        }
+        by_device.values().map(|sizes| sizes.iter().skip(1).sum::<u64>()).sum()
+    }
+
+    // Size of a single copy
+    pub fn file_size(&self) -> u64 {
+        self.files.first().map_or(0, |f| f.size)
+    }
+
     pub fn duplicate_count(&self) -> usize {
         self.files.len().saturating_sub(1)
     }
+
+    pub fn canonical_file(&self, policy: &KeepPolicy) -> Option<&FileMetadata> {
+        let preferred: Vec<&FileMetadata> = match &policy.prefer_globs {
+            Some(globs) => self.files.iter().filter(|f| globs.is_match(&f.path)).collect(),
+            None => Vec::new(),
+        };
+
+        if preferred.is_empty() {
+            policy.strategy.select(self.files.iter())
+        } else {
+            policy.strategy.select(preferred.into_iter())
+        }
+    }
+
+    // The file to keep under a ranked list of directories: among the members under the first
+    // directory that holds any, the one the policy's strategy picks. None when no member lies
+    // under any of them. `dirs` are expected to be absolute; member paths are made absolute
+    // before comparing.
+    pub fn keep_by_directory_priority(&self, dirs: &[PathBuf], policy: &KeepPolicy) -> Option<&FileMetadata> {
+        let absolute: Vec<PathBuf> = self
+            .files
+            .iter()
+            .map(|f| std::path::absolute(&f.path).unwrap_or_else(|_| f.path.clone()))
+            .collect();
+        dirs.iter().find_map(|dir| {
+            let under = self
+                .files
+                .iter()
+                .zip(&absolute)
+                .filter(|(_, path)| path.starts_with(dir))
+                .map(|(file, _)| file);
+            policy.strategy.select(under)
+        })
+    }
+}
+
+// Locations where deleting a "duplicate" is likely to break the system or installed software
+pub const DEFAULT_RISKY_DIRS: &[&str] = &[
+    "/bin", "/boot", "/etc", "/lib", "/lib64", "/opt", "/sbin", "/usr", "/var/lib",
+    "/System", "/Library", "/Applications",
+    "C:\\Windows", "C:\\Program Files", "C:\\Program Files (x86)",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SafetyTier {
+    // Every copy has the same file name and none live in a risky directory
+    VerySafe,
+    // Names differ between copies but none live in a risky directory
+    Safe,
+    // At least one copy lives under a risky directory
+    Risky,
+}
+
+impl SafetyTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SafetyTier::VerySafe => "very safe",
+            SafetyTier::Safe => "safe",
+            SafetyTier::Risky => "risky",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SafetyTier::VerySafe => "identical name and content",
+            SafetyTier::Safe => "identical content, different names",
+            SafetyTier::Risky => "at least one copy in a system or application directory",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeepStrategy {
+    Newest,
+    Oldest,
+    MostLinked,
+    FirstAlphabetical,
+    LastAlphabetical,
+    ShortestPath,
+}
+
+impl KeepStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeepStrategy::Newest => "newest",
+            KeepStrategy::Oldest => "oldest",
+            KeepStrategy::MostLinked => "most hardlinked",
+            KeepStrategy::FirstAlphabetical => "first alphabetically",
+            KeepStrategy::LastAlphabetical => "last alphabetically",
+            KeepStrategy::ShortestPath => "shortest path",
+        }
+    }
+
+    // Ties are broken by path so the same group always yields the same kept file
+    fn select<'a>(&self, files: impl Iterator<Item = &'a FileMetadata>) -> Option<&'a FileMetadata> {
+        match self {
+            KeepStrategy::Newest => files.min_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path))),
+            KeepStrategy::Oldest => files.min_by(|a, b| a.modified.cmp(&b.modified).then_with(|| a.path.cmp(&b.path))),
+            // Equal link counts (or none known) fall back to keeping the newest
+            KeepStrategy::MostLinked => files.min_by(|a, b| {
+                b.nlink
+                    .cmp(&a.nlink)
+                    .then_with(|| b.modified.cmp(&a.modified))
+                    .then_with(|| a.path.cmp(&b.path))
+            }),
+            KeepStrategy::FirstAlphabetical => files.min_by(|a, b| a.path.cmp(&b.path)),
+            KeepStrategy::LastAlphabetical => files.max_by(|a, b| a.path.cmp(&b.path)),
+            // Fewest components wins, since deeply nested copies are usually the redundant ones;
+            // equal depths prefer the shorter path
+            KeepStrategy::ShortestPath => files.min_by(|a, b| {
+                a.path
+                    .components()
+                    .count()
+                    .cmp(&b.path.components().count())
+                    .then_with(|| a.path.as_os_str().len().cmp(&b.path.as_os_str().len()))
+                    .then_with(|| a.path.cmp(&b.path))
+            }),
+        }
+    }
+}
+
+// Serialized as its strategy and glob patterns; the compiled set is rebuilt on load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "KeepPolicySpec", try_from = "KeepPolicySpec")]
+pub struct KeepPolicy {
+    pub strategy: KeepStrategy,
+    pub prefer_globs: Option<GlobSet>,
+    pub prefer_patterns: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeepPolicySpec {
+    strategy: KeepStrategy,
+    prefer_globs: Vec<String>,
+}
+
+impl From<KeepPolicy> for KeepPolicySpec {
+    fn from(policy: KeepPolicy) -> Self {
+        Self {
+            strategy: policy.strategy,
+            prefer_globs: policy.prefer_patterns,
+        }
+    }
+}
+
+impl TryFrom<KeepPolicySpec> for KeepPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(spec: KeepPolicySpec) -> Result<Self> {
+        KeepPolicy::new(spec.strategy).with_prefer_globs(&spec.prefer_globs)
+    }
 }
 
-#[derive(Debug, Clone)]
+impl KeepPolicy {
+    pub fn new(strategy: KeepStrategy) -> Self {
+        Self {
+            strategy,
+            prefer_globs: None,
+            prefer_patterns: Vec::new(),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: KeepStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_prefer_globs(mut self, patterns: &[String]) -> Result<Self> {
+        self.prefer_patterns = patterns.to_vec();
+        if patterns.is_empty() {
+            self.prefer_globs = None;
+            return Ok(self);
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern).map_err(|e| anyhow::anyhow!("Invalid glob '{}': {}", pattern, e))?);
+        }
+        self.prefer_globs = Some(builder.build()?);
+        Ok(self)
+    }
+}
+
+impl Default for KeepPolicy {
+    fn default() -> Self {
+        Self::new(KeepStrategy::Newest)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MatchMode {
+    // Byte content, confirmed through partial and full hashing
+    #[default]
+    Content,
+    // Identical basename and size, no file contents are read
+    NameSize,
+    // Identical basename only, no file contents are read
+    Name,
+}
+
+impl MatchMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchMode::Content => "content",
+            MatchMode::NameSize => "name-size",
+            MatchMode::Name => "name",
+        }
+    }
+
+    pub fn is_approximate(&self) -> bool {
+        *self != MatchMode::Content
+    }
+}
+
+// Which bytes the partial-hash stage samples before full hashing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PartialMode {
+    // The first N bytes only
+    #[default]
+    Head,
+    // The first and last N bytes, for files that share long headers; files under 2N use head only
+    HeadTail,
+}
+
+// Order duplicate groups are reported in. Every key but `Path` puts the largest groups first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortKey {
+    #[default]
+    Wasted,
+    Size,
+    Count,
+    Path,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefixMatch {
+    pub prefix: FileMetadata,
+    pub container: FileMetadata,
+}
+
+// Two different files sharing content-defined chunks. `similarity` is the shared chunk bytes
+// divided by the size of the smaller file, so 1.0 means the smaller is fully contained.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkOverlap {
+    pub first: PathBuf,
+    pub second: PathBuf,
+    pub shared_bytes: u64,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ScanConfig {
     pub paths: Vec<PathBuf>,
     pub follow_symlinks: bool,
     pub min_size: u64,
+    pub max_size: Option<u64>,
+    // Drop zero-length files regardless of `min_size`. They are all trivially identical and
+    // would otherwise form one huge group that frees no space when cleaned up.
+    pub skip_empty: bool,
+    // Hard link count bounds, only enforced on unix where the count is available
+    pub min_nlink: Option<u64>,
+    pub max_nlink: Option<u64>,
     pub max_depth: Option<usize>,
+    #[serde(serialize_with = "serialize_sorted")]
     pub ignore_patterns: HashSet<String>,
+    // Shell-style globs matched against each entry's absolute path; `*` also crosses `/`.
+    // A matching directory is not descended into.
+    pub exclude_globs: Vec<String>,
+    // Skip what `.gitignore` (inside git repositories), `.ignore`, and the global git excludes
+    // file leave out, like git and ripgrep do
+    pub respect_ignore_files: bool,
+    // Walk into dot-directories and pick up dotfiles, which are skipped by default
+    pub include_hidden: bool,
+    // Lowercase extensions without the dot. A non-empty include set admits only files with one
+    // of these extensions; excluded extensions are dropped either way.
+    #[serde(serialize_with = "serialize_sorted")]
+    pub include_extensions: HashSet<String>,
+    #[serde(serialize_with = "serialize_sorted")]
+    pub exclude_extensions: HashSet<String>,
+    // Exclusive bounds on modification time
+    #[serde(with = "rfc3339")]
+    pub modified_after: Option<SystemTime>,
+    #[serde(with = "rfc3339")]
+    pub modified_before: Option<SystemTime>,
+    // MIME types (or `type/*` families) detected from file content; empty means no filter
+    pub include_types: Vec<String>,
+    pub resolve_symlinks: bool,
+    pub ignore_bom: bool,
     pub partial_hash_size: u64,
+    pub partial_mode: PartialMode,
     pub use_mmap_threshold: u64,
     pub thread_count: Option<usize>,
+    // Size the hashing pool from the storage type when `thread_count` isn't given
+    pub auto_threads: bool,
+    pub max_open_files: Option<usize>,
     pub hash_algorithm: HashAlgorithm,
     pub cross_filesystem: bool,
-    pub cache_file: Option<PathBuf>,
+    // Cache loaded before the scan and cache written after it; usually the same file
+    pub cache_in: Option<PathBuf>,
+    pub cache_out: Option<PathBuf>,
+    // Older caches are ignored; `None` means the 24-hour default and zero never expires
+    pub cache_max_age: Option<Duration>,
     pub incremental: bool,
+    pub find_prefixes: bool,
+    pub chunk_dedup: bool,
+    pub chunk_similarity_threshold: f64,
+    pub match_by: MatchMode,
+    pub trust_dir_mtimes: bool,
+    pub link_only_same_fs: bool,
+    pub same_extension_only: bool,
+    pub bloom_prefilter: bool,
+    // Smallest group reported; the default of 2 reports every duplicate pair
+    pub min_group_size: usize,
+    // List every hardlinked path instead of treating paths to one inode as a single file
+    pub keep_hardlinks: bool,
+    // Byte-compare the members of every hash-equal set before reporting it
+    pub verify_bytes: bool,
+    // Compare candidates byte by byte instead of hashing them when fewer than this many remain
+    // after size grouping. The default of 0 always hashes. Only used when the service has a
+    // comparator.
+    pub compare_threshold: usize,
+    // Report each skipped file through the progress port as the scan runs
+    pub verbose_errors: bool,
+    // Process candidates in path order so interrupted or partial scans are reproducible
+    pub ordered: bool,
+    // Order of the reported duplicate groups
+    pub sort_by: SortKey,
+    // Stop hashing once one reportable group is confirmed and report only that group
+    pub stop_at_first: bool,
+    // Decides which file represents each group in results and which copy deletion keeps
+    pub keep_policy: KeepPolicy,
+}
+
+// Sets are written in sorted order so the same settings always serialize the same way
+fn serialize_sorted<S: serde::Serializer>(set: &HashSet<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(set.iter().collect::<BTreeSet<_>>())
+}
+
+// Optional timestamps as RFC 3339 strings, e.g. "2024-05-01T00:00:00Z"
+mod rfc3339 {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::SystemTime;
+
+    pub fn serialize<S: Serializer>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.collect_str(&humantime::format_rfc3339(*time)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SystemTime>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|time| humantime::parse_rfc3339_weak(&time).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+// Stops a running scan from another thread, e.g. a GUI's Cancel button. Clones share one flag,
+// so keep a clone and hand the other to the service.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// What comparing files directly found: subsets of indices whose files have identical content,
+// and the files that couldn't be read, with the error
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comparison {
+    pub equal: Vec<Vec<usize>>,
+    pub failed: Vec<(usize, String)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryScan {
+    pub files: Vec<FileMetadata>,
+    pub dir_mtimes: HashMap<PathBuf, SystemTime>,
+    // Entries the walk couldn't read, with the error
+    pub skipped: Vec<(PathBuf, String)>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +725,11 @@ pub struct FileCache {
     pub scan_config_hash: String,
     pub last_scan: SystemTime,
     pub version: String,
+    // Only recorded with `trust_dir_mtimes`. A directory whose mtime is unchanged is assumed to
+    // hold the same files as before: adding, removing, or renaming an entry bumps the directory
+    // mtime. Rewriting a file in place does not, so cached files are still checked one by one.
+    #[serde(default)]
+    pub dir_mtimes: HashMap<PathBuf, SystemTime>,
 }
 
 impl Default for ScanConfig {
@@ -128,15 +738,51 @@ impl Default for ScanConfig {
             paths: vec![PathBuf::from(".")],
             follow_symlinks: false,
             min_size: 0,
+            skip_empty: false,
+            max_size: None,
+            min_nlink: None,
+            max_nlink: None,
             max_depth: None,
             ignore_patterns: HashSet::new(),
+            exclude_globs: Vec::new(),
+            respect_ignore_files: true,
+            include_hidden: false,
+            include_extensions: HashSet::new(),
+            exclude_extensions: HashSet::new(),
+            modified_after: None,
+            modified_before: None,
+            include_types: Vec::new(),
+            resolve_symlinks: false,
+            ignore_bom: false,
             partial_hash_size: 8192,
+            partial_mode: PartialMode::Head,
             use_mmap_threshold: 64 * 1024 * 1024,
             thread_count: None,
+            auto_threads: false,
+            max_open_files: None,
             hash_algorithm: HashAlgorithm::XxHash64,
             cross_filesystem: true,
-            cache_file: None,
+            cache_in: None,
+            cache_out: None,
+            cache_max_age: None,
             incremental: false,
+            find_prefixes: false,
+            chunk_dedup: false,
+            chunk_similarity_threshold: 0.5,
+            match_by: MatchMode::Content,
+            trust_dir_mtimes: false,
+            link_only_same_fs: false,
+            same_extension_only: false,
+            bloom_prefilter: false,
+            min_group_size: 2,
+            keep_hardlinks: false,
+            verify_bytes: false,
+            compare_threshold: 0,
+            verbose_errors: false,
+            ordered: false,
+            sort_by: SortKey::default(),
+            stop_at_first: false,
+            keep_policy: KeepPolicy::default(),
         }
     }
 }
@@ -161,13 +807,66 @@ impl ScanConfig {
         self
     }
 
+    // Accepts extensions with or without the leading dot, in any case
+    pub fn with_extension_filters(mut self, include: &[String], exclude: &[String]) -> Self {
+        let normalize = |ext: &String| ext.trim_start_matches('.').to_lowercase();
+        self.include_extensions = include.iter().map(normalize).collect();
+        self.exclude_extensions = exclude.iter().map(normalize).collect();
+        self
+    }
+
+    // Whether `path` passes the extension filters; only the final extension is compared
+    pub fn extension_allowed(&self, path: &Path) -> bool {
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension {
+            Some(ext) => {
+                !self.exclude_extensions.contains(&ext)
+                    && (self.include_extensions.is_empty() || self.include_extensions.contains(&ext))
+            }
+            None => self.include_extensions.is_empty(),
+        }
+    }
+
+    pub fn with_modified_range(mut self, after: Option<SystemTime>, before: Option<SystemTime>) -> Self {
+        self.modified_after = after;
+        self.modified_before = before;
+        self
+    }
+
+    pub fn modified_in_range(&self, modified: SystemTime) -> bool {
+        self.modified_after.is_none_or(|after| modified > after)
+            && self.modified_before.is_none_or(|before| modified < before)
+    }
+
+    pub fn with_max_size(mut self, size: Option<u64>) -> Self {
+        self.max_size = size;
+        self
+    }
+
+    pub fn with_nlink_range(mut self, min_nlink: Option<u64>, max_nlink: Option<u64>) -> Self {
+        self.min_nlink = min_nlink;
+        self.max_nlink = max_nlink;
+        self
+    }
+
     pub fn with_max_depth(mut self, depth: usize) -> Self {
         self.max_depth = Some(depth);
         self
     }
 
     pub fn with_cache_file(mut self, cache_file: PathBuf) -> Self {
-        self.cache_file = Some(cache_file);
+        self.cache_in = Some(cache_file.clone());
+        self.cache_out = Some(cache_file);
+        self
+    }
+
+    pub fn with_cache_in(mut self, cache_in: Option<PathBuf>) -> Self {
+        self.cache_in = cache_in;
+        self
+    }
+
+    pub fn with_cache_out(mut self, cache_out: Option<PathBuf>) -> Self {
+        self.cache_out = cache_out;
         self
     }
 
@@ -176,6 +875,31 @@ impl ScanConfig {
         self
     }
 
+    pub fn with_match_by(mut self, match_by: MatchMode) -> Self {
+        self.match_by = match_by;
+        self
+    }
+
+    pub fn with_find_prefixes(mut self, find_prefixes: bool) -> Self {
+        self.find_prefixes = find_prefixes;
+        self
+    }
+
+    // Roots resolved against the current directory, so cached paths stay valid whatever
+    // directory a later run is started from
+    pub fn with_absolute_paths(mut self) -> Self {
+        self.paths = self
+            .paths
+            .into_iter()
+            .map(|path| {
+                std::fs::canonicalize(&path)
+                    .or_else(|_| std::path::absolute(&path))
+                    .unwrap_or(path)
+            })
+            .collect();
+        self
+    }
+
     pub fn config_hash(&self) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
@@ -184,15 +908,34 @@ impl ScanConfig {
         self.paths.hash(&mut hasher);
         self.follow_symlinks.hash(&mut hasher);
         self.min_size.hash(&mut hasher);
+        self.skip_empty.hash(&mut hasher);
+        self.max_size.hash(&mut hasher);
+        self.min_nlink.hash(&mut hasher);
+        self.max_nlink.hash(&mut hasher);
         self.max_depth.hash(&mut hasher);
         let mut sorted_patterns: Vec<_> = self.ignore_patterns.iter().collect();
         sorted_patterns.sort();
         sorted_patterns.hash(&mut hasher);
+        self.exclude_globs.hash(&mut hasher);
+        self.respect_ignore_files.hash(&mut hasher);
+        self.include_hidden.hash(&mut hasher);
+        let mut include_extensions: Vec<_> = self.include_extensions.iter().collect();
+        include_extensions.sort();
+        include_extensions.hash(&mut hasher);
+        let mut exclude_extensions: Vec<_> = self.exclude_extensions.iter().collect();
+        exclude_extensions.sort();
+        exclude_extensions.hash(&mut hasher);
+        self.modified_after.hash(&mut hasher);
+        self.modified_before.hash(&mut hasher);
+        self.include_types.hash(&mut hasher);
+        self.ignore_bom.hash(&mut hasher);
         self.partial_hash_size.hash(&mut hasher);
+        self.partial_mode.hash(&mut hasher);
         self.use_mmap_threshold.hash(&mut hasher);
         self.thread_count.hash(&mut hasher);
         self.hash_algorithm.hash(&mut hasher);
         self.cross_filesystem.hash(&mut hasher);
+        self.trust_dir_mtimes.hash(&mut hasher);
 
         format!("{:x}", hasher.finish())
     }
@@ -204,24 +947,365 @@ pub struct ScanResult {
     pub total_files_scanned: usize,
     pub total_size_scanned: u64,
     pub total_wasted_space: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prefix_matches: Vec<PrefixMatch>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunk_overlaps: Vec<ChunkOverlap>,
+    #[serde(default)]
+    pub match_by: MatchMode,
+    // Files dropped because they couldn't be read, with the error that stopped them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<(PathBuf, String)>,
+    // The scan was stopped through a `CancellationToken`, so groups may be missing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+    // The settings that produced this result, so saved reports say what was scanned and how
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_config: Option<ScanConfig>,
+    // When the scan started; results built directly rather than by a scan get their
+    // creation time
+    #[serde(default = "SystemTime::now")]
+    pub scanned_at: SystemTime,
+    // Indices into `duplicates` of the groups to present, when output is limited with
+    // `limit_display`; summary statistics always cover every group
+    #[serde(skip)]
+    shown: Option<HashSet<usize>>,
 }
 
 impl ScanResult {
-    pub fn new(duplicates: Vec<DuplicateSet>, total_files_scanned: usize, total_size_scanned: u64) -> Self {
+    pub fn new(mut duplicates: Vec<DuplicateSet>, total_files_scanned: usize, total_size_scanned: u64) -> Self {
+        duplicates.retain(DuplicateSet::is_duplicate);
         let total_wasted_space = duplicates.iter().map(|d| d.wasted_space()).sum();
         Self {
             duplicates,
             total_files_scanned,
             total_size_scanned,
             total_wasted_space,
+            prefix_matches: Vec::new(),
+            chunk_overlaps: Vec::new(),
+            match_by: MatchMode::Content,
+            skipped: Vec::new(),
+            cancelled: false,
+            scan_config: None,
+            scanned_at: SystemTime::now(),
+            shown: None,
+        }
+    }
+
+    // Presents only the `limit` groups wasting the most space, in their current order. This is
+    // a view for output, so it is applied after sorting and the groups themselves are kept.
+    pub fn limit_display(&mut self, limit: Option<usize>) {
+        self.shown = limit.map(|limit| {
+            let mut by_waste: Vec<(usize, u64)> = self
+                .duplicates
+                .iter()
+                .enumerate()
+                .filter(|(_, group)| group.is_duplicate())
+                .map(|(i, group)| (i, group.wasted_space()))
+                .collect();
+            by_waste.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            by_waste.into_iter().take(limit).map(|(i, _)| i).collect()
+        });
+    }
+
+    pub fn with_provenance(mut self, scan_config: ScanConfig, scanned_at: SystemTime) -> Self {
+        self.scan_config = Some(scan_config);
+        self.scanned_at = scanned_at;
+        self
+    }
+
+    pub fn with_cancelled(mut self, cancelled: bool) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    pub fn with_skipped(mut self, skipped: Vec<(PathBuf, String)>) -> Self {
+        self.skipped = skipped;
+        self
+    }
+
+    pub fn with_match_by(mut self, match_by: MatchMode) -> Self {
+        self.match_by = match_by;
+        self
+    }
+
+    pub fn with_prefix_matches(mut self, prefix_matches: Vec<PrefixMatch>) -> Self {
+        self.prefix_matches = prefix_matches;
+        self
+    }
+
+    pub fn with_chunk_overlaps(mut self, chunk_overlaps: Vec<ChunkOverlap>) -> Self {
+        self.chunk_overlaps = chunk_overlaps;
+        self
+    }
+
+    pub fn with_representatives(mut self, policy: &KeepPolicy) -> Self {
+        for group in &mut self.duplicates {
+            group.order_by_representative(policy);
         }
+        self.total_wasted_space = self.duplicates.iter().map(|d| d.wasted_space()).sum();
+        self
+    }
+
+    // Ties fall back to the representative's path and then the hash, so the order is the same
+    // on every run
+    pub fn sort_groups(&mut self, key: SortKey) {
+        self.duplicates.sort_by(|a, b| {
+            let primary = match key {
+                SortKey::Wasted => b.wasted_space().cmp(&a.wasted_space()),
+                SortKey::Size => b.file_size().cmp(&a.file_size()),
+                SortKey::Count => b.files.len().cmp(&a.files.len()),
+                SortKey::Path => std::cmp::Ordering::Equal,
+            };
+            primary
+                .then_with(|| a.representative().map(|f| &f.path).cmp(&b.representative().map(|f| &f.path)))
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+    }
+
+    pub fn total_hardlink_savings(&self) -> u64 {
+        self.all_groups().map(|group| group.hardlink_savings()).sum()
     }
 
     pub fn total_duplicate_files(&self) -> usize {
         self.duplicates.iter().map(|d| d.duplicate_count()).sum()
     }
 
+    // Fraction of scanned bytes that are redundant copies, 0.0 for an empty scan
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.total_size_scanned == 0 {
+            return 0.0;
+        }
+        self.total_wasted_space as f64 / self.total_size_scanned as f64
+    }
+
+    // Fraction of scanned files that are redundant copies, 0.0 for an empty scan
+    pub fn duplicate_file_ratio(&self) -> f64 {
+        if self.total_files_scanned == 0 {
+            return 0.0;
+        }
+        self.total_duplicate_files() as f64 / self.total_files_scanned as f64
+    }
+
     pub fn duplicate_groups(&self) -> usize {
-        self.duplicates.len()
+        self.all_groups().count()
+    }
+
+    // Groups to present as duplicates, limited by `limit_display` if set; single-file
+    // sets are skipped defensively
+    pub fn groups(&self) -> impl Iterator<Item = &DuplicateSet> {
+        self.duplicates
+            .iter()
+            .enumerate()
+            .filter(|(i, group)| group.is_duplicate() && self.shown.as_ref().is_none_or(|shown| shown.contains(i)))
+            .map(|(_, group)| group)
+    }
+
+    fn all_groups(&self) -> impl Iterator<Item = &DuplicateSet> {
+        self.duplicates.iter().filter(|group| group.is_duplicate())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub timestamp: SystemTime,
+    pub total_files_scanned: usize,
+    pub total_size_scanned: u64,
+    pub duplicate_groups: usize,
+    pub duplicate_files: usize,
+    pub wasted_space: u64,
+}
+
+impl HistoryRecord {
+    pub fn from_result(results: &ScanResult) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            total_files_scanned: results.total_files_scanned,
+            total_size_scanned: results.total_size_scanned,
+            duplicate_groups: results.duplicate_groups(),
+            duplicate_files: results.total_duplicate_files(),
+            wasted_space: results.total_wasted_space,
+        }
+    }
+}
+
+// Differences between two file manifests, matched by path. A file counts as changed when both
+// sides carry a full hash and those differ, or, lacking full hashes, when the sizes differ.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManifestDiff {
+    pub added: Vec<FileMetadata>,
+    pub removed: Vec<FileMetadata>,
+    pub changed: Vec<(FileMetadata, FileMetadata)>,
+}
+
+impl ManifestDiff {
+    pub fn between(old: &[FileMetadata], new: &[FileMetadata]) -> Self {
+        let old_by_path: HashMap<&PathBuf, &FileMetadata> = old.iter().map(|f| (&f.path, f)).collect();
+        let new_by_path: HashMap<&PathBuf, &FileMetadata> = new.iter().map(|f| (&f.path, f)).collect();
+
+        let mut diff = ManifestDiff::default();
+        for file in new {
+            match old_by_path.get(&file.path) {
+                None => diff.added.push(file.clone()),
+                Some(before) if Self::content_changed(before, file) => {
+                    diff.changed.push(((*before).clone(), file.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = old
+            .iter()
+            .filter(|f| !new_by_path.contains_key(&f.path))
+            .cloned()
+            .collect();
+
+        diff.added.sort_by(|a, b| a.path.cmp(&b.path));
+        diff.removed.sort_by(|a, b| a.path.cmp(&b.path));
+        diff.changed.sort_by(|a, b| a.0.path.cmp(&b.0.path));
+        diff
+    }
+
+    fn content_changed(before: &FileMetadata, after: &FileMetadata) -> bool {
+        match (&before.full_hash, &after.full_hash) {
+            (Some(a), Some(b)) => a != b,
+            _ => before.size != after.size,
+        }
     }
-}
\ No newline at end of file
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, bom_len: Option<u64>) -> FileMetadata {
+        let file = FileMetadata::new(PathBuf::from(name), 10, SystemTime::UNIX_EPOCH);
+        match bom_len {
+            Some(bom_len) => file.with_bom_len(bom_len),
+            None => file,
+        }
+    }
+
+    #[test]
+    fn only_content_matched_sets_with_equal_marks_are_byte_identical() {
+        let plain = DuplicateSet::new("h".into(), vec![file("a", None), file("b", None)]);
+        assert!(plain.is_byte_identical());
+        assert!(!plain.clone().with_verification(VerificationLevel::Metadata).is_byte_identical());
+
+        let utf8 = DuplicateSet::new("h".into(), vec![file("a", Some(3)), file("b", Some(3))]);
+        assert!(utf8.is_byte_identical());
+
+        let mixed = DuplicateSet::new("h".into(), vec![file("a", Some(3)), file("b", None)]);
+        assert!(!mixed.is_byte_identical());
+
+        let utf16 = DuplicateSet::new("h".into(), vec![file("a", Some(2)), file("b", Some(2))]);
+        assert!(!utf16.is_byte_identical());
+    }
+
+    #[test]
+    fn scan_config_serializes_deterministically() {
+        let config = |extensions: &[&str]| {
+            let mut config = ScanConfig::new();
+            config.include_extensions = extensions.iter().map(|e| e.to_string()).collect();
+            config.modified_after = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_714_521_600));
+            config.keep_policy = KeepPolicy::new(KeepStrategy::Oldest)
+                .with_prefer_globs(&["**/keep/**".to_string()])
+                .unwrap();
+            serde_json::to_value(&config).unwrap()
+        };
+        let json = config(&["txt", "jpg", "png", "md", "rs"]);
+        assert_eq!(json, config(&["rs", "md", "png", "jpg", "txt"]));
+        assert_eq!(json["include_extensions"], serde_json::json!(["jpg", "md", "png", "rs", "txt"]));
+        assert_eq!(json["modified_after"], "2024-05-01T00:00:00Z");
+        assert_eq!(json["modified_before"], serde_json::Value::Null);
+        assert_eq!(json["keep_policy"], serde_json::json!({"strategy": "Oldest", "prefer_globs": ["**/keep/**"]}));
+
+        let parsed: ScanConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.modified_after, Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_714_521_600)));
+        assert_eq!(parsed.keep_policy.strategy, KeepStrategy::Oldest);
+        assert!(parsed.keep_policy.prefer_globs.unwrap().is_match("a/keep/b"));
+    }
+
+    #[test]
+    fn config_hash_covers_the_modified_time_window() {
+        let base = ScanConfig::new();
+        let mut after = ScanConfig::new();
+        after.modified_after = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(86_400));
+        let mut before = ScanConfig::new();
+        before.modified_before = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(86_400));
+
+        assert_ne!(base.config_hash(), after.config_hash());
+        assert_ne!(base.config_hash(), before.config_hash());
+        assert_ne!(after.config_hash(), before.config_hash());
+        assert_eq!(after.config_hash(), after.clone().config_hash());
+    }
+
+    #[test]
+    fn sets_saved_without_same_filesystem_load_as_same_filesystem() {
+        let json = r#"{"hash": "h", "files": [], "total_size": 0}"#;
+        let set: DuplicateSet = serde_json::from_str(json).unwrap();
+        assert!(set.same_filesystem);
+    }
+
+    #[test]
+    fn prefer_keep_globs_win_over_the_strategy_and_fall_back_to_it() {
+        let at = |name: &str, secs: u64| {
+            FileMetadata::new(PathBuf::from(name), 10, SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+        };
+        let policy = KeepPolicy::new(KeepStrategy::Newest).with_prefer_globs(&["*/originals/*".to_string()]).unwrap();
+        let kept = |files: Vec<FileMetadata>| {
+            DuplicateSet::new("hash".into(), files).canonical_file(&policy).map(|f| f.path.clone())
+        };
+
+        // The newest copy loses to the one matching the glob
+        assert_eq!(
+            kept(vec![at("photos/originals/a.jpg", 1), at("photos/copies/a.jpg", 2)]),
+            Some(PathBuf::from("photos/originals/a.jpg"))
+        );
+        // Several matches are settled by the strategy
+        assert_eq!(
+            kept(vec![at("x/originals/a.jpg", 1), at("y/originals/a.jpg", 3), at("copies/a.jpg", 5)]),
+            Some(PathBuf::from("y/originals/a.jpg"))
+        );
+        // No match falls back to the strategy across every member
+        assert_eq!(
+            kept(vec![at("copies/a.jpg", 1), at("backup/a.jpg", 2)]),
+            Some(PathBuf::from("backup/a.jpg"))
+        );
+    }
+
+    #[test]
+    fn manifest_diff_sorts_files_into_added_removed_and_changed() {
+        let hashed = |name: &str, size: u64, hash: Option<&str>| {
+            let file = FileMetadata::new(PathBuf::from(name), size, SystemTime::UNIX_EPOCH);
+            match hash {
+                Some(hash) => file.with_full_hash(hash.to_string()),
+                None => file,
+            }
+        };
+        let old = [
+            hashed("same", 4, Some("aa")),
+            hashed("edited", 4, Some("bb")),
+            hashed("gone", 4, Some("cc")),
+            hashed("grown", 4, None),
+        ];
+        let new = [
+            hashed("same", 4, Some("aa")),
+            hashed("edited", 4, Some("dd")),
+            hashed("grown", 8, None),
+            hashed("fresh", 4, Some("ee")),
+        ];
+        let names = |files: &[FileMetadata]| files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+
+        let diff = ManifestDiff::between(&old, &new);
+        assert_eq!(names(&diff.added), [PathBuf::from("fresh")]);
+        assert_eq!(names(&diff.removed), [PathBuf::from("gone")]);
+        // Compared by hash when both sides have one, otherwise by size
+        let changed: Vec<_> = diff.changed.iter().map(|(before, after)| (before.path.clone(), after.size)).collect();
+        assert_eq!(changed, [(PathBuf::from("edited"), 4), (PathBuf::from("grown"), 8)]);
+        assert!(ManifestDiff::between(&new, &new).is_empty());
+    }
+}
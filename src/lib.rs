@@ -2,4 +2,26 @@ pub mod adapters;
 pub mod cli;
 pub mod domain;
 pub mod ports;
-pub mod services;
\ No newline at end of file
+pub mod services;
+
+use adapters::{FileSystemAdapter, MultiAlgorithmHasher, ProgressBarAdapter, StreamingComparator};
+use domain::{CancellationToken, ScanConfig, ScanResult};
+use services::DuplicateFinderService;
+
+// Scans with the same adapters the CLI uses, minus the progress bar. Use
+// `DuplicateFinderService` directly to plug in custom ports.
+pub fn find_duplicates(config: &ScanConfig) -> anyhow::Result<ScanResult> {
+    find_duplicates_cancellable(config, CancellationToken::new())
+}
+
+// Like `find_duplicates`, but stops early with a partial result once `token` is cancelled
+pub fn find_duplicates_cancellable(config: &ScanConfig, token: CancellationToken) -> anyhow::Result<ScanResult> {
+    let hasher = MultiAlgorithmHasher::new()
+        .with_mmap_threshold(config.use_mmap_threshold)
+        .with_max_open_files(config.max_open_files);
+    let comparator = StreamingComparator::new().with_max_open_files(config.max_open_files);
+    DuplicateFinderService::new(FileSystemAdapter::new().with_cancellation(token.clone()), hasher, ProgressBarAdapter::new_quiet())
+        .with_comparator(comparator)
+        .with_cancellation(token)
+        .find_duplicates(config)
+}
@@ -1,61 +1,277 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use rdupe::adapters::{
-    ConsoleOutputAdapter, CsvOutputAdapter, FileSystemAdapter, InteractiveOutputAdapter, 
-    JsonOutputAdapter, MultiAlgorithmHasher, ProgressBarAdapter, TreeOutputAdapter
+    CacheFormat, ConfirmationLevel, DeleteErrorPolicy, InteractiveTheme, SavingsMode,
+    ConsoleOutputAdapter, CsvOutputAdapter, DeletionMode, DeletionScriptAdapter, DirectoryOutputAdapter, FdupesOutputAdapter, FileCacheAdapter, HardlinkReportAdapter, FileSystemAdapter, HistoryAdapter, InteractiveOutputAdapter, JournalAdapter, MachineProgressAdapter, ManifestAdapter, 
+    JsonOutputAdapter, JsonPerGroupOutputAdapter, MarkdownOutputAdapter, MultiAlgorithmHasher, NdjsonOutputAdapter, SavingsOutputAdapter, SqliteOutputAdapter, StreamingComparator, SubtreeOutputAdapter, ProgressBarAdapter, TierOutputAdapter, TreeOutputAdapter, UndoOutcome
 };
-use rdupe::cli::{Cli, OutputFormat};
-use rdupe::ports::OutputPort;
+use rdupe::cli::{
+    CacheFormatChoice, Cli, Command, ConfirmationChoice, DeleteErrorChoice, OutputFormat, ProgressFormat, SavingsChoice, ThemeChoice,
+};
+use rdupe::domain::{HistoryRecord, KeepPolicy, KeepStrategy, ManifestDiff};
+use rdupe::ports::{CachePort, HashingPort, OutputPort, ProgressPort};
 use rdupe::services::DuplicateFinderService;
+use anyhow::bail;
+use std::io::Read;
 use std::process;
+use std::sync::Arc;
 
 fn main() {
-    let args = Cli::parse();
-    let config = args.to_scan_config();
-    let filesystem = FileSystemAdapter::new();
-    let hasher = MultiAlgorithmHasher::new().with_mmap_threshold(config.use_mmap_threshold);
-    let progress = ProgressBarAdapter::new().with_quiet(args.quiet);
-
-    let finder = DuplicateFinderService::new(filesystem, hasher, progress);
-    
-    match finder.find_duplicates(&config) {
-        Ok(results) => {
-            if args.interactive {
-                let interactive_output = InteractiveOutputAdapter::new();
-                if let Err(e) = interactive_output.write_results(&results) {
+    let matches = Cli::command().get_matches();
+    let mut args = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(command) = &args.command {
+        if let Err(e) = run_command(command) {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(e) = args.apply_config_file(&matches) {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    }
+
+    if let Some(journal) = &args.undo {
+        if let Err(e) = run_undo(journal, args.dry_run) {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(e) = args.validate_output_path() {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+
+    let keep_strategy: Option<KeepStrategy> = args.keep.clone().map(Into::into);
+    let keep_policy = KeepPolicy::new(keep_strategy.unwrap_or(KeepStrategy::Newest))
+        .with_prefer_globs(&args.prefer_keep_globs)
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        });
+    let keep_regex = args.keep_regex.as_deref().map(|pattern| {
+        regex::Regex::new(pattern).unwrap_or_else(|e| {
+            eprintln!("Error: invalid --keep-regex: {}", e);
+            process::exit(1);
+        })
+    });
+    let mut config = args.to_scan_config().unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    config.keep_policy = keep_policy.clone();
+    if args.dump_config {
+        match serde_json::to_string_pretty(&config) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    if config.match_by.is_approximate() {
+        eprintln!(
+            "Warning: --match-by {} does not compare file contents; reported groups may not be real duplicates.",
+            config.match_by.as_str()
+        );
+    }
+
+    let hasher = MultiAlgorithmHasher::new()
+        .with_mmap_threshold(config.use_mmap_threshold)
+        .with_max_open_files(config.max_open_files);
+    let comparator = StreamingComparator::new().with_max_open_files(config.max_open_files);
+    let progress: Arc<dyn ProgressPort + Send + Sync> = match args.progress_format {
+        ProgressFormat::Json if !args.quiet => Arc::new(MachineProgressAdapter::new()),
+        _ => Arc::new(ProgressBarAdapter::new().with_quiet(args.quiet)),
+    };
+    let filesystem = FileSystemAdapter::new().with_progress(progress.clone());
+
+    let scan = match &args.stdin_name {
+        Some(name) => match read_stdin_capped() {
+            Ok(data) => hasher.hash_bytes(&data, config.hash_algorithm).and_then(|hash| {
+                DuplicateFinderService::new(filesystem, hasher, progress)
+                    .find_matches_of_content(name, data.len() as u64, &hash, &config)
+            }),
+            Err(e) => Err(e),
+        },
+        None => DuplicateFinderService::new(filesystem, hasher, progress)
+            .with_cache(FileCacheAdapter::new().with_format(args.cache_format.as_ref().map(cache_format)))
+            .with_comparator(comparator)
+            .find_duplicates(&config),
+    };
+
+    match scan {
+        Ok(mut results) => {
+            if let Some(ref path) = args.history_file
+                && let Err(e) = HistoryAdapter::new().append_record(path, &HistoryRecord::from_result(&results))
+            {
+                eprintln!("Warning: failed to record history: {:#}", e);
+            }
+
+            #[cfg(target_os = "linux")]
+            let reflink = args.reflink;
+            #[cfg(not(target_os = "linux"))]
+            let reflink = false;
+
+            if args.interactive || reflink || args.quarantine.is_some() {
+                let journal_path = JournalAdapter::new().session_path();
+                let interactive_output = InteractiveOutputAdapter::new()
+                    .with_keep_policy(keep_policy)
+                    .with_preselected_strategy(keep_strategy)
+                    .with_preferred_dirs(args.prefer_dirs.clone())
+                    .with_keep_regex(keep_regex.clone())
+                    .with_theme(interactive_theme(&args.interactive_theme))
+                    .with_confirmations(confirmation_level(&args.confirmations))
+                    .with_delete_error_policy(delete_error_policy(&args.on_delete_error))
+                    .with_deletion_mode(if args.trash { DeletionMode::Trash } else { DeletionMode::Permanent })
+                    .with_journal(journal_path.clone())
+                    .with_dry_run(args.dry_run);
+                #[cfg(target_os = "linux")]
+                let outcome = if let Some(ref dir) = args.quarantine {
+                    interactive_output.quarantine_duplicates(&results, dir)
+                } else if reflink {
+                    interactive_output.reflink_duplicates(&results)
+                } else {
+                    interactive_output.write_results(&results)
+                };
+                #[cfg(not(target_os = "linux"))]
+                let outcome = match args.quarantine {
+                    Some(ref dir) => interactive_output.quarantine_duplicates(&results, dir),
+                    None => interactive_output.write_results(&results),
+                };
+                if let Some(path) = journal_path.filter(|path| path.exists()) {
+                    eprintln!("Undo journal written to {} (restore with: rdupe --undo {})", path.display(), path.display());
+                }
+                if let Err(e) = outcome {
                     eprintln!("Error in interactive mode: {}", e);
                     process::exit(1);
                 }
             } else {
-                let output: Box<dyn OutputPort> = match args.output_format {
-                    OutputFormat::Text => Box::new(ConsoleOutputAdapter::new().with_summary_only(args.summary_only)),
-                    OutputFormat::Json => {
-                        if let Some(ref path) = args.output_file {
-                            Box::new(JsonOutputAdapter::with_file(path).unwrap_or_else(|e| {
-                                eprintln!("Error creating output file: {}", e);
-                                process::exit(1);
-                            }))
-                        } else {
-                            Box::new(JsonOutputAdapter::with_stdout())
-                        }
+                results.limit_display(args.display_limit());
+                // Resolved up front so the adapters can compare it against absolute file paths
+                let relative_to = args.relative.as_ref().map(|base| std::path::absolute(base).unwrap_or_else(|_| base.clone()));
+                let output: Box<dyn OutputPort> = if args.report_savings_only {
+                    Box::new(SavingsOutputAdapter::new().with_mode(savings_mode(&args.savings_mode)))
+                } else if args.hardlink_report {
+                    if let Some(ref path) = args.output_file {
+                        Box::new(HardlinkReportAdapter::with_file(path).unwrap_or_else(|e| {
+                            eprintln!("Error creating output file: {}", e);
+                            process::exit(1);
+                        }))
+                    } else {
+                        Box::new(HardlinkReportAdapter::with_stdout())
                     }
-                    OutputFormat::Csv => {
-                        if let Some(ref path) = args.output_file {
-                            Box::new(CsvOutputAdapter::with_file(path).unwrap_or_else(|e| {
-                                eprintln!("Error creating output file: {}", e);
-                                process::exit(1);
-                            }))
-                        } else {
-                            Box::new(CsvOutputAdapter::with_stdout())
+                } else if let Some(ref path) = args.emit_script {
+                    Box::new(DeletionScriptAdapter::new(path))
+                } else if let Some(ref dir) = args.json_per_group {
+                    Box::new(JsonPerGroupOutputAdapter::new(dir))
+                } else {
+                    match args.output_format {
+                        OutputFormat::Text => Box::new(
+                            ConsoleOutputAdapter::new()
+                                .with_summary_only(args.summary_only)
+                                .with_color(!args.no_color && console::colors_enabled())
+                                .with_relative_to(relative_to.clone()),
+                        ),
+                        OutputFormat::Json => {
+                            if let Some(ref path) = args.output_file {
+                                Box::new(JsonOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                }).with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            } else {
+                                Box::new(JsonOutputAdapter::with_stdout().with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            }
                         }
-                    }
-                    OutputFormat::Tree => {
-                        if let Some(ref path) = args.output_file {
-                            Box::new(TreeOutputAdapter::with_file(path).unwrap_or_else(|e| {
-                                eprintln!("Error creating output file: {}", e);
-                                process::exit(1);
-                            }))
-                        } else {
-                            Box::new(TreeOutputAdapter::with_stdout())
+                        OutputFormat::Ndjson => {
+                            if let Some(ref path) = args.output_file {
+                                Box::new(NdjsonOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                }).with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            } else {
+                                Box::new(NdjsonOutputAdapter::with_stdout().with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            }
+                        }
+                        OutputFormat::Fdupes => {
+                            if let Some(ref path) = args.output_file {
+                                Box::new(FdupesOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                }).with_relative_to(relative_to.clone()))
+                            } else {
+                                Box::new(FdupesOutputAdapter::with_stdout().with_relative_to(relative_to.clone()))
+                            }
+                        }
+                        OutputFormat::Markdown => {
+                            if let Some(ref path) = args.output_file {
+                                Box::new(MarkdownOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                }).with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            } else {
+                                Box::new(MarkdownOutputAdapter::with_stdout().with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            }
+                        }
+                        OutputFormat::Csv => {
+                            if let Some(ref path) = args.output_file {
+                                Box::new(CsvOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                }).with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            } else {
+                                Box::new(CsvOutputAdapter::with_stdout().with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            }
+                        }
+                        OutputFormat::Tree => {
+                            if let Some(ref path) = args.output_file {
+                                Box::new(TreeOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                }).with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            } else {
+                                Box::new(TreeOutputAdapter::with_stdout().with_relative_to(relative_to.clone()).with_summary_only(args.summary_only))
+                            }
+                        }
+                        OutputFormat::Tiers => {
+                            let adapter = if let Some(ref path) = args.output_file {
+                                TierOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                })
+                            } else {
+                                TierOutputAdapter::with_stdout()
+                            };
+                            Box::new(adapter.with_risky_dirs(args.risky_dirs.clone()).with_relative_to(relative_to.clone()))
+                        }
+                        OutputFormat::Subtrees => {
+                            let adapter = if let Some(ref path) = args.output_file {
+                                SubtreeOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                })
+                            } else {
+                                SubtreeOutputAdapter::with_stdout()
+                            };
+                            Box::new(adapter.with_roots(config.paths.clone()).with_relative_to(relative_to.clone()))
+                        }
+                        OutputFormat::Sqlite => {
+                            // validate_output_path has already required --output for this format
+                            let path = args.output_file.clone().unwrap_or_default();
+                            Box::new(SqliteOutputAdapter::new(&path).with_relative_to(relative_to.clone()))
+                        }
+                        OutputFormat::Dirs => {
+                            if let Some(ref path) = args.output_file {
+                                Box::new(DirectoryOutputAdapter::with_file(path).unwrap_or_else(|e| {
+                                    eprintln!("Error creating output file: {}", e);
+                                    process::exit(1);
+                                }).with_relative_to(relative_to.clone()))
+                            } else {
+                                Box::new(DirectoryOutputAdapter::with_stdout().with_relative_to(relative_to.clone()))
+                            }
                         }
                     }
                 };
@@ -65,6 +281,11 @@ fn main() {
                     process::exit(1);
                 }
             }
+
+            // 1 stays reserved for errors so scripts can tell a failed scan from a dirty tree
+            if (args.fail_on_duplicates || args.any) && results.duplicate_groups() > 0 {
+                process::exit(2);
+            }
         }
         Err(e) => {
             eprintln!("Error during scan: {}", e);
@@ -72,3 +293,138 @@ fn main() {
         }
     }
 }
+
+// Stdin is buffered whole so it can be hashed like a file; refuse rather than exhaust memory
+const STDIN_SIZE_CAP: u64 = 512 * 1024 * 1024;
+
+// The adapters' settings mirror the CLI choices; they're mapped here so the CLI stays
+// independent of the adapters
+fn interactive_theme(choice: &ThemeChoice) -> InteractiveTheme {
+    match choice {
+        ThemeChoice::Colorful => InteractiveTheme::Colorful,
+        ThemeChoice::Plain => InteractiveTheme::Plain,
+    }
+}
+
+fn confirmation_level(choice: &ConfirmationChoice) -> ConfirmationLevel {
+    match choice {
+        ConfirmationChoice::Full => ConfirmationLevel::Full,
+        ConfirmationChoice::Single => ConfirmationLevel::Single,
+        ConfirmationChoice::None => ConfirmationLevel::None,
+    }
+}
+
+fn delete_error_policy(choice: &DeleteErrorChoice) -> DeleteErrorPolicy {
+    match choice {
+        DeleteErrorChoice::Continue => DeleteErrorPolicy::Continue,
+        DeleteErrorChoice::Abort => DeleteErrorPolicy::Abort,
+        DeleteErrorChoice::SkipGroup => DeleteErrorPolicy::SkipGroup,
+    }
+}
+
+fn savings_mode(choice: &SavingsChoice) -> SavingsMode {
+    match choice {
+        SavingsChoice::Delete => SavingsMode::Delete,
+        SavingsChoice::Hardlink => SavingsMode::Hardlink,
+    }
+}
+
+fn cache_format(choice: &CacheFormatChoice) -> CacheFormat {
+    match choice {
+        CacheFormatChoice::Json => CacheFormat::Json,
+        CacheFormatChoice::Binary => CacheFormat::Binary,
+    }
+}
+
+fn read_stdin_capped() -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    std::io::stdin().lock().take(STDIN_SIZE_CAP + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > STDIN_SIZE_CAP {
+        bail!("stdin exceeds the {} MB limit for --stdin-name", STDIN_SIZE_CAP / 1_048_576);
+    }
+    Ok(data)
+}
+
+fn run_undo(journal: &std::path::Path, dry_run: bool) -> anyhow::Result<()> {
+    let summary = JournalAdapter::new().undo(journal, dry_run)?;
+    for outcome in &summary.outcomes {
+        match outcome {
+            UndoOutcome::Restored { original, from } if dry_run => {
+                let from = from.as_ref().map_or_else(|| "trash".to_string(), |from| from.display().to_string());
+                println!("Would restore: {} <- {}", original.display(), from);
+            }
+            UndoOutcome::Restored { original, .. } => println!("Restored:  {}", original.display()),
+            UndoOutcome::Failed { original, error } => println!("Failed:    {}: {}", original.display(), error),
+            UndoOutcome::Lost { original, size } => {
+                println!("Lost:      {} ({} bytes, permanently deleted)", original.display(), size)
+            }
+        }
+    }
+    println!("\n=== Undo Summary ===");
+    if dry_run {
+        println!("Would restore: {}", summary.restored);
+    } else {
+        println!("Restored: {}", summary.restored);
+        println!("Failed: {}", summary.failed);
+    }
+    println!("Permanently deleted, not restorable: {} ({:.2} MB)", summary.lost, summary.lost_bytes as f64 / 1_048_576.0);
+    if summary.failed > 0 {
+        bail!("{} files could not be restored", summary.failed);
+    }
+    Ok(())
+}
+
+fn run_command(command: &Command) -> anyhow::Result<()> {
+    match command {
+        Command::History { file } => {
+            let history = HistoryAdapter::new();
+            let records = history.load_records(file)?;
+            print!("{}", history.format_trend(&records));
+        }
+        Command::VerifyCache { cache_file, prune } => {
+            let cache_adapter = FileCacheAdapter::new();
+            let Some(mut cache) = cache_adapter.load_cache(cache_file)? else {
+                bail!("Cache file '{}' does not exist", cache_file.display());
+            };
+
+            let valid = cache_adapter.filter_changed_files(&cache.files);
+            let valid_paths: std::collections::HashSet<_> = valid.iter().map(|f| &f.path).collect();
+            let (missing, stale): (Vec<_>, Vec<_>) = cache.files
+                .iter()
+                .filter(|f| !valid_paths.contains(&f.path))
+                .partition(|f| !f.path.exists());
+
+            println!("=== Cache Verification ===");
+            println!("Cache file: {}", cache_file.display());
+            println!("Cached entries: {}", cache.files.len());
+            println!("Valid: {}", valid.len());
+            println!("Stale (size or mtime changed): {}", stale.len());
+            println!("Missing: {}", missing.len());
+
+            for file in &stale {
+                println!("  stale    {}", file.path.display());
+            }
+            for file in &missing {
+                println!("  missing  {}", file.path.display());
+            }
+
+            if *prune && valid.len() != cache.files.len() {
+                let removed = cache.files.len() - valid.len();
+                cache.files = valid;
+                cache_adapter.save_cache(cache_file, &cache)?;
+                println!("Pruned {} entries from the cache.", removed);
+            }
+        }
+        Command::ManifestDiff { old, new, json } => {
+            let manifests = ManifestAdapter::new();
+            let diff = ManifestDiff::between(&manifests.load_manifest(old)?, &manifests.load_manifest(new)?);
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", manifests.format_diff(&diff));
+            }
+        }
+    }
+
+    Ok(())
+}
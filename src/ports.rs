@@ -1,14 +1,54 @@
-use crate::domain::{FileMetadata, HashAlgorithm, ScanConfig, ScanResult};
+use crate::domain::{Comparison, DirectoryScan, FileCache, FileMetadata, HashAlgorithm, ScanConfig, ScanResult};
 use anyhow::Result;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 pub trait FileSystemPort {
     fn scan_files(&self, config: &ScanConfig) -> Result<Vec<FileMetadata>>;
+
+    // Walks like `scan_files` but prunes every directory in `trusted_dirs`, whose contents the
+    // caller already knows, and also returns the entries it couldn't read. Adapters that can't
+    // prune fall back to a full walk.
+    fn scan_files_skipping(&self, config: &ScanConfig, _trusted_dirs: &HashSet<PathBuf>) -> Result<DirectoryScan> {
+        Ok(DirectoryScan {
+            files: self.scan_files(config)?,
+            dir_mtimes: HashMap::new(),
+            skipped: Vec::new(),
+        })
+    }
 }
 
+// File digests start `skip` bytes in, which is how a file's ignored byte-order mark
+// (`FileMetadata::content_offset`) is left out
 pub trait HashingPort {
-    fn hash_file(&self, path: &Path, algorithm: HashAlgorithm) -> Result<String>;
-    fn hash_partial(&self, path: &Path, bytes: u64, algorithm: HashAlgorithm) -> Result<String>;
+    fn hash_file(&self, path: &Path, skip: u64, algorithm: HashAlgorithm) -> Result<String>;
+    fn hash_partial(&self, path: &Path, skip: u64, bytes: u64, algorithm: HashAlgorithm) -> Result<String>;
+    fn hash_bytes(&self, data: &[u8], algorithm: HashAlgorithm) -> Result<String>;
+
+    // One digest over the first and last `bytes` of the file. Adapters that can't seek to the
+    // end fall back to hashing the head alone.
+    fn hash_head_tail(&self, path: &Path, skip: u64, bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+        self.hash_partial(path, skip, bytes, algorithm)
+    }
+}
+
+// Partitions files into subsets with identical content by comparing them directly, from each
+// file's `content_offset`. Each returned subset holds indices into `files`; files matching no
+// other file are left out, and files that can't be read are listed as failed.
+pub trait ComparisonPort {
+    fn are_equal(&self, files: &[&FileMetadata]) -> Result<Comparison>;
+}
+
+pub trait CachePort {
+    fn load_cache(&self, cache_path: &Path) -> Result<Option<FileCache>>;
+    fn save_cache(&self, cache_path: &Path, cache: &FileCache) -> Result<()>;
+    fn is_cache_valid(&self, cache: &FileCache, config: &ScanConfig) -> bool;
+    // Cached entries whose file still has the recorded size and mtime
+    fn filter_changed_files(&self, cached_files: &[FileMetadata]) -> Vec<FileMetadata>;
+    fn create_cache(&self, files: Vec<FileMetadata>, config: &ScanConfig) -> FileCache;
+    // Cached directories whose whole subtree is unchanged and need not be walked again
+    fn trusted_directories(&self, dir_mtimes: &HashMap<PathBuf, SystemTime>) -> HashSet<PathBuf>;
 }
 
 pub trait OutputPort {
@@ -19,4 +59,45 @@ pub trait ProgressPort {
     fn start(&self, total: u64);
     fn update(&self, processed: u64);
     fn finish(&self);
+
+    // The directory walk runs before the file count is known, so it only reports how many
+    // files it has found so far
+    fn scan_started(&self) {}
+    fn scan_progress(&self, _files_found: u64) {}
+    fn scan_finished(&self, _files_found: u64) {}
+
+    // A file the scan had to leave out, reported as it happens when `verbose_errors` is set.
+    // `path` is `None` when the walk failed before it could name the entry.
+    fn file_skipped(&self, _path: Option<&Path>, _reason: &str) {}
+}
+
+// Lets the progress reporter be chosen at runtime and shared between the walk and the hashing
+impl<T: ProgressPort + ?Sized> ProgressPort for std::sync::Arc<T> {
+    fn start(&self, total: u64) {
+        (**self).start(total)
+    }
+
+    fn update(&self, processed: u64) {
+        (**self).update(processed)
+    }
+
+    fn finish(&self) {
+        (**self).finish()
+    }
+
+    fn scan_started(&self) {
+        (**self).scan_started()
+    }
+
+    fn scan_progress(&self, files_found: u64) {
+        (**self).scan_progress(files_found)
+    }
+
+    fn scan_finished(&self, files_found: u64) {
+        (**self).scan_finished(files_found)
+    }
+
+    fn file_skipped(&self, path: Option<&Path>, reason: &str) {
+        (**self).file_skipped(path, reason)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,45 @@
+use xxhash_rust::xxh3::xxh3_128;
+
+// Fixed-size bloom filter using double hashing over a single 128-bit digest
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    bit_count: u64,
+    hash_count: u32,
+}
+
+impl BloomFilter {
+    pub fn with_capacity(expected_items: usize, false_positive_rate: f64) -> Self {
+        let n = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = ((-n * false_positive_rate.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let hash_count = ((bit_count as f64 / n) * ln2).round().clamp(1.0, 16.0) as u32;
+        Self {
+            bits: vec![0; bit_count.div_ceil(64) as usize],
+            bit_count,
+            hash_count,
+        }
+    }
+
+    // Inserts the item and reports whether it may have been present already
+    pub fn insert(&mut self, item: &[u8]) -> bool {
+        let mut present = true;
+        for index in self.bit_indexes(item) {
+            let (word, mask) = ((index / 64) as usize, 1u64 << (index % 64));
+            present &= self.bits[word] & mask != 0;
+            self.bits[word] |= mask;
+        }
+        present
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.bit_indexes(item)
+            .all(|index| self.bits[(index / 64) as usize] & (1u64 << (index % 64)) != 0)
+    }
+
+    fn bit_indexes(&self, item: &[u8]) -> impl Iterator<Item = u64> + use<> {
+        let digest = xxh3_128(item);
+        let (h1, h2) = (digest as u64, (digest >> 64) as u64);
+        let bit_count = self.bit_count;
+        (0..self.hash_count as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bit_count)
+    }
+}
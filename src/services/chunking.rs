@@ -0,0 +1,216 @@
+use crate::domain::{CancellationToken, ChunkOverlap, FileMetadata};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use xxhash_rust::xxh3::xxh3_64;
+
+// FastCDC-style gear chunking: boundaries fall where the rolling gear hash has its low bits
+// clear, so an insertion only moves the boundaries next to it and later chunks still line up.
+const MIN_CHUNK: usize = 2 * 1024;
+const MAX_CHUNK: usize = 64 * 1024;
+// 13 mask bits give an 8 KiB average chunk past the minimum
+const BOUNDARY_MASK: u64 = (1 << 13) - 1;
+// Chunks found in more files than this (zero pages, common headers) would pair up every
+// holder, so they are left out of the pairing
+const MAX_CHUNK_HOLDERS: usize = 64;
+
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    // splitmix64 gives a fixed, well-mixed table without embedding 256 literals
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+fn chunk_lengths(data: &[u8]) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let end = (start + MAX_CHUNK).min(data.len());
+        let mut cut = end;
+        let mut hash: u64 = 0;
+        for (i, &byte) in data[start..end].iter().enumerate() {
+            hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+            if i + 1 >= MIN_CHUNK && hash & BOUNDARY_MASK == 0 {
+                cut = start + i + 1;
+                break;
+            }
+        }
+        lengths.push(cut - start);
+        start = cut;
+    }
+    lengths
+}
+
+// A file's distinct chunks, and a digest of its whole content for telling identical files apart
+struct FileChunks {
+    content: u64,
+    chunks: HashMap<u64, u64>,
+}
+
+// Distinct chunk digests with their lengths
+fn file_chunks(path: &Path) -> Option<FileChunks> {
+    let file = File::open(path).ok()?;
+    // Safety: the map is read-only and dropped before returning; a concurrent truncation
+    // can fault, the same trade-off the hasher makes for large files
+    let data = unsafe { Mmap::map(&file) }.ok()?;
+
+    let mut chunks = HashMap::new();
+    let mut offset = 0;
+    for len in chunk_lengths(&data) {
+        chunks.insert(xxh3_64(&data[offset..offset + len]), len as u64);
+        offset += len;
+    }
+    Some(FileChunks { content: xxh3_64(&data), chunks })
+}
+
+// Pairs of files whose shared chunk bytes cover at least `threshold` of the smaller file.
+// Byte-identical pairs are left to the normal duplicate report. Files not yet chunked when
+// `cancellation` fires are left out.
+pub fn find_chunk_overlaps(files: &[FileMetadata], threshold: f64, cancellation: &CancellationToken) -> Vec<ChunkOverlap> {
+    let candidates: Vec<&FileMetadata> = files.iter().filter(|f| f.size as usize >= MIN_CHUNK).collect();
+    let chunked: Vec<(usize, FileChunks)> = candidates
+        .par_iter()
+        .enumerate()
+        .filter(|_| !cancellation.is_cancelled())
+        .filter_map(|(i, file)| file_chunks(&file.path).map(|chunks| (i, chunks)))
+        .collect();
+
+    let content: HashMap<usize, u64> = chunked.iter().map(|(i, file)| (*i, file.content)).collect();
+    let mut holders: HashMap<u64, (u64, Vec<usize>)> = HashMap::new();
+    for (i, file) in &chunked {
+        for (&digest, &len) in &file.chunks {
+            holders.entry(digest).or_insert_with(|| (len, Vec::new())).1.push(*i);
+        }
+    }
+
+    let mut shared: HashMap<(usize, usize), u64> = HashMap::new();
+    for (len, files) in holders.values() {
+        if files.len() < 2 || files.len() > MAX_CHUNK_HOLDERS {
+            continue;
+        }
+        for (n, &a) in files.iter().enumerate() {
+            for &b in &files[n + 1..] {
+                *shared.entry((a.min(b), a.max(b))).or_default() += len;
+            }
+        }
+    }
+
+    let mut overlaps: Vec<ChunkOverlap> = shared
+        .into_iter()
+        .filter_map(|((a, b), shared_bytes)| {
+            // Shared bytes count each distinct chunk once, so they can't tell a file with
+            // repeated chunks from its copy; the whole-content digest can
+            let identical = content[&a] == content[&b] && candidates[a].size == candidates[b].size;
+            let (a, b) = (candidates[a], candidates[b]);
+            let smaller = a.size.min(b.size);
+            let similarity = shared_bytes as f64 / smaller as f64;
+            (similarity >= threshold && !identical).then(|| ChunkOverlap {
+                first: a.path.clone(),
+                second: b.path.clone(),
+                shared_bytes,
+                similarity: similarity.min(1.0),
+            })
+        })
+        .collect();
+
+    overlaps.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| a.first.cmp(&b.first))
+            .then_with(|| a.second.cmp(&b.second))
+    });
+    overlaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::SystemTime;
+
+    // Deterministic bytes that chunk well: a simple LCG keeps the gear hash moving
+    fn noise(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    fn overlaps(contents: &[(&str, Vec<u8>)], threshold: f64) -> Vec<(String, String)> {
+        overlaps_until(contents, threshold, &CancellationToken::new())
+    }
+
+    fn overlaps_until(contents: &[(&str, Vec<u8>)], threshold: f64, cancellation: &CancellationToken) -> Vec<(String, String)> {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<FileMetadata> = contents
+            .iter()
+            .map(|(name, data)| {
+                let path = dir.path().join(name);
+                fs::write(&path, data).unwrap();
+                FileMetadata::new(path, data.len() as u64, SystemTime::now())
+            })
+            .collect();
+        let name = |path: &Path| path.file_name().unwrap().to_string_lossy().into_owned();
+        find_chunk_overlaps(&files, threshold, cancellation)
+            .iter()
+            .map(|overlap| (name(&overlap.first), name(&overlap.second)))
+            .collect()
+    }
+
+    #[test]
+    fn copies_with_repeated_chunks_are_left_to_the_duplicate_report() {
+        // The same block four times over: only one distinct chunk run, shared in full
+        let block = noise(1, 40 * 1024);
+        let repeated: Vec<u8> = block.iter().chain(&block).chain(&block).chain(&block).copied().collect();
+        assert!(overlaps(&[("a", repeated.clone()), ("b", repeated)], 0.0).is_empty());
+    }
+
+    #[test]
+    fn reordered_content_is_an_overlap_not_a_copy() {
+        let (x, y) = (noise(2, 64 * 1024), noise(3, 64 * 1024));
+        let xy: Vec<u8> = x.iter().chain(&y).copied().collect();
+        let yx: Vec<u8> = y.iter().chain(&x).copied().collect();
+        assert_eq!(overlaps(&[("a", xy), ("b", yx)], 0.5), vec![("a".to_string(), "b".to_string())]);
+    }
+
+    #[test]
+    fn threshold_bounds_which_pairs_are_reported() {
+        let shared = noise(4, 96 * 1024);
+        let mut first = shared.clone();
+        first.extend(noise(5, 96 * 1024));
+        let mut second = shared;
+        second.extend(noise(6, 96 * 1024));
+        let pair = vec![("a".to_string(), "b".to_string())];
+
+        assert_eq!(overlaps(&[("a", first.clone()), ("b", second.clone())], 0.0), pair);
+        assert_eq!(overlaps(&[("a", first.clone()), ("b", second.clone())], 0.3), pair);
+        assert!(overlaps(&[("a", first), ("b", second)], 1.0).is_empty());
+    }
+
+    #[test]
+    fn cancelled_pass_chunks_nothing() {
+        let shared = noise(7, 96 * 1024);
+        let mut second = shared.clone();
+        second.extend(noise(8, 96 * 1024));
+        let token = CancellationToken::new();
+        token.cancel();
+
+        assert!(overlaps_until(&[("a", shared), ("b", second)], 0.0, &token).is_empty());
+    }
+}
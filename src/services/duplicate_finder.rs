@@ -1,22 +1,80 @@
 use crate::adapters::FileCacheAdapter;
-use crate::domain::{DuplicateSet, FileMetadata, ScanConfig, ScanResult};
-use crate::ports::{FileSystemPort, HashingPort, ProgressPort};
+use crate::domain::{CancellationToken, DuplicateSet, FileMetadata, MatchMode, PartialMode, PrefixMatch, ScanConfig, ScanResult, VerificationLevel};
+use crate::ports::{CachePort, ComparisonPort, FileSystemPort, HashingPort, ProgressPort};
+use crate::services::bloom::BloomFilter;
+use crate::services::storage;
 use anyhow::Result;
 use rayon::prelude::*;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
-pub struct DuplicateFinderService<F, H, P> {
+// Files an `ordered` scan hashes between cancellation checks. Fixed rather than tied to the
+// thread count, so where a stopped scan ends doesn't depend on the machine.
+const ORDERED_BATCH_FILES: usize = 64;
+
+// Side results of the hashing stages, kept apart from the groups being narrowed down
+#[derive(Default)]
+struct HashingOutcome {
+    // Hashed copies of files, written back to the cache so the next run can reuse them
+    hashed: Vec<FileMetadata>,
+    // Files that couldn't be hashed, with the reason
+    skipped: Vec<(PathBuf, String)>,
+}
+
+// Spots the first reportable group while full hashes are still coming in, so a `stop_at_first`
+// scan can skip every file still queued. Keys include whatever `finish_groups` later splits
+// sets on, so a match found here survives it. Byte verification could still split it, but
+// only on a digest collision.
+#[derive(Default)]
+struct FirstGroupWatch {
+    found: AtomicBool,
+    matches: Mutex<HashMap<WatchKey, usize>>,
+}
+
+// Size group, full hash, and the extension and device when those split sets
+type WatchKey = (usize, String, Option<OsString>, Option<u64>);
+
+impl FirstGroupWatch {
+    fn is_found(&self) -> bool {
+        self.found.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, group_id: usize, file: &FileMetadata, config: &ScanConfig) {
+        let Some(hash) = &file.full_hash else {
+            return;
+        };
+        let key = (
+            group_id,
+            hash.clone(),
+            config.same_extension_only.then(|| file.path.extension().map(|e| e.to_ascii_lowercase())).flatten(),
+            file.dev.filter(|_| config.link_only_same_fs),
+        );
+        let mut matches = self.matches.lock().unwrap();
+        let count = matches.entry(key).or_default();
+        *count += 1;
+        if *count >= config.min_group_size {
+            self.found.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+pub struct DuplicateFinderService<F, H, P, C = FileCacheAdapter> {
     filesystem: F,
     hasher: H,
     progress: P,
-    cache: FileCacheAdapter,
+    cache: C,
+    comparator: Option<Box<dyn ComparisonPort + Send + Sync>>,
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    cancellation: CancellationToken,
 }
 
 impl<F, H, P> DuplicateFinderService<F, H, P>
 where
-    F: FileSystemPort,
+    F: FileSystemPort + Sync,
     H: HashingPort + Send + Sync,
     P: ProgressPort + Send + Sync,
 {
@@ -26,91 +84,484 @@ where
             hasher,
             progress,
             cache: FileCacheAdapter::new(),
+            comparator: None,
+            thread_pool: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+}
+
+impl<F, H, P, C> DuplicateFinderService<F, H, P, C>
+where
+    F: FileSystemPort + Sync,
+    H: HashingPort + Send + Sync,
+    P: ProgressPort + Send + Sync,
+    C: CachePort + Sync,
+{
+    // Replaces the default file cache, e.g. with `NoOpCache` to never read or write one
+    pub fn with_cache<C2: CachePort + Sync>(self, cache: C2) -> DuplicateFinderService<F, H, P, C2> {
+        DuplicateFinderService {
+            filesystem: self.filesystem,
+            hasher: self.hasher,
+            progress: self.progress,
+            cache,
+            comparator: self.comparator,
+            thread_pool: self.thread_pool,
+            cancellation: self.cancellation,
         }
     }
 
+    // Small candidate sets (below `compare_threshold`) are then compared directly instead of
+    // being hashed. Only one file per confirmed set is read a second time, to give the set its
+    // digest in the configured algorithm.
+    pub fn with_comparator(mut self, comparator: impl ComparisonPort + Send + Sync + 'static) -> Self {
+        self.comparator = Some(Box::new(comparator));
+        self
+    }
+
+    // Runs all parallel work inside the caller's pool instead of a per-scan one, so
+    // `thread_count`/`auto_threads` in the config are ignored.
+    pub fn with_thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    // Once the token is cancelled the scan stops hashing and returns what it has confirmed so
+    // far, marked `cancelled`. Without one a scan always runs to completion.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
     pub fn find_duplicates(&self, config: &ScanConfig) -> Result<ScanResult> {
-        if let Some(thread_count) = config.thread_count {
-            rayon::ThreadPoolBuilder::new()
+        let started = SystemTime::now();
+        let mut results = self
+            .in_pool(config, || self.scan(config))?
+            .with_provenance(config.clone(), started);
+        results.sort_groups(config.sort_by);
+        Ok(results)
+    }
+
+    // A pool sized from the config lives only for this call, so repeated scans in one process
+    // can each use their own thread count without touching rayon's global pool
+    fn in_pool<T: Send>(&self, config: &ScanConfig, work: impl FnOnce() -> Result<T> + Send) -> Result<T> {
+        if let Some(pool) = &self.thread_pool {
+            return pool.install(work);
+        }
+
+        let thread_count = match config.thread_count {
+            None if config.auto_threads => storage::auto_thread_count(&config.paths),
+            explicit => explicit,
+        };
+        match thread_count {
+            Some(thread_count) => rayon::ThreadPoolBuilder::new()
                 .num_threads(thread_count)
-                .build_global()
-                .map_err(|e| anyhow::anyhow!("Failed to configure thread pool: {}", e))?;
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to configure thread pool: {}", e))?
+                .install(work),
+            None => work(),
         }
+    }
 
-        let mut cached_files = Vec::new();
-        if let Some(cache_path) = &config.cache_file {
-            if let Ok(Some(cache)) = self.cache.load_cache(cache_path) {
-                if self.cache.is_cache_valid(&cache, config) {
-                    cached_files = if config.incremental {
-                        self.cache.filter_changed_files(&cache.files)
-                    } else {
-                        cache.files
-                    };
-                }
-            }
+    fn scan(&self, config: &ScanConfig) -> Result<ScanResult> {
+        // The cache is keyed by path, so scans always work on absolute paths; resolving them
+        // whether or not a cache is used keeps the reported paths the same either way
+        let config = &config.clone().with_absolute_paths();
+
+        let mut cached = None;
+        if let Some(cache_path) = &config.cache_in
+            && let Ok(Some(cache)) = self.cache.load_cache(cache_path)
+            && self.cache.is_cache_valid(&cache, config)
+        {
+            cached = Some(cache);
         }
 
-        let files = if config.incremental && !cached_files.is_empty() {
-            let new_files = self.filesystem.scan_files(config)?;
-            let new_paths: std::collections::HashSet<_> = new_files.iter().map(|f| &f.path).collect();
-            let valid_cached: Vec<_> = cached_files.into_iter()
-                .filter(|f| new_paths.contains(&f.path))
-                .collect();
+        let mut dir_mtimes;
+        let mut walk_skipped;
+        let mut files = if config.incremental && config.trust_dir_mtimes {
+            let (cached_files, cached_dir_mtimes) = cached
+                .map(|cache| (cache.files, cache.dir_mtimes))
+                .unwrap_or_default();
+            let mut trusted = self.cache.trusted_directories(&cached_dir_mtimes);
 
-            let mut merged = valid_cached;
-            for file in new_files {
-                if !merged.iter().any(|f| f.path == file.path) {
-                    merged.push(file);
+            // Rewriting a file in place leaves its directory's mtime alone, so every cached file
+            // is still checked, and the directories above a changed one are walked again
+            let in_trusted: Vec<FileMetadata> = cached_files
+                .into_iter()
+                .filter(|f| f.path.parent().is_some_and(|parent| trusted.contains(parent)))
+                .collect();
+            let mut unchanged: HashMap<PathBuf, FileMetadata> = self
+                .cache
+                .filter_changed_files(&in_trusted)
+                .into_iter()
+                .map(|f| (f.path.clone(), f))
+                .collect();
+            for changed in in_trusted.iter().filter(|f| !unchanged.contains_key(&f.path)) {
+                for dir in changed.path.ancestors().skip(1) {
+                    trusted.remove(dir);
                 }
             }
+
+            let scan = self.filesystem.scan_files_skipping(config, &trusted)?;
+            dir_mtimes = scan.dir_mtimes;
+            walk_skipped = scan.skipped;
+            dir_mtimes.extend(cached_dir_mtimes.into_iter().filter(|(dir, _)| trusted.contains(dir)));
+
+            // Unchanged files in directories walked again keep their cached hashes
+            let mut merged: Vec<FileMetadata> =
+                scan.files.into_iter().map(|f| unchanged.remove(&f.path).unwrap_or(f)).collect();
+            merged.extend(
+                unchanged
+                    .into_values()
+                    .filter(|f| f.path.parent().is_some_and(|parent| trusted.contains(parent))),
+            );
             merged
         } else {
-            self.filesystem.scan_files(config)?
+            let scan = self.filesystem.scan_files_skipping(config, &HashSet::new())?;
+            dir_mtimes = scan.dir_mtimes;
+            walk_skipped = scan.skipped;
+            let cached_files = match cached {
+                Some(cache) if config.incremental => self.cache.filter_changed_files(&cache.files),
+                _ => Vec::new(),
+            };
+
+            if cached_files.is_empty() {
+                scan.files
+            } else {
+                let new_files = scan.files;
+                let new_paths: HashSet<_> = new_files.iter().map(|f| &f.path).collect();
+                let valid_cached: Vec<_> = cached_files.into_iter()
+                    .filter(|f| new_paths.contains(&f.path))
+                    .collect();
+                let cached_paths: HashSet<PathBuf> = valid_cached.iter().map(|f| f.path.clone()).collect();
+
+                let mut merged = valid_cached;
+                merged.extend(new_files.into_iter().filter(|file| !cached_paths.contains(&file.path)));
+                merged
+            }
         };
 
         let total_files = files.len();
         let total_size: u64 = files.iter().map(|f| f.size).sum();
 
+        // A walk cut short leaves a partial file list, which is no basis for any later pass
+        if self.cancellation.is_cancelled() {
+            return Ok(ScanResult::new(vec![], total_files, total_size)
+                .with_cancelled(true)
+                .with_skipped(walk_skipped));
+        }
         if files.is_empty() {
-            return Ok(ScanResult::new(vec![], 0, 0));
+            return Ok(ScanResult::new(vec![], 0, 0).with_skipped(walk_skipped));
+        }
+
+        let prefix_matches = if config.find_prefixes {
+            self.find_prefix_matches(&files, config)
+        } else {
+            Vec::new()
+        };
+
+        #[cfg(feature = "chunk-dedup")]
+        let chunk_overlaps = if config.chunk_dedup {
+            crate::services::chunking::find_chunk_overlaps(&files, config.chunk_similarity_threshold, &self.cancellation)
+        } else {
+            Vec::new()
+        };
+        #[cfg(not(feature = "chunk-dedup"))]
+        let chunk_overlaps = Vec::new();
+
+        if self.cancellation.is_cancelled() {
+            return Ok(ScanResult::new(vec![], total_files, total_size)
+                .with_cancelled(true)
+                .with_skipped(walk_skipped)
+                .with_prefix_matches(prefix_matches)
+                .with_chunk_overlaps(chunk_overlaps));
+        }
+
+        // Hardlinks are one physical file, so only the first path seen for each inode is compared
+        let mut seen_inodes = HashSet::new();
+        let physical: Vec<&FileMetadata> = files
+            .iter()
+            .filter(|f| config.keep_hardlinks || f.inode_key().is_none_or(|key| seen_inodes.insert(key)))
+            .collect();
+
+        if config.match_by.is_approximate() {
+            let mut duplicates =
+                Self::apply_filesystem_policy(Self::group_by_name(&physical, config.match_by), config);
+            duplicates.retain(|set| set.files.len() >= config.min_group_size);
+            if config.stop_at_first {
+                duplicates.truncate(1);
+            }
+            self.store_cache(files, dir_mtimes, config);
+            return Ok(ScanResult::new(duplicates, total_files, total_size)
+                .with_skipped(walk_skipped)
+                .with_prefix_matches(prefix_matches)
+                .with_chunk_overlaps(chunk_overlaps)
+                .with_match_by(config.match_by)
+                .with_representatives(&config.keep_policy));
+        }
+
+        // Stops at the first repeated size, so the common all-unique case costs one pass over
+        // sizes and never clones or groups the file list.
+        let mut seen_sizes = HashSet::with_capacity(physical.len());
+        if physical.iter().all(|f| seen_sizes.insert(f.content_size())) {
+            self.store_cache(files, dir_mtimes, config);
+            return Ok(ScanResult::new(vec![], total_files, total_size)
+                .with_skipped(walk_skipped)
+                .with_prefix_matches(prefix_matches)
+                .with_chunk_overlaps(chunk_overlaps));
         }
+        drop(seen_sizes);
 
-        let mut size_groups: HashMap<u64, Vec<FileMetadata>> = HashMap::new();
-        for file in files.clone() {
-            size_groups.entry(file.size).or_default().push(file);
+        let mut size_groups: HashMap<u64, Vec<&FileMetadata>> = HashMap::new();
+        for file in physical {
+            size_groups.entry(file.content_size()).or_default().push(file);
         }
 
-        let potential_duplicates: Vec<Vec<FileMetadata>> = size_groups
+        let mut potential_duplicates: Vec<Vec<FileMetadata>> = size_groups
             .into_values()
             .filter(|group| group.len() > 1)
+            .map(|group| group.into_iter().cloned().collect())
             .collect();
 
-        if potential_duplicates.is_empty() {
-            if let Some(cache_path) = &config.cache_file {
-                let cache = self.cache.create_cache(files, config);
-                let _ = self.cache.save_cache(cache_path, &cache);
+        if config.ordered {
+            Self::sort_groups(&mut potential_duplicates);
+        }
+
+        let mut outcome = HashingOutcome::default();
+        let candidate_count: usize = potential_duplicates.iter().map(Vec::len).sum();
+        let mut result = match &self.comparator {
+            Some(comparator) if candidate_count < config.compare_threshold => {
+                self.compare_directly(comparator.as_ref(), potential_duplicates, config, &mut outcome)?
+            }
+            _ => self.progressive_hash_with_channels(potential_duplicates, config, &mut outcome)?,
+        };
+        // Several groups can be confirmed at once before the hashing threads notice
+        if config.stop_at_first {
+            result.truncate(1);
+        }
+        Self::record_hashes(&mut files, outcome.hashed);
+        self.store_cache(files, dir_mtimes, config);
+        walk_skipped.extend(outcome.skipped);
+
+        Ok(ScanResult::new(result, total_files, total_size)
+            .with_cancelled(self.cancellation.is_cancelled())
+            .with_skipped(walk_skipped)
+            .with_prefix_matches(prefix_matches)
+            .with_chunk_overlaps(chunk_overlaps)
+            .with_representatives(&config.keep_policy))
+    }
+
+    pub fn find_matches_of_content(
+        &self,
+        name: &str,
+        size: u64,
+        hash: &str,
+        config: &ScanConfig,
+    ) -> Result<ScanResult> {
+        let started = SystemTime::now();
+        Ok(self
+            .in_pool(config, || self.match_content(name, size, hash, config))?
+            .with_provenance(config.clone(), started))
+    }
+
+    fn match_content(&self, name: &str, size: u64, hash: &str, config: &ScanConfig) -> Result<ScanResult> {
+        let scan = self.filesystem.scan_files_skipping(config, &HashSet::new())?;
+        let files = scan.files;
+        let total_files = files.len();
+        let total_size: u64 = files.iter().map(|f| f.size).sum();
+
+        let candidates: Vec<FileMetadata> = files.into_iter().filter(|f| f.size == size).collect();
+        let hasher = &self.hasher;
+        let progress = &self.progress;
+        progress.start(candidates.len() as u64);
+        let counter = AtomicUsize::new(0);
+        let skipped_hashes = Mutex::new(Vec::new());
+        let matches: Vec<FileMetadata> = candidates
+            .into_par_iter()
+            .filter_map(|file| {
+                let full_hash = hasher
+                    .hash_file(&file.path, 0, config.hash_algorithm)
+                    .inspect_err(|e| {
+                        self.report_skipped(config, &file.path, e);
+                        skipped_hashes.lock().unwrap().push((file.path.clone(), e.to_string()));
+                    })
+                    .ok();
+                let count = counter.fetch_add(1, Ordering::SeqCst);
+                progress.update(count as u64 + 1);
+                full_hash
+                    .filter(|full_hash| full_hash == hash)
+                    .map(|full_hash| file.with_full_hash(full_hash))
+            })
+            .collect();
+        progress.finish();
+        let mut skipped = scan.skipped;
+        skipped.extend(skipped_hashes.into_inner().unwrap());
+
+        if matches.is_empty() {
+            return Ok(ScanResult::new(vec![], total_files, total_size).with_skipped(skipped));
+        }
+
+        let pseudo_file = FileMetadata::new(PathBuf::from(name), size, SystemTime::now())
+            .with_full_hash(hash.to_string());
+        let mut group = vec![pseudo_file];
+        group.extend(matches);
+        Ok(ScanResult::new(vec![DuplicateSet::new(hash.to_string(), group)], total_files, total_size).with_skipped(skipped))
+    }
+
+    fn report_skipped(&self, config: &ScanConfig, path: &Path, reason: impl std::fmt::Display) {
+        if config.verbose_errors {
+            self.progress.file_skipped(Some(path), &reason.to_string());
+        }
+    }
+
+    // Copies hashes from `hashed` onto the matching scanned files. Files hashed at both stages
+    // appear twice, and the later full-hash copy carries both hashes.
+    fn record_hashes(files: &mut [FileMetadata], hashed: Vec<FileMetadata>) {
+        if hashed.is_empty() {
+            return;
+        }
+        let by_path: HashMap<PathBuf, FileMetadata> = hashed.into_iter().map(|f| (f.path.clone(), f)).collect();
+        for file in files {
+            if let Some(hashed) = by_path.get(&file.path) {
+                file.partial_hash.clone_from(&hashed.partial_hash);
+                file.full_hash.clone_from(&hashed.full_hash);
             }
-            return Ok(ScanResult::new(vec![], total_files, total_size));
         }
+    }
 
-        let result = self.progressive_hash_with_channels(potential_duplicates, config)?;
-        if let Some(cache_path) = &config.cache_file {
-            let cache = self.cache.create_cache(files, config);
+    fn store_cache(&self, files: Vec<FileMetadata>, dir_mtimes: HashMap<PathBuf, SystemTime>, config: &ScanConfig) {
+        if let Some(cache_path) = &config.cache_out {
+            let mut cache = self.cache.create_cache(files, config);
+            cache.dir_mtimes = dir_mtimes;
             let _ = self.cache.save_cache(cache_path, &cache);
         }
+    }
+
+    fn apply_filesystem_policy(duplicates: Vec<DuplicateSet>, config: &ScanConfig) -> Vec<DuplicateSet> {
+        if !config.link_only_same_fs {
+            return duplicates;
+        }
+        duplicates.into_iter().flat_map(DuplicateSet::split_by_filesystem).collect()
+    }
+
+    fn group_by_name(files: &[&FileMetadata], match_by: MatchMode) -> Vec<DuplicateSet> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut name_groups: HashMap<(OsString, Option<u64>), Vec<FileMetadata>> = HashMap::new();
+        for file in files {
+            let Some(name) = file.path.file_name() else {
+                continue;
+            };
+            let size = (match_by == MatchMode::NameSize).then_some(file.size);
+            name_groups.entry((name.to_os_string(), size)).or_default().push((*file).clone());
+        }
+
+        // No content hash exists in these modes, so the group key is hashed instead to give
+        // each set a stable fixed-width identifier.
+        name_groups
+            .into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .map(|(key, files)| {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+                DuplicateSet::new(format!("{:016x}", hasher.finish()), files)
+                    .with_verification(VerificationLevel::Metadata)
+            })
+            .collect()
+    }
+
+    // Once cancelled, files not yet probed get no heads and no further pairs are checked, so
+    // only matches already confirmed are returned
+    fn find_prefix_matches(&self, files: &[FileMetadata], config: &ScanConfig) -> Vec<PrefixMatch> {
+        let hasher = &self.hasher;
+        let cancellation = &self.cancellation;
+
+        // Empty files are a prefix of everything, so they carry no information here
+        let mut candidates: Vec<&FileMetadata> = files.iter().filter(|f| f.size > 0).collect();
+        candidates.sort_by_key(|f| f.size);
+
+        // A prefix and the file containing it agree on every leading byte up to the prefix's
+        // size. Each file is keyed by the hash of its first `probe(size)` bytes, at most
+        // `head_size`, and is only compared with larger files sharing that key, so files with
+        // different heads are never paired up.
+        let head_size = config.partial_hash_size.max(1);
+        let probe = |size: u64| if size >= head_size { head_size } else { 1 << size.ilog2() };
+        let probes: BTreeSet<u64> = candidates.iter().map(|f| probe(f.size)).collect();
+
+        let keyed: Vec<(&FileMetadata, HashMap<u64, String>)> = candidates
+            .par_iter()
+            .map(|file| {
+                let heads = probes
+                    .iter()
+                    .filter(|&&len| len <= file.size && !cancellation.is_cancelled())
+                    .filter_map(|&len| {
+                        hasher
+                            .hash_partial(&file.path, 0, len, config.hash_algorithm)
+                            .ok()
+                            .map(|hash| (len, hash))
+                    })
+                    .collect();
+                (*file, heads)
+            })
+            .collect();
+
+        let mut buckets: HashMap<(u64, &str), Vec<&FileMetadata>> = HashMap::new();
+        for (file, heads) in &keyed {
+            for (&len, hash) in heads {
+                buckets.entry((len, hash.as_str())).or_default().push(file);
+            }
+        }
 
-        Ok(ScanResult::new(result, total_files, total_size))
+        keyed
+            .par_iter()
+            .flat_map_iter(|(small, heads)| {
+                let mut matches = Vec::new();
+                if cancellation.is_cancelled() {
+                    return matches;
+                }
+                let len = probe(small.size);
+                let Some(larger) = heads.get(&len).and_then(|hash| buckets.get(&(len, hash.as_str()))) else {
+                    return matches;
+                };
+
+                let mut small_full: Option<Option<String>> = None;
+                for large in larger.iter().filter(|large| large.size > small.size) {
+                    // When the probe covers the whole smaller file, a shared key is already a match
+                    if small.size != len {
+                        let full = small_full.get_or_insert_with(|| hasher.hash_file(&small.path, 0, config.hash_algorithm).ok());
+                        let Some(full) = full else {
+                            break;
+                        };
+                        if hasher
+                            .hash_partial(&large.path, 0, small.size, config.hash_algorithm)
+                            .ok()
+                            .is_none_or(|partial| &partial != full)
+                        {
+                            continue;
+                        }
+                    }
+                    matches.push(PrefixMatch {
+                        prefix: (*small).clone(),
+                        container: (*large).clone(),
+                    });
+                }
+                matches
+            })
+            .collect()
     }
 
     fn progressive_hash_with_channels(
         &self,
         size_groups: Vec<Vec<FileMetadata>>,
         config: &ScanConfig,
+        outcome: &mut HashingOutcome,
     ) -> Result<Vec<DuplicateSet>> {
         let total_files_to_hash: usize = size_groups.iter().map(|group| group.len()).sum();
         self.progress.start(total_files_to_hash as u64 * 2); // Partial + full hash
-        let partial_hash_groups = self.hash_files_parallel(size_groups, config, true)?;
-        let full_hash_groups = self.hash_files_parallel(partial_hash_groups, config, false)?;
+        let partial_hash_groups = self.hash_files_parallel(size_groups, config, true, outcome, None)?;
+        let watch = config.stop_at_first.then(FirstGroupWatch::default);
+        let full_hash_groups = self.hash_files_parallel(partial_hash_groups, config, false, outcome, watch.as_ref())?;
         self.progress.finish();
 
         let mut hash_groups: HashMap<String, Vec<FileMetadata>> = HashMap::new();
@@ -125,10 +576,104 @@ where
         let duplicates: Vec<DuplicateSet> = hash_groups
             .into_iter()
             .filter(|(_, files)| files.len() > 1)
-            .map(|(hash, files)| DuplicateSet::new(hash, files))
+            .map(|(hash, files)| DuplicateSet::new(hash, files).with_verification(VerificationLevel::FullHash))
             .collect();
 
-        Ok(duplicates)
+        let duplicates = if config.verify_bytes {
+            self.verify_bytes(duplicates, config, outcome)?
+        } else {
+            duplicates
+        };
+        Ok(Self::finish_groups(duplicates, config))
+    }
+
+    // Splits every hash-equal set into the subsets whose bytes really match, so a digest
+    // collision can never put different files in one group
+    fn verify_bytes(&self, duplicates: Vec<DuplicateSet>, config: &ScanConfig, outcome: &mut HashingOutcome) -> Result<Vec<DuplicateSet>> {
+        let Some(comparator) = &self.comparator else {
+            anyhow::bail!("Byte verification needs a comparator; set one with `with_comparator`");
+        };
+
+        let skipped = Mutex::new(Vec::new());
+        let verified: Vec<Vec<DuplicateSet>> = duplicates
+            .into_par_iter()
+            .map(|set| {
+                // Unverified sets are dropped rather than reported as verified
+                if self.cancellation.is_cancelled() {
+                    return Ok(Vec::new());
+                }
+                let files: Vec<&FileMetadata> = set.files.iter().collect();
+                let comparison = comparator.are_equal(&files)?;
+                for (i, e) in comparison.failed {
+                    self.report_skipped(config, &set.files[i].path, &e);
+                    skipped.lock().unwrap().push((set.files[i].path.clone(), e));
+                }
+                Ok(comparison
+                    .equal
+                    .into_iter()
+                    .map(|subset| {
+                        let files = subset.into_iter().map(|i| set.files[i].clone()).collect();
+                        DuplicateSet::new(set.hash.clone(), files).with_verification(VerificationLevel::ByteVerified)
+                    })
+                    .collect())
+            })
+            .collect::<Result<_>>()?;
+        outcome.skipped.extend(skipped.into_inner().unwrap());
+        Ok(verified.into_iter().flatten().collect())
+    }
+
+    fn compare_directly(
+        &self,
+        comparator: &dyn ComparisonPort,
+        size_groups: Vec<Vec<FileMetadata>>,
+        config: &ScanConfig,
+        outcome: &mut HashingOutcome,
+    ) -> Result<Vec<DuplicateSet>> {
+        let total_files: usize = size_groups.iter().map(Vec::len).sum();
+        self.progress.start(total_files as u64);
+
+        let mut processed = 0;
+        let mut duplicates = Vec::new();
+        for group in size_groups {
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            let files: Vec<&FileMetadata> = group.iter().collect();
+            let comparison = comparator.are_equal(&files)?;
+            for (i, e) in comparison.failed {
+                self.report_skipped(config, &group[i].path, &e);
+                outcome.skipped.push((group[i].path.clone(), e));
+            }
+            for subset in comparison.equal {
+                let files: Vec<FileMetadata> = subset.into_iter().map(|i| group[i].clone()).collect();
+                // The members are known to be identical, so one of them is hashed with the
+                // configured algorithm to give the set the digest a hashed scan would report
+                let Some(hash) = files.iter().find_map(|f| self.hasher.hash_file(&f.path, f.content_offset(), config.hash_algorithm).ok())
+                else {
+                    continue;
+                };
+                let files = files.into_iter().map(|f| f.with_full_hash(hash.clone())).collect();
+                duplicates.push(DuplicateSet::new(hash, files).with_verification(VerificationLevel::ByteVerified));
+            }
+            processed += group.len();
+            self.progress.update(processed as u64);
+            if config.stop_at_first && !Self::finish_groups(duplicates.clone(), config).is_empty() {
+                break;
+            }
+        }
+        self.progress.finish();
+
+        Ok(Self::finish_groups(duplicates, config))
+    }
+
+    fn finish_groups(duplicates: Vec<DuplicateSet>, config: &ScanConfig) -> Vec<DuplicateSet> {
+        // Verification or filesystem splitting can leave a set with a lone member; never report those
+        let mut duplicates = Self::apply_filesystem_policy(duplicates, config);
+        if config.same_extension_only {
+            duplicates = duplicates.into_iter().flat_map(DuplicateSet::split_by_extension).collect();
+        }
+        duplicates.retain(|set| set.is_duplicate() && set.files.len() >= config.min_group_size);
+        duplicates
     }
 
     fn hash_files_parallel(
@@ -136,47 +681,130 @@ where
         file_groups: Vec<Vec<FileMetadata>>,
         config: &ScanConfig,
         is_partial: bool,
+        outcome: &mut HashingOutcome,
+        watch: Option<&FirstGroupWatch>,
     ) -> Result<Vec<Vec<FileMetadata>>> {
         let hasher = Arc::new(&self.hasher);
         let counter = Arc::new(AtomicUsize::new(0));
         let progress_ref = &self.progress;
+        let cancellation = &self.cancellation;
 
-        let hashed_groups: Vec<Vec<FileMetadata>> = file_groups
-            .into_par_iter()
-            .filter(|group| group.len() > 1)
-            .map(|group| {
-                let mut processed_files = Vec::new();
-                
-                for file in group {
-                    let hash_result = if is_partial {
-                        let adaptive_size = Self::calculate_adaptive_partial_hash_size(file.size, config.partial_hash_size);
-                        hasher.hash_partial(&file.path, adaptive_size, config.hash_algorithm)
-                    } else {
-                        hasher.hash_file(&file.path, config.hash_algorithm)
-                    };
+        // Files are flattened to (group, file) pairs so one huge size group still spreads over
+        // every thread, then regrouped in their original order
+        let file_groups: Vec<Vec<FileMetadata>> = file_groups.into_iter().filter(|group| group.len() > 1).collect();
+        let group_count = file_groups.len();
+        let mut pairs: Vec<(usize, Option<FileMetadata>)> = file_groups
+            .into_iter()
+            .enumerate()
+            .flat_map(|(group_id, group)| group.into_iter().map(move |file| (group_id, Some(file))))
+            .collect();
 
-                    match hash_result {
-                        Ok(hash) => {
-                            let updated_file = if is_partial {
-                                file.with_partial_hash(hash)
-                            } else {
-                                file.with_full_hash(hash)
-                            };
-                            processed_files.push(updated_file);
-                        }
-                        Err(_) => {
-                            // Skip files that can't be hashed
-                            continue;
+        // Two-level bloom filter fed as hashes stream in: the second level holds every
+        // (group, hash) key inserted at least twice. False positives only let a unique hash through
+        // to the exact grouping step, never drop a real pair.
+        let bloom = (config.bloom_prefilter && is_partial).then(|| {
+            Mutex::new((
+                BloomFilter::with_capacity(pairs.len(), 0.01),
+                BloomFilter::with_capacity(pairs.len() / 2 + 1, 0.01),
+            ))
+        });
+
+        // Hashes are filled in place, leaving `None` for files that were skipped, so hashing
+        // never holds a second copy of the candidate list
+        let skipped = Mutex::new(Vec::new());
+        let stopped = || cancellation.is_cancelled() || watch.is_some_and(FirstGroupWatch::is_found);
+        let hash_one = |(group_id, slot): &mut (usize, Option<FileMetadata>)| {
+            let Some(file) = slot.take() else {
+                return;
+            };
+            // Entries from a valid cache still carry the hashes of their unchanged file
+            let cached_hash = if is_partial { &file.partial_hash } else { &file.full_hash };
+            let hash_result = if let Some(hash) = cached_hash {
+                Ok(hash.clone())
+            } else if is_partial {
+                let adaptive_size = Self::calculate_adaptive_partial_hash_size(file.content_size(), config.partial_hash_size);
+                match config.partial_mode {
+                    PartialMode::Head => hasher.hash_partial(&file.path, file.content_offset(), adaptive_size, config.hash_algorithm),
+                    PartialMode::HeadTail => hasher.hash_head_tail(&file.path, file.content_offset(), adaptive_size, config.hash_algorithm),
+                }
+            } else {
+                hasher.hash_file(&file.path, file.content_offset(), config.hash_algorithm)
+            };
+
+            let count = counter.fetch_add(1, Ordering::SeqCst);
+            progress_ref.update(count as u64 + 1);
+
+            match hash_result {
+                Ok(hash) => {
+                    if let Some(bloom) = &bloom {
+                        let key = bloom_key(*group_id, &hash);
+                        let (seen, repeated) = &mut *bloom.lock().unwrap();
+                        if seen.insert(&key) {
+                            repeated.insert(&key);
                         }
                     }
-                    
-                    let count = counter.fetch_add(1, Ordering::SeqCst);
-                    progress_ref.update(count as u64 + 1);
+                    let updated_file = if is_partial {
+                        file.with_partial_hash(hash)
+                    } else {
+                        file.with_full_hash(hash)
+                    };
+                    if let Some(watch) = watch {
+                        watch.record(*group_id, &updated_file, config);
+                    }
+                    *slot = Some(updated_file);
                 }
-                
-                processed_files
-            })
-            .collect();
+                Err(e) => {
+                    // Files that can't be hashed are left out and reported with the results
+                    self.report_skipped(config, &file.path, &e);
+                    skipped.lock().unwrap().push((file.path, e.to_string()));
+                }
+            }
+        };
+        // Files still queued once the scan stops are dropped unhashed
+        if config.ordered {
+            // Cancellation only takes effect between fixed-size batches, so a stopped scan has
+            // always hashed the same path-ordered prefix, whatever the thread count
+            for batch in pairs.chunks_mut(ORDERED_BATCH_FILES) {
+                if stopped() {
+                    batch.iter_mut().for_each(|(_, slot)| *slot = None);
+                } else {
+                    batch.par_iter_mut().for_each(hash_one);
+                }
+            }
+        } else {
+            pairs.par_iter_mut().for_each(|pair| {
+                if stopped() {
+                    pair.1 = None;
+                } else {
+                    hash_one(pair);
+                }
+            });
+        }
+
+        outcome.skipped.extend(skipped.into_inner().unwrap());
+        if config.cache_out.is_some() {
+            outcome.hashed.extend(pairs.iter().filter_map(|(_, file)| file.clone()));
+        }
+
+        // Hashes the filter has only seen once can't form a group, so they're dropped before
+        // any grouping allocates for them
+        match bloom {
+            Some(bloom) => {
+                let (_, repeated) = bloom.into_inner().unwrap();
+                pairs.retain(|(group_id, file)| {
+                    file.as_ref()
+                        .and_then(|file| file.partial_hash.as_ref())
+                        .is_some_and(|hash| repeated.contains(&bloom_key(*group_id, hash)))
+                });
+                pairs.shrink_to_fit();
+            }
+            None => pairs.retain(|(_, file)| file.is_some()),
+        }
+
+        let mut hashed_groups: Vec<Vec<FileMetadata>> = vec![Vec::new(); group_count];
+        for (group_id, file) in pairs {
+            hashed_groups[group_id].extend(file);
+        }
 
         let mut results = Vec::new();
         for group in hashed_groups {
@@ -196,21 +824,669 @@ where
             }
         }
 
+        if config.ordered {
+            Self::sort_groups(&mut results);
+        }
         Ok(results)
     }
 
+    // Puts files and groups in path order, which fixes what each batch of an ordered scan
+    // holds. This only orders the work; reported groups follow `sort_by`.
+    fn sort_groups(groups: &mut [Vec<FileMetadata>]) {
+        for group in groups.iter_mut() {
+            group.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        groups.sort_by(|a, b| a.first().map(|f| &f.path).cmp(&b.first().map(|f| &f.path)));
+    }
+
+    // A size equal to a bracket's upper bound belongs to that bracket, so a file of exactly
+    // 4 KiB is hashed whole and one of exactly 64 KiB still gets the small-file sample. The
+    // 4 KiB - 64 KiB bracket samples 1 KiB, or `base_size` when that is smaller, so a small
+    // `--partial-hash-size` shrinks every bracket rather than all but this one.
     fn calculate_adaptive_partial_hash_size(file_size: u64, base_size: u64) -> u64 {
-        match file_size {
-            // For very small files (< 4KB), use the entire file
-            0..=4096 => file_size,
-            // For small files (4KB - 64KB), use 1KB 
-            4097..=65536 => 1024.min(file_size),
+        const WHOLE_FILE_MAX: u64 = 4 * 1024;
+        const SMALL_MAX: u64 = 64 * 1024;
+        const MEDIUM_MAX: u64 = 1024 * 1024;
+        const LARGE_MAX: u64 = 100 * 1024 * 1024;
+
+        let sample = if file_size <= WHOLE_FILE_MAX {
+            // Up to 4KB, use the entire file
+            return file_size;
+        } else if file_size <= SMALL_MAX {
+            // For small files (4KB - 64KB), use 1KB, capped by the base size
+            base_size.min(1024)
+        } else if file_size <= MEDIUM_MAX {
             // For medium files (64KB - 1MB), use the configured base size
-            65537..=1048576 => base_size.min(file_size),
-            // For large files (1MB - 100MB), use 16KB
-            1048577..=104857600 => (base_size * 2).min(file_size),
-            // For very large files (> 100MB), use 64KB for better discrimination
-            _ => (base_size * 8).min(file_size),
+            base_size
+        } else if file_size <= LARGE_MAX {
+            // For large files (1MB - 100MB), use twice the base size (16KB by default)
+            base_size.saturating_mul(2)
+        } else {
+            // For very large files (> 100MB), use 8x the base size (64KB by default)
+            base_size.saturating_mul(8)
+        };
+        sample.min(file_size)
+    }
+}
+
+// Bloom filter key for a hash within one size group, so equal hashes in different groups
+// don't count as repeats
+fn bloom_key(group_id: usize, hash: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(size_of::<u64>() + hash.len());
+    key.extend_from_slice(&(group_id as u64).to_le_bytes());
+    key.extend_from_slice(hash.as_bytes());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::{FileSystemAdapter, MultiAlgorithmHasher, ProgressBarAdapter, StreamingComparator};
+    use crate::domain::HashAlgorithm;
+    use std::fs;
+
+    // Records every path handed to the hasher, so tests can tell which files a cache spared
+    #[derive(Clone, Default)]
+    struct RecordingHasher {
+        inner: Arc<MultiAlgorithmHasher>,
+        hashed: Arc<Mutex<Vec<PathBuf>>>,
+    }
+
+    impl RecordingHasher {
+        // Every hash call in the order it was made, repeats included
+        fn calls(&self) -> Vec<PathBuf> {
+            self.hashed.lock().unwrap().clone()
+        }
+
+        fn take(&self) -> Vec<PathBuf> {
+            let mut hashed = std::mem::take(&mut *self.hashed.lock().unwrap());
+            hashed.sort();
+            hashed.dedup();
+            hashed
+        }
+    }
+
+    impl HashingPort for RecordingHasher {
+        fn hash_file(&self, path: &Path, skip: u64, algorithm: HashAlgorithm) -> Result<String> {
+            self.hashed.lock().unwrap().push(path.to_path_buf());
+            self.inner.hash_file(path, skip, algorithm)
+        }
+
+        fn hash_partial(&self, path: &Path, skip: u64, bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+            self.hashed.lock().unwrap().push(path.to_path_buf());
+            self.inner.hash_partial(path, skip, bytes, algorithm)
+        }
+
+        fn hash_bytes(&self, data: &[u8], algorithm: HashAlgorithm) -> Result<String> {
+            self.inner.hash_bytes(data, algorithm)
+        }
+    }
+
+    fn service() -> DuplicateFinderService<FileSystemAdapter, MultiAlgorithmHasher, ProgressBarAdapter> {
+        DuplicateFinderService::new(FileSystemAdapter::new(), MultiAlgorithmHasher::new(), ProgressBarAdapter::new_quiet())
+            .with_comparator(StreamingComparator::new())
+    }
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "same contents").unwrap();
+        fs::write(dir.path().join("b.txt"), "same contents").unwrap();
+        fs::write(dir.path().join("c.txt"), "diff contents").unwrap();
+        dir
+    }
+
+    #[test]
+    fn direct_comparison_is_off_by_default() {
+        let dir = fixture();
+        let config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        assert_eq!(config.compare_threshold, 0);
+
+        let results = service().find_duplicates(&config).unwrap();
+        assert_eq!(results.duplicates.len(), 1);
+        assert_eq!(results.duplicates[0].verification, VerificationLevel::FullHash);
+    }
+
+    #[test]
+    fn compared_sets_carry_a_digest_in_the_configured_algorithm() {
+        let dir = fixture();
+        let mut config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        config.compare_threshold = 8;
+        config.hash_algorithm = HashAlgorithm::Sha256;
+
+        let results = service().find_duplicates(&config).unwrap();
+        assert_eq!(results.duplicates.len(), 1);
+        let set = &results.duplicates[0];
+        let expected = MultiAlgorithmHasher::new()
+            .hash_bytes(b"same contents", HashAlgorithm::Sha256)
+            .unwrap();
+        assert_eq!(set.verification, VerificationLevel::ByteVerified);
+        assert_eq!(set.hash, expected);
+        assert!(set.files.iter().all(|f| f.full_hash.as_deref() == Some(expected.as_str())));
+    }
+
+    fn rescan_hashes_only_changed_files(trust_dir_mtimes: bool) {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache.json");
+        let root = dir.path().join("tree");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("a.txt"), "same contents").unwrap();
+        fs::write(root.join("b.txt"), "same contents").unwrap();
+
+        let mut config = ScanConfig::new()
+            .with_paths(vec![root.clone()])
+            .with_cache_file(cache)
+            .with_incremental(true);
+        config.trust_dir_mtimes = trust_dir_mtimes;
+        let hasher = RecordingHasher::default();
+        let service = DuplicateFinderService::new(FileSystemAdapter::new(), hasher.clone(), ProgressBarAdapter::new_quiet());
+
+        assert_eq!(service.find_duplicates(&config).unwrap().duplicates.len(), 1);
+        assert_eq!(hasher.take().len(), 2);
+
+        // Same size, new contents and mtime, and the directory itself is left untouched
+        fs::write(root.join("b.txt"), "diff contents").unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        fs::File::options().write(true).open(root.join("b.txt")).unwrap().set_modified(later).unwrap();
+
+        let results = service.find_duplicates(&config).unwrap();
+        assert!(results.duplicates.is_empty());
+        assert_eq!(hasher.take(), vec![root.canonicalize().unwrap().join("b.txt")]);
+    }
+
+    #[test]
+    fn incremental_scan_only_hashes_changed_files() {
+        rescan_hashes_only_changed_files(false);
+    }
+
+    #[test]
+    fn trusted_directories_still_rehash_changed_files() {
+        rescan_hashes_only_changed_files(true);
+    }
+
+    #[test]
+    fn byte_verification_needs_a_comparator() {
+        let dir = fixture();
+        let mut config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        config.verify_bytes = true;
+
+        let bare = DuplicateFinderService::new(FileSystemAdapter::new(), MultiAlgorithmHasher::new(), ProgressBarAdapter::new_quiet());
+        assert!(bare.find_duplicates(&config).is_err());
+
+        let results = service().find_duplicates(&config).unwrap();
+        assert_eq!(results.duplicates[0].verification, VerificationLevel::ByteVerified);
+    }
+
+    #[test]
+    fn cached_and_uncached_scans_report_the_same_paths() {
+        let dir = fixture();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let root = dir.path().join("sub").join("..");
+        let config = ScanConfig::new().with_paths(vec![root]);
+        let cached_config = ScanConfig { cache_out: Some(dir.path().join("sub").join("cache")), ..config.clone() };
+
+        let paths = |config: &ScanConfig| -> Vec<PathBuf> {
+            let results = service().find_duplicates(config).unwrap();
+            results.duplicates.iter().flat_map(|group| group.files.iter().map(|f| f.path.clone())).collect()
+        };
+        let uncached = paths(&config);
+        assert_eq!(uncached, paths(&cached_config));
+        let canonical = dir.path().canonicalize().unwrap();
+        assert_eq!(uncached, vec![canonical.join("a.txt"), canonical.join("b.txt")]);
+    }
+
+    #[test]
+    fn cancelled_token_stops_the_walk_and_every_pass() {
+        let dir = fixture();
+        fs::write(dir.path().join("prefix.txt"), "same").unwrap();
+        let config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]).with_find_prefixes(true);
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let walked = FileSystemAdapter::new().with_cancellation(token.clone()).scan_files(&config).unwrap();
+        assert!(walked.is_empty());
+
+        let files = FileSystemAdapter::new().scan_files(&config).unwrap();
+        let cancelled = service().with_cancellation(token);
+        assert!(cancelled.find_prefix_matches(&files, &config).is_empty());
+        assert!(!service().find_prefix_matches(&files, &config).is_empty());
+
+        let results = cancelled.find_duplicates(&config).unwrap();
+        assert!(results.cancelled);
+        assert!(results.duplicates.is_empty() && results.prefix_matches.is_empty());
+    }
+
+    #[test]
+    fn name_matching_groups_without_reading_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in [("a/x.txt", "hello"), ("b/x.txt", "world"), ("c/x.txt", "longer text"), ("d/y.txt", "hello")] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let groups = |match_by: MatchMode| -> Vec<Vec<PathBuf>> {
+            let config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]).with_match_by(match_by);
+            let hasher = RecordingHasher::default();
+            let results = DuplicateFinderService::new(FileSystemAdapter::new(), hasher.clone(), ProgressBarAdapter::new_quiet())
+                .find_duplicates(&config)
+                .unwrap();
+            assert!(hasher.take().is_empty());
+            assert_eq!(results.match_by, match_by);
+            results
+                .duplicates
+                .iter()
+                .map(|group| {
+                    assert_eq!(group.verification, VerificationLevel::Metadata);
+                    let mut names: Vec<PathBuf> = group.files.iter().map(|f| f.path.strip_prefix(dir.path()).unwrap().to_path_buf()).collect();
+                    names.sort();
+                    names
+                })
+                .collect()
+        };
+
+        let path = PathBuf::from;
+        assert_eq!(groups(MatchMode::NameSize), vec![vec![path("a/x.txt"), path("b/x.txt")]]);
+        assert_eq!(groups(MatchMode::Name), vec![vec![path("a/x.txt"), path("b/x.txt"), path("c/x.txt")]]);
+    }
+
+    #[test]
+    fn same_extension_only_splits_identical_content() {
+        let dir = fixture();
+        fs::write(dir.path().join("copy.bak"), "same contents").unwrap();
+        fs::write(dir.path().join("upper.TXT"), "same contents").unwrap();
+        let config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        let names = |config: &ScanConfig| -> Vec<Vec<String>> {
+            service()
+                .find_duplicates(config)
+                .unwrap()
+                .duplicates
+                .iter()
+                .map(|group| {
+                    let mut names: Vec<String> =
+                        group.files.iter().map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+                    names.sort();
+                    names
+                })
+                .collect()
+        };
+
+        assert_eq!(names(&config), [["a.txt", "b.txt", "copy.bak", "upper.TXT"]]);
+        // Extensions compare case-insensitively, and the lone `.bak` copy is dropped
+        let split = ScanConfig { same_extension_only: true, ..config };
+        assert_eq!(names(&split), [["a.txt", "b.txt", "upper.TXT"]]);
+    }
+
+    #[test]
+    fn cache_is_read_from_one_file_and_written_to_another() {
+        let dir = tempfile::tempdir().unwrap();
+        let (baseline, delta) = (dir.path().join("baseline.json"), dir.path().join("delta.json"));
+        let root = dir.path().join("tree");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("a.txt"), "same contents").unwrap();
+        fs::write(root.join("b.txt"), "same contents").unwrap();
+        let hasher = RecordingHasher::default();
+        let service = DuplicateFinderService::new(FileSystemAdapter::new(), hasher.clone(), ProgressBarAdapter::new_quiet());
+
+        let seed = ScanConfig::new().with_paths(vec![root.clone()]).with_cache_out(Some(baseline.clone()));
+        service.find_duplicates(&seed).unwrap();
+        assert_eq!(hasher.take().len(), 2);
+        let seeded = fs::read(&baseline).unwrap();
+
+        let config = seed.with_cache_in(Some(baseline.clone())).with_cache_out(Some(delta.clone())).with_incremental(true);
+        assert_eq!(service.find_duplicates(&config).unwrap().duplicates.len(), 1);
+        // Every hash came from the baseline, which is left as it was
+        assert!(hasher.take().is_empty());
+        assert_eq!(fs::read(&baseline).unwrap(), seeded);
+        let written = FileCacheAdapter::new().load_cache(&delta).unwrap().unwrap();
+        assert_eq!(written.files.len(), 2);
+    }
+
+    // Records the name of each thread that hashes, so tests can tell which pool did the work
+    #[derive(Clone, Default)]
+    struct ThreadRecordingHasher {
+        inner: Arc<MultiAlgorithmHasher>,
+        threads: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl HashingPort for ThreadRecordingHasher {
+        fn hash_file(&self, path: &Path, skip: u64, algorithm: HashAlgorithm) -> Result<String> {
+            self.threads.lock().unwrap().push(std::thread::current().name().map(str::to_string));
+            self.inner.hash_file(path, skip, algorithm)
+        }
+
+        fn hash_partial(&self, path: &Path, skip: u64, bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+            self.threads.lock().unwrap().push(std::thread::current().name().map(str::to_string));
+            self.inner.hash_partial(path, skip, bytes, algorithm)
+        }
+
+        fn hash_bytes(&self, data: &[u8], algorithm: HashAlgorithm) -> Result<String> {
+            self.inner.hash_bytes(data, algorithm)
+        }
+    }
+
+    #[test]
+    fn supplied_thread_pool_does_the_hashing() {
+        let dir = fixture();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .thread_name(|i| format!("embedder-{}", i))
+            .build()
+            .unwrap();
+        let hasher = ThreadRecordingHasher::default();
+        let service = DuplicateFinderService::new(FileSystemAdapter::new(), hasher.clone(), ProgressBarAdapter::new_quiet())
+            .with_thread_pool(Arc::new(pool));
+        // An explicit thread count is ignored in favour of the supplied pool
+        let config = ScanConfig { thread_count: Some(7), ..ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]) };
+
+        assert_eq!(service.find_duplicates(&config).unwrap().duplicates.len(), 1);
+        let threads = hasher.threads.lock().unwrap();
+        assert!(!threads.is_empty());
+        assert!(threads.iter().all(|name| name.as_deref().is_some_and(|name| name.starts_with("embedder-"))), "{:?}", threads);
+    }
+
+    #[test]
+    fn scans_with_different_thread_counts_agree() {
+        let dir = fixture();
+        fs::write(dir.path().join("d.bin"), "other pair").unwrap();
+        fs::write(dir.path().join("e.bin"), "other pair").unwrap();
+        let service = service();
+        let scan = |threads: usize| {
+            let config = ScanConfig { thread_count: Some(threads), ..ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]) };
+            let results = service.find_duplicates(&config).unwrap();
+            results
+                .duplicates
+                .iter()
+                .map(|group| (group.hash.clone(), group.files.iter().map(|f| f.path.clone()).collect::<Vec<_>>()))
+                .collect::<Vec<_>>()
+        };
+
+        // Each scan builds its own pool, so a second one in the same process still succeeds
+        let single = scan(1);
+        assert_eq!(single.len(), 2);
+        assert_eq!(single, scan(4));
+    }
+
+    #[test]
+    fn ordered_scan_hashes_in_path_order() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["d.txt", "a.txt", "c.txt", "b.txt", "f.bin", "e.bin"] {
+            let contents = if name.ends_with(".bin") { "other pair" } else { "same contents" };
+            fs::write(dir.path().join(name), contents).unwrap();
+        }
+        let mut config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        config.ordered = true;
+        config.thread_count = Some(1);
+
+        let hasher = RecordingHasher::default();
+        let service = DuplicateFinderService::new(FileSystemAdapter::new(), hasher.clone(), ProgressBarAdapter::new_quiet());
+        let results = service.find_duplicates(&config).unwrap();
+        assert_eq!(results.duplicates.len(), 2);
+
+        // Groups are taken by their first path and each group's files in path order
+        let expected: Vec<PathBuf> =
+            ["a.txt", "b.txt", "c.txt", "d.txt", "e.bin", "f.bin"].iter().map(|name| dir.path().join(name)).collect();
+        assert_eq!(hasher.calls()[..expected.len()], expected);
+    }
+
+    // Cancels the scan once it has been asked for `after` full hashes
+    struct CancellingHasher {
+        inner: MultiAlgorithmHasher,
+        token: CancellationToken,
+        after: usize,
+        full_hashes: AtomicUsize,
+    }
+
+    impl HashingPort for CancellingHasher {
+        fn hash_file(&self, path: &Path, skip: u64, algorithm: HashAlgorithm) -> Result<String> {
+            if self.full_hashes.fetch_add(1, Ordering::SeqCst) + 1 == self.after {
+                self.token.cancel();
+            }
+            self.inner.hash_file(path, skip, algorithm)
+        }
+
+        fn hash_partial(&self, path: &Path, skip: u64, bytes: u64, algorithm: HashAlgorithm) -> Result<String> {
+            self.inner.hash_partial(path, skip, bytes, algorithm)
+        }
+
+        fn hash_bytes(&self, data: &[u8], algorithm: HashAlgorithm) -> Result<String> {
+            self.inner.hash_bytes(data, algorithm)
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn cancelled_ordered_scan_reports_the_same_groups_at_any_thread_count() {
+        let dir = tempfile::tempdir().unwrap();
+        // 100 pairs of one size, each pair adjacent in path order
+        for i in 0..200 {
+            fs::write(dir.path().join(format!("{:03}.txt", i)), format!("pair{:03}", i / 2)).unwrap();
+        }
+        let scan = |threads: usize| -> Vec<Vec<PathBuf>> {
+            let token = CancellationToken::new();
+            let hasher = CancellingHasher { inner: MultiAlgorithmHasher::new(), token: token.clone(), after: 70, full_hashes: AtomicUsize::new(0) };
+            let service = DuplicateFinderService::new(FileSystemAdapter::new(), hasher, ProgressBarAdapter::new_quiet())
+                .with_cancellation(token);
+            let mut config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+            config.ordered = true;
+            config.thread_count = Some(threads);
+
+            let results = service.find_duplicates(&config).unwrap();
+            assert!(results.cancelled);
+            let mut groups: Vec<Vec<PathBuf>> =
+                results.duplicates.iter().map(|set| set.files.iter().map(|f| f.path.clone()).collect()).collect();
+            groups.iter_mut().for_each(|group| group.sort());
+            groups.sort();
+            groups
+        };
+
+        // The batch the cancellation lands in still finishes, so two batches of 64 files
+        let single = scan(1);
+        assert_eq!(single.len(), 64);
+        for threads in [2, 4, 8] {
+            assert_eq!(scan(threads), single, "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn bloom_prefilter_keeps_every_group() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..40 {
+            // Five pairs of duplicates among unique files, all of one size
+            let contents = if i % 4 == 0 { format!("dup{:03}", i / 8) } else { format!("one{:03}", i) };
+            fs::write(dir.path().join(format!("{:02}.txt", i)), contents).unwrap();
+        }
+        let mut config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        let plain = service().find_duplicates(&config).unwrap();
+        config.bloom_prefilter = true;
+        let filtered = service().find_duplicates(&config).unwrap();
+
+        let groups = |result: &ScanResult| {
+            let mut groups: Vec<Vec<PathBuf>> = result
+                .duplicates
+                .iter()
+                .map(|set| {
+                    let mut paths: Vec<PathBuf> = set.files.iter().map(|f| f.path.clone()).collect();
+                    paths.sort();
+                    paths
+                })
+                .collect();
+            groups.sort();
+            groups
+        };
+        assert_eq!(plain.duplicates.len(), 5);
+        assert_eq!(groups(&filtered), groups(&plain));
+    }
+
+    fn prefix_pairs(head_size: u64) -> Vec<(String, String)> {
+        let dir = tempfile::tempdir().unwrap();
+        let mut files = Vec::new();
+        for (name, contents) in [
+            ("a", "abc"),
+            ("b", "abcdef"),
+            ("c", "abcdefghijklmnop"),
+            ("d", "abd"),
+            ("e", "abdxxxxxxxxxxxxx"),
+            ("f", "x"),
+            ("g", "abcdefghijklmnopqrstuvwxyz"),
+            ("h", "abcdefghijklmnoX"),
+        ] {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            files.push(FileMetadata::new(path, contents.len() as u64, SystemTime::now()));
+        }
+        let mut config = ScanConfig::new();
+        config.partial_hash_size = head_size;
+
+        let name = |f: &FileMetadata| f.path.file_name().unwrap().to_string_lossy().into_owned();
+        let mut pairs: Vec<(String, String)> = service()
+            .find_prefix_matches(&files, &config)
+            .iter()
+            .map(|m| (name(&m.prefix), name(&m.container)))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn prefix_matches_are_found_whatever_the_head_size() {
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        let expected = vec![
+            pair("a", "b"),
+            pair("a", "c"),
+            pair("a", "g"),
+            pair("a", "h"),
+            pair("b", "c"),
+            pair("b", "g"),
+            pair("b", "h"),
+            pair("c", "g"),
+            pair("d", "e"),
+        ];
+        for head_size in [1, 2, 4, 5, 16, 8192] {
+            assert_eq!(prefix_pairs(head_size), expected, "head size {}", head_size);
+        }
+    }
+
+    // Lists files that vanished after the walk, so every hash attempt fails
+    struct VanishedFiles(Vec<FileMetadata>);
+
+    impl FileSystemPort for VanishedFiles {
+        fn scan_files(&self, _config: &ScanConfig) -> Result<Vec<FileMetadata>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[derive(Default)]
+    struct SkipRecorder(Mutex<Vec<PathBuf>>);
+
+    impl ProgressPort for SkipRecorder {
+        fn start(&self, _total: u64) {}
+        fn update(&self, _processed: u64) {}
+        fn finish(&self) {}
+
+        fn file_skipped(&self, path: Option<&Path>, _reason: &str) {
+            self.0.lock().unwrap().extend(path.map(Path::to_path_buf));
+        }
+    }
+
+    #[test]
+    fn skipped_files_reach_the_progress_port_only_when_verbose() {
+        let dir = tempfile::tempdir().unwrap();
+        let vanished: Vec<FileMetadata> = ["a", "b"]
+            .iter()
+            .map(|name| FileMetadata::new(dir.path().join(name), 10, SystemTime::now()))
+            .collect();
+
+        for verbose_errors in [false, true] {
+            let mut config = ScanConfig::new();
+            config.verbose_errors = verbose_errors;
+            let progress = Arc::new(SkipRecorder::default());
+            let service = DuplicateFinderService::new(VanishedFiles(vanished.clone()), MultiAlgorithmHasher::new(), progress.clone());
+
+            let results = service.find_duplicates(&config).unwrap();
+            assert_eq!(results.skipped.len(), 2);
+            let reported = progress.0.lock().unwrap().len();
+            assert_eq!(reported, if verbose_errors { 2 } else { 0 });
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_errors_are_reported_with_the_results() {
+        let dir = fixture();
+        std::os::unix::fs::symlink(dir.path().join("missing.txt"), dir.path().join("broken.txt")).unwrap();
+        let mut config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        config.follow_symlinks = true;
+
+        let results = service().find_duplicates(&config).unwrap();
+        assert_eq!(results.duplicates.len(), 1);
+        let skipped: Vec<&PathBuf> = results.skipped.iter().map(|(path, _)| path).collect();
+        assert_eq!(skipped, vec![&dir.path().join("broken.txt")]);
+    }
+
+    #[test]
+    fn direct_comparison_failures_are_reported_with_the_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let vanished: Vec<FileMetadata> = ["a", "b"]
+            .iter()
+            .map(|name| FileMetadata::new(dir.path().join(name), 10, SystemTime::now()))
+            .collect();
+        let mut config = ScanConfig::new();
+        config.compare_threshold = 8;
+
+        let service = DuplicateFinderService::new(VanishedFiles(vanished), MultiAlgorithmHasher::new(), ProgressBarAdapter::new_quiet())
+            .with_comparator(StreamingComparator::new());
+        let results = service.find_duplicates(&config).unwrap();
+        assert!(results.duplicates.is_empty());
+        assert_eq!(results.skipped.len(), 2);
+    }
+
+    #[test]
+    fn adaptive_partial_hash_size_brackets() {
+        const KIB: u64 = 1024;
+        const MIB: u64 = 1024 * KIB;
+        let size = DuplicateFinderService::<FileSystemAdapter, MultiAlgorithmHasher, ProgressBarAdapter>::calculate_adaptive_partial_hash_size;
+
+        // (file size, base size, expected sample), probing both sides of every bracket bound
+        let cases = [
+            (0, 8 * KIB, 0),
+            (4 * KIB, 8 * KIB, 4 * KIB),
+            (4 * KIB + 1, 8 * KIB, KIB),
+            (64 * KIB, 8 * KIB, KIB),
+            (64 * KIB + 1, 8 * KIB, 8 * KIB),
+            (MIB, 8 * KIB, 8 * KIB),
+            (MIB + 1, 8 * KIB, 16 * KIB),
+            (100 * MIB, 8 * KIB, 16 * KIB),
+            (100 * MIB + 1, 8 * KIB, 64 * KIB),
+            // A smaller base size shrinks every bracket but the whole-file one
+            (4 * KIB, 512, 4 * KIB),
+            (4 * KIB + 1, 512, 512),
+            (64 * KIB + 1, 512, 512),
+            (MIB + 1, 512, KIB),
+            (100 * MIB + 1, 512, 4 * KIB),
+            // A larger one never samples past the end of the file
+            (64 * KIB + 1, MIB, 64 * KIB + 1),
+            (MIB + 1, MIB, MIB + 1),
+            (100 * MIB + 1, 64 * MIB, 100 * MIB + 1),
+            (u64::MAX, u64::MAX, u64::MAX),
+        ];
+        for (file_size, base_size, expected) in cases {
+            assert_eq!(size(file_size, base_size), expected, "file {} base {}", file_size, base_size);
+        }
+    }
+
+    #[test]
+    fn ignored_bom_is_skipped_by_hashing_and_comparison() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("bom.txt"), "\u{feff}same contents").unwrap();
+        fs::write(dir.path().join("plain.txt"), "same contents").unwrap();
+        let mut config = ScanConfig::new().with_paths(vec![dir.path().to_path_buf()]);
+        assert!(service().find_duplicates(&config).unwrap().duplicates.is_empty());
+
+        config.ignore_bom = true;
+        for (compare_threshold, verify_bytes) in [(0, false), (0, true), (8, false)] {
+            config.compare_threshold = compare_threshold;
+            config.verify_bytes = verify_bytes;
+            let results = service().find_duplicates(&config).unwrap();
+            assert_eq!(results.duplicates.len(), 1);
+            assert_eq!(results.duplicates[0].files.len(), 2);
+        }
+    }
+}
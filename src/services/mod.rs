@@ -1,3 +1,7 @@
+pub mod bloom;
+#[cfg(feature = "chunk-dedup")]
+pub mod chunking;
 pub mod duplicate_finder;
+pub mod storage;
 
 pub use duplicate_finder::DuplicateFinderService;
\ No newline at end of file
@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+// Hashing threads when any scanned path sits on a spinning disk; more would just make it seek
+const ROTATIONAL_THREADS: usize = 2;
+// Multiplier over the core count for solid-state storage, where hashing is mostly I/O-wait
+const SOLID_STATE_THREADS_PER_CORE: usize = 2;
+
+// Picks a hashing pool size from the storage behind `paths`, or None to keep rayon's default
+// when the media type can't be detected
+pub fn auto_thread_count(paths: &[PathBuf]) -> Option<usize> {
+    let rotational = paths.iter().map(|path| is_rotational(path)).collect::<Option<Vec<bool>>>()?;
+    if rotational.iter().any(|&r| r) {
+        Some(ROTATIONAL_THREADS)
+    } else {
+        let cores = std::thread::available_parallelism().ok()?.get();
+        Some(cores * SOLID_STATE_THREADS_PER_CORE)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_rotational(path: &std::path::Path) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = std::fs::metadata(path).ok()?.dev();
+    // glibc's encoding of major/minor numbers in a dev_t
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+
+    // Partitions have no queue of their own; it lives on the parent disk one level up
+    let device = PathBuf::from(format!("/sys/dev/block/{}:{}", major, minor));
+    [device.join("queue/rotational"), device.join("../queue/rotational")]
+        .iter()
+        .find_map(|flag| std::fs::read_to_string(flag).ok())
+        .map(|flag| flag.trim() == "1")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_rotational(_path: &std::path::Path) -> Option<bool> {
+    None
+}
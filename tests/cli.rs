@@ -0,0 +1,25 @@
+use std::fs;
+use std::process::Command;
+
+// Progress and skip notices go to stderr, so piped results are only ever the report itself
+#[cfg(unix)]
+#[test]
+fn progress_goes_to_stderr_and_stdout_stays_parseable() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "same contents").unwrap();
+    fs::write(dir.path().join("b.txt"), "same contents").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("missing"), dir.path().join("dangling")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rdupe"))
+        .arg(dir.path())
+        .args(["--format", "json", "--follow-symlinks", "--verbose-errors"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains(['\r', '\x1b']));
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(report["duplicates"].as_array().unwrap().len(), 1);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("skipped"));
+}